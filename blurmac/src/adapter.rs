@@ -31,11 +31,9 @@ impl BluetoothAdapter {
         let delegate = bm::delegate();
         let manager = cb::centralmanager(delegate);
         let adapter = BluetoothAdapter { manager: manager, delegate: delegate };
-
-        // NOTE: start discovery at once, servo leaves close to no time to do a proper discovery
-        // in a BluetoothDiscoverySession
-        adapter.start_discovery().unwrap();
-
+        // NOTE: discovery used to start at once, to leave servo time for a proper discovery.
+        // It is now started by the first BluetoothDiscoverySession, so devices only show up
+        // while a session is started, as on the other platforms.
         Ok(adapter)
     }
 
@@ -104,9 +102,9 @@ impl BluetoothAdapter {
         Ok(v)
     }
 
-    // Was in BluetoothDiscoverySession
+    // Used by BluetoothDiscoverySession
 
-    fn start_discovery(&self) -> Result<(), Box<dyn Error>> {
+    pub(crate) fn start_discovery(&self) -> Result<(), Box<dyn Error>> {
         trace!("BluetoothAdapter::start_discovery");
         let options = ns::mutabledictionary();
         // NOTE: If duplicates are not allowed then a peripheral will not show up again once
@@ -116,7 +114,7 @@ impl BluetoothAdapter {
         Ok(())
     }
 
-    fn stop_discovery(&self) -> Result<(), Box<dyn Error>> {
+    pub(crate) fn stop_discovery(&self) -> Result<(), Box<dyn Error>> {
         trace!("BluetoothAdapter::stop_discovery");
         cb::centralmanager_stopscan(self.manager);
         Ok(())
@@ -203,7 +201,7 @@ impl BluetoothAdapter {
 impl Drop for BluetoothAdapter {
     fn drop(&mut self) {
         trace!("BluetoothAdapter::drop");
-        // NOTE: stop discovery only here instead of in BluetoothDiscoverySession
+        // NOTE: a discovery session may still be scanning
         self.stop_discovery().unwrap();
     }
 }
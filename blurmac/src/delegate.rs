@@ -106,6 +106,8 @@ pub mod bm {
             ns::mutabledictionary_setobject_forkey(data, cbuuids_nsarray, nsx::string_from_str(PERIPHERALDATA_UUIDSKEY));
         }
 
+        ns::mutabledictionary_setobject_forkey(data, adv_data, nsx::string_from_str(PERIPHERALDATA_ADVERTISEMENTDATAKEY));
+
         if ns::dictionary_objectforkey(data, nsx::string_from_str(PERIPHERALDATA_EVENTSKEY)) == nil {
             ns::mutabledictionary_setobject_forkey(data, ns::mutabledictionary(), nsx::string_from_str(PERIPHERALDATA_EVENTSKEY));
         }
@@ -235,6 +237,7 @@ pub mod bm {
     pub const PERIPHERALDATA_RSSIKEY: &'static str = "rssi";
     pub const PERIPHERALDATA_UUIDSKEY: &'static str = "uuids";
     pub const PERIPHERALDATA_EVENTSKEY: &'static str = "events";
+    pub const PERIPHERALDATA_ADVERTISEMENTDATAKEY: &'static str = "advertisementdata";
 
     pub const PERIPHERALEVENT_SERVICESDISCOVEREDKEY: &'static str = "services";
     pub const PERIPHERALEVENT_INCLUDEDSERVICESDISCOVEREDKEYSUFFIX: &'static str = ":includes";
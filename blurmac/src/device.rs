@@ -6,6 +6,7 @@
 // according to those terms.
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::sync::Arc;
 
@@ -100,6 +101,80 @@ impl BluetoothDevice {
 
     }
 
+    // CoreBluetooth only hands out the decoded advertisement, so the AD structures are rebuilt
+    // from the parts it keeps.
+    pub fn get_advertisement_data(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        trace!("BluetoothDevice::get_advertisement_data");
+        if self.peripheral == nil {
+            return Err(Box::from(NO_PERIPHERAL_FOUND));
+        }
+
+        let data = bmx::peripheraldata(self.adapter.delegate, self.peripheral)?;
+        let adv_data = ns::dictionary_objectforkey(data, nsx::string_from_str(bm::PERIPHERALDATA_ADVERTISEMENTDATAKEY));
+        let mut v = vec!();
+        if adv_data == nil {
+            return Ok(v);
+        }
+        let mut push = |ad_type: u8, bytes: &[u8]| {
+            if let Ok(length) = u8::try_from(bytes.len() + 1) {
+                v.push(length);
+                v.push(ad_type);
+                v.extend_from_slice(bytes);
+            }
+        };
+        // CBUUID data is big-endian, AD structures are little-endian.
+        let uuid_bytes = |cbuuid: *mut Object| {
+            let mut bytes = nsx::data_to_vec(cb::uuid_data(cbuuid));
+            bytes.reverse();
+            bytes
+        };
+
+        let name = ns::dictionary_objectforkey(adv_data, unsafe { cb::ADVERTISEMENTDATALOCALNAMEKEY });
+        if name != nil {
+            push(0x09, nsx::string_to_string(name).as_bytes());
+        }
+        let tx_power = ns::dictionary_objectforkey(adv_data, unsafe { cb::ADVERTISEMENTDATATXPOWERLEVELKEY });
+        if tx_power != nil {
+            push(0x0A, &[ns::number_longlongvalue(tx_power) as i8 as u8]);
+        }
+        let cbuuids_nsarray = ns::dictionary_objectforkey(adv_data, unsafe { cb::ADVERTISEMENTDATASERVICEUUIDSKEY });
+        if cbuuids_nsarray != nil {
+            let mut lists: [(u8, Vec<u8>); 3] = [(0x03, vec!()), (0x05, vec!()), (0x07, vec!())];
+            for i in 0..ns::array_count(cbuuids_nsarray) {
+                let bytes = uuid_bytes(ns::array_objectatindex(cbuuids_nsarray, i));
+                match bytes.len() {
+                    2 => lists[0].1.extend(bytes),
+                    4 => lists[1].1.extend(bytes),
+                    _ => lists[2].1.extend(bytes),
+                }
+            }
+            for &(ad_type, ref list) in lists.iter().filter(|&&(_, ref list)| !list.is_empty()) {
+                push(ad_type, list);
+            }
+        }
+        let service_data = ns::dictionary_objectforkey(adv_data, unsafe { cb::ADVERTISEMENTDATASERVICEDATAKEY });
+        if service_data != nil {
+            let cbuuids = ns::dictionary_allkeys(service_data);
+            for i in 0..ns::array_count(cbuuids) {
+                let cbuuid = ns::array_objectatindex(cbuuids, i);
+                let mut bytes = uuid_bytes(cbuuid);
+                let ad_type = match bytes.len() {
+                    2 => 0x16,
+                    4 => 0x20,
+                    _ => 0x21,
+                };
+                bytes.extend(nsx::data_to_vec(ns::dictionary_objectforkey(service_data, cbuuid)));
+                push(ad_type, &bytes);
+            }
+        }
+        let manufacturer_data = ns::dictionary_objectforkey(adv_data, unsafe { cb::ADVERTISEMENTDATAMANUFACTURERDATAKEY });
+        if manufacturer_data != nil {
+            push(0xFF, &nsx::data_to_vec(manufacturer_data));
+        }
+        debug!("BluetoothDevice::get_advertisement_data -> {:?}", v);
+        Ok(v)
+    }
+
     pub fn connect(&self) -> Result<(), Box<dyn Error>> {
         trace!("BluetoothDevice::connect");
         if self.peripheral == nil {
@@ -139,7 +214,7 @@ impl BluetoothDevice {
 
         let events = bmx::peripheralevents(self.adapter.delegate, self.peripheral)?;
         let key = nsx::string_from_str(bm::PERIPHERALEVENT_SERVICESDISCOVEREDKEY);
-        wait::wait_or_timeout(|| { ns::dictionary_objectforkey(events, key) != nil })?;
+        wait::wait_or_timeout(wait::timeout(), || { ns::dictionary_objectforkey(events, key) != nil })?;
 
         let mut v = vec!();
         let services = cb::peripheral_services(self.peripheral);
@@ -151,6 +226,20 @@ impl BluetoothDevice {
         Ok(v)
     }
 
+    // CoreBluetooth negotiates the ATT MTU by itself, and only tells how long a write without
+    // response can be, which is the MTU minus the 3 bytes of the ATT header.
+    pub fn get_mtu(&self) -> Result<u16, Box<dyn Error>> {
+        trace!("BluetoothDevice::get_mtu");
+        if self.peripheral == nil {
+            return Err(Box::from(NO_PERIPHERAL_FOUND));
+        }
+
+        let length = cb::peripheral_maximumwritevaluelengthfortype(self.peripheral, cb::CHARACTERISTICWRITETYPE_WITHOUTRESPONSE);
+        let mtu = u16::try_from(length + 3).unwrap_or(u16::MAX);
+        debug!("BluetoothDevice::get_mtu -> {}", mtu);
+        Ok(mtu)
+    }
+
     // Not supported
 
     pub fn get_rssi(&self) -> Result<i16, Box<dyn Error>> {
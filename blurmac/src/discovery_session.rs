@@ -13,26 +13,24 @@ use adapter::BluetoothAdapter;
 
 #[derive(Clone, Debug)]
 pub struct BluetoothDiscoverySession {
-    // pub(crate) adapter: Arc<BluetoothAdapter>,
+    pub(crate) adapter: Arc<BluetoothAdapter>,
 }
 
 impl BluetoothDiscoverySession {
-    pub fn create_session(_adapter: Arc<BluetoothAdapter>) -> Result<BluetoothDiscoverySession, Box<dyn Error>> {
+    pub fn create_session(adapter: Arc<BluetoothAdapter>) -> Result<BluetoothDiscoverySession, Box<dyn Error>> {
         trace!("BluetoothDiscoverySession::create_session");
         Ok(BluetoothDiscoverySession {
-            // adapter: adapter.clone()
+            adapter: adapter.clone()
         })
     }
 
     pub fn start_discovery(&self) -> Result<(), Box<dyn Error>> {
         trace!("BluetoothDiscoverySession::start_discovery");
-        // NOTE: discovery is started by BluetoothAdapter::new to allow devices to pop up
-        Ok(())
+        self.adapter.start_discovery()
     }
 
     pub fn stop_discovery(&self) -> Result<(), Box<dyn Error>> {
         trace!("BluetoothDiscoverySession::stop_discovery");
-        // NOTE: discovery is only stopped when BluetoothAdapter is dropped
-        Ok(())
+        self.adapter.stop_discovery()
     }
 }
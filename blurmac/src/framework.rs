@@ -49,6 +49,13 @@ pub mod ns {
         }
     }
 
+    pub fn number_longlongvalue(nsnumber: *mut Object) -> i64 {
+        unsafe {
+            let value: i64 = msg_send![nsnumber, longLongValue];
+            value
+        }
+    }
+
     // NSString
 
     pub fn string(cstring: *const c_char) -> *mut Object /* NSString* */ {
@@ -231,6 +238,10 @@ pub mod cb {
         #[link(name = "CoreBluetooth", kind = "framework")]
         extern {
             pub static CBAdvertisementDataServiceUUIDsKey: *mut Object;
+            pub static CBAdvertisementDataLocalNameKey: *mut Object;
+            pub static CBAdvertisementDataManufacturerDataKey: *mut Object;
+            pub static CBAdvertisementDataServiceDataKey: *mut Object;
+            pub static CBAdvertisementDataTxPowerLevelKey: *mut Object;
 
             pub static CBCentralManagerScanOptionAllowDuplicatesKey: *mut Object;
         }
@@ -295,6 +306,13 @@ pub mod cb {
         }
     }
 
+    pub fn peripheral_maximumwritevaluelengthfortype(cbperipheral: *mut Object, writetype: c_int) -> c_uint {
+        unsafe {
+            let length: c_uint = msg_send![cbperipheral, maximumWriteValueLengthForType:writetype];
+            length
+        }
+    }
+
     pub fn peripheral_setdelegate(cbperipheral: *mut Object, delegate: *mut Object /* CBPeripheralDelegate* */) {
         unsafe {
             let () = msg_send![cbperipheral, setDelegate:delegate];
@@ -332,12 +350,17 @@ pub mod cb {
         }
     }
 
-    pub fn peripheral_writevalue_forcharacteristic(cbperipheral: *mut Object, value: *mut Object /* NSData* */, characteristic: *mut Object /* CBCharacteristic* */) {
+    pub fn peripheral_writevalue_forcharacteristic(cbperipheral: *mut Object, value: *mut Object /* NSData* */, characteristic: *mut Object /* CBCharacteristic* */, writetype: c_int) {
         unsafe {
-            let () = msg_send![cbperipheral, writeValue:value forCharacteristic:characteristic type:0]; // CBCharacteristicWriteWithResponse from CBPeripheral.h
+            let () = msg_send![cbperipheral, writeValue:value forCharacteristic:characteristic type:writetype];
         }
     }
 
+    // CBCharacteristicWriteType from CBPeripheral.h
+
+    pub const CHARACTERISTICWRITETYPE_WITHRESPONSE : c_int    = 0; // CBCharacteristicWriteWithResponse
+    pub const CHARACTERISTICWRITETYPE_WITHOUTRESPONSE : c_int = 1; // CBCharacteristicWriteWithoutResponse
+
     pub fn peripheral_setnotifyvalue_forcharacteristic(cbperipheral: *mut Object, value: BOOL, characteristic: *mut Object /* CBCharacteristic* */) {
         unsafe {
             let () = msg_send![cbperipheral, setNotifyValue:value forCharacteristic:characteristic];
@@ -428,6 +451,13 @@ pub mod cb {
         }
     }
 
+    pub fn uuid_data(cbuuid: *mut Object) -> *mut Object /* NSData* */ {
+        unsafe {
+            let data: *mut Object = msg_send![cbuuid, data];
+            data
+        }
+    }
+
     // CBCentralManagerScanOption...Key
 
     pub use self::link::CBCentralManagerScanOptionAllowDuplicatesKey as CENTRALMANAGERSCANOPTIONALLOWDUPLICATESKEY;
@@ -435,4 +465,8 @@ pub mod cb {
     // CBAdvertisementData...Key
 
     pub use self::link::CBAdvertisementDataServiceUUIDsKey as ADVERTISEMENTDATASERVICEUUIDSKEY;
+    pub use self::link::CBAdvertisementDataLocalNameKey as ADVERTISEMENTDATALOCALNAMEKEY;
+    pub use self::link::CBAdvertisementDataManufacturerDataKey as ADVERTISEMENTDATAMANUFACTURERDATAKEY;
+    pub use self::link::CBAdvertisementDataServiceDataKey as ADVERTISEMENTDATASERVICEDATAKEY;
+    pub use self::link::CBAdvertisementDataTxPowerLevelKey as ADVERTISEMENTDATATXPOWERLEVELKEY;
 }
@@ -98,7 +98,7 @@ impl BluetoothGATTCharacteristic {
 
         cb::peripheral_readvalueforcharacteristic(self.service.device.peripheral, self.characteristic);
 
-        wait::wait_or_timeout(|| {
+        wait::wait_or_timeout(wait::timeout(), || {
             let nsnumber = ns::dictionary_objectforkey(events, key);
             (nsnumber != nil) && (ns::number_unsignedlonglongvalue(nsnumber) >= t)
         })?;
@@ -116,9 +116,12 @@ impl BluetoothGATTCharacteristic {
         let key = bmx::valuewrittenkey(self.characteristic);
         let t = wait::get_timestamp();
 
-        cb::peripheral_writevalue_forcharacteristic(self.service.device.peripheral, ns::data(values.as_ptr(), values.len() as c_uint), self.characteristic);
+        cb::peripheral_writevalue_forcharacteristic(self.service.device.peripheral,
+                                                    ns::data(values.as_ptr(), values.len() as c_uint),
+                                                    self.characteristic,
+                                                    cb::CHARACTERISTICWRITETYPE_WITHRESPONSE);
 
-        wait::wait_or_timeout(|| {
+        wait::wait_or_timeout(wait::timeout(), || {
             let nsnumber = ns::dictionary_objectforkey(events, key);
             (nsnumber != nil) && (ns::number_unsignedlonglongvalue(nsnumber) >= t)
         })?;
@@ -126,6 +129,21 @@ impl BluetoothGATTCharacteristic {
         Ok(())
     }
 
+    // CoreBluetooth reports no completion for writes without response, and signs them if the
+    // characteristic has the authenticated signed writes property and the link is not encrypted.
+    pub fn write_value_without_response(&self, values: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        trace!("BluetoothGATTCharacteristic::write_value_without_response");
+        if self.characteristic == nil {
+            return Err(Box::from(NO_CHARACTERISTIC_FOUND));
+        }
+
+        cb::peripheral_writevalue_forcharacteristic(self.service.device.peripheral,
+                                                    ns::data(values.as_ptr(), values.len() as c_uint),
+                                                    self.characteristic,
+                                                    cb::CHARACTERISTICWRITETYPE_WITHOUTRESPONSE);
+        Ok(())
+    }
+
     pub fn is_notifying(&self) -> Result<bool, Box<dyn Error>> {
         trace!("BluetoothGATTCharacteristic::is_notifying");
         if self.characteristic == nil {
@@ -53,6 +53,11 @@ impl BluetoothGATTService {
         nil
     }
 
+    pub fn get_device(&self) -> Arc<BluetoothDevice> {
+        trace!("BluetoothGATTService::get_device");
+        self.device.clone()
+    }
+
     pub fn get_id(&self) -> String {
         trace!("BluetoothGATTService::get_id");
         self.get_uuid().unwrap_or(String::new())
@@ -90,7 +95,7 @@ impl BluetoothGATTService {
 
         let events = bmx::peripheralevents(self.device.adapter.delegate, self.device.peripheral)?;
         let key = bmx::includedservicesdiscoveredkey(self.service);
-        wait::wait_or_timeout(|| { ns::dictionary_objectforkey(events, key) != nil })?;
+        wait::wait_or_timeout(wait::timeout(), || { ns::dictionary_objectforkey(events, key) != nil })?;
 
         let mut v = vec!();
         let includes = cb::service_includedservices(self.service);
@@ -108,7 +113,7 @@ impl BluetoothGATTService {
 
         let events = bmx::peripheralevents(self.device.adapter.delegate, self.device.peripheral)?;
         let key = bmx::characteristicsdiscoveredkey(self.service);
-        wait::wait_or_timeout(|| { ns::dictionary_objectforkey(events, key) != nil })?;
+        wait::wait_or_timeout(wait::timeout(), || { ns::dictionary_objectforkey(events, key) != nil })?;
 
         let mut v = vec!();
         let chars = cb::service_characteristics(self.service);
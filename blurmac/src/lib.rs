@@ -26,3 +26,5 @@ pub use device::BluetoothDevice;
 pub use gatt_service::BluetoothGATTService;
 pub use gatt_characteristic::BluetoothGATTCharacteristic;
 pub use gatt_descriptor::BluetoothGATTDescriptor;
+pub use utils::{NOT_SUPPORTED_ERROR, NO_PERIPHERAL_FOUND, NO_SERVICE_FOUND, NO_CHARACTERISTIC_FOUND, TIMEOUT_ERROR};
+pub use utils::wait::{with_timeout, DEFAULT_TIMEOUT};
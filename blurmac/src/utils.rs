@@ -5,8 +5,11 @@
 // This file may not be copied, modified, or distributed except
 // according to those terms.
 
+use std::cell::Cell;
+use std::cmp;
 use std::error::Error;
 use std::ffi::{CStr, CString};
+use std::slice;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time;
 use std::thread;
@@ -20,6 +23,7 @@ pub const NOT_SUPPORTED_ERROR: &'static str = "Error! Not supported by blurmac!"
 pub const NO_PERIPHERAL_FOUND: &'static str = "Error! No peripheral found!";
 pub const NO_SERVICE_FOUND: &'static str = "Error! No service found!";
 pub const NO_CHARACTERISTIC_FOUND: &'static str = "Error! No characteristic found!";
+pub const TIMEOUT_ERROR: &'static str = "timeout";
 
 pub mod nsx {
     use super::*;
@@ -35,6 +39,14 @@ pub mod nsx {
         let cstring = CString::new(string).unwrap();
         ns::string(cstring.as_ptr())
     }
+
+    pub fn data_to_vec(nsdata: *mut Object) -> Vec<u8> {
+        let length = ns::data_length(nsdata);
+        if length == 0 {
+            return vec!();
+        }
+        unsafe { slice::from_raw_parts(ns::data_bytes(nsdata), length as usize).to_vec() }
+    }
 }
 
 
@@ -89,16 +101,42 @@ pub mod wait {
         ns::number_withunsignedlonglong(get_timestamp())
     }
 
-    pub fn wait_or_timeout<F>(mut f: F) -> Result<(), Box<dyn Error>>
+    /// How long the operations wait for CoreBluetooth when no timeout was given.
+    pub const DEFAULT_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+    // How often the waits check whether CoreBluetooth has answered.
+    const POLL_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+    thread_local! {
+        static TIMEOUT: Cell<Option<time::Duration>> = Cell::new(None);
+    }
+
+    /// Runs `f`, whose operations wait at most `timeout` for CoreBluetooth, or
+    /// `DEFAULT_TIMEOUT` if it is `None`.
+    pub fn with_timeout<T, F>(timeout: Option<time::Duration>, f: F) -> T
+        where F: FnOnce() -> T {
+        let previous = TIMEOUT.with(|current| current.replace(timeout));
+        let result = f();
+        TIMEOUT.with(|current| current.set(previous));
+        result
+    }
+
+    /// The timeout of the operations of the current thread, see `with_timeout`.
+    pub fn timeout() -> time::Duration {
+        TIMEOUT.with(|current| current.get()).unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    pub fn wait_or_timeout<F>(timeout: time::Duration, mut f: F) -> Result<(), Box<dyn Error>>
         where F: FnMut() -> bool {
 
         let now = time::Instant::now();
 
         while !f() {
-            thread::sleep(time::Duration::from_secs(1));
-            if now.elapsed().as_secs() > 30 {
-                return Err(Box::from("timeout"));
+            let elapsed = now.elapsed();
+            if elapsed >= timeout {
+                return Err(Box::from(TIMEOUT_ERROR));
             }
+            thread::sleep(cmp::min(POLL_INTERVAL, timeout - elapsed));
         }
         Ok(())
     }
@@ -1,3 +1,7 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
 #[cfg(all(target_os = "linux", feature = "bluetooth"))]
 use blurz::bluetooth_adapter::BluetoothAdapter as BluetoothAdapterBluez;
 #[cfg(all(target_os = "android", feature = "bluetooth"))]
@@ -10,338 +14,469 @@ use blurmac::BluetoothAdapter as BluetoothAdapterMac;
 use super::empty::EmptyAdapter as BluetoothAdapterEmpty;
 #[cfg(feature = "bluetooth-test")]
 use blurmock::fake_adapter::FakeBluetoothAdapter;
-
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_discovery_session::BluetoothDiscoverySession as BluetoothDiscoverySessionBluez;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_discovery_session::DiscoverySession as BluetoothDiscoverySessionAndroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothDiscoverySession as BluetoothDiscoverySessionMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothDiscoverySession as BluetoothDiscoverySessionEmpty;
 #[cfg(feature = "bluetooth-test")]
-use blurmock::fake_discovery_session::FakeBluetoothDiscoverySession;
+use blurmock::fake_device::FakeBluetoothDevice;
 
+use std::cell::RefCell;
+use std::cmp;
+use std::sync::{Arc, Weak};
+use std::thread::{self, LocalKey};
+use std::time::{Duration, Instant};
+
+use super::address::BdAddr;
+use super::backend::BluetoothAdapterBackend;
+use super::bluetooth::BluetoothDevice;
+use super::bluetooth::BluetoothDiscoverySession;
+use super::class::ClassOfDevice;
+use super::device_id::{DeviceId, VendorIdSource};
+use super::discovery::{DiscoveryFilter, DiscoverySessions};
+use super::error::BluetoothError;
+use super::event::AdapterEventStream;
+use super::future::{OperationTimeouts, TimeoutSettings};
+use super::pairing::{IoCapability, PairingAgent};
+#[cfg(feature = "bluetooth-test")]
+use super::beacon::Beacon;
+#[cfg(feature = "bluetooth-test")]
+use super::event::AdapterEvent;
+use super::uuid::BluetoothUuid;
 #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_device::BluetoothDevice as BluetoothDeviceBluez;
+use super::bluez;
 #[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_device::Device as BluetoothDeviceAndroid;
+use super::android;
 #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothDevice as BluetoothDeviceMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothDevice as BluetoothDeviceEmpty;
+use super::mac;
 #[cfg(feature = "bluetooth-test")]
-use blurmock::fake_device::FakeBluetoothDevice;
+use super::mock;
 
-use super::macros::get_inner_and_call;
-#[cfg(feature = "bluetooth-test")]
-use super::macros::get_inner_and_call_test_func;
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
-use std::sync::Arc;
-use std::error::Error;
+// The state shared by every handle on an adapter, keyed by `state_key`. Handles are not `Send`,
+// so the handles on an adapter all live on the thread that registered its state.
+type SharedStates<T> = RefCell<Vec<(String, Weak<T>)>>;
 
-use super::bluetooth::BluetoothDevice;
-use super::bluetooth::BluetoothDiscoverySession;
+thread_local! {
+    static DISCOVERY_SESSIONS: SharedStates<DiscoverySessions> = const { RefCell::new(Vec::new()) };
+    static TIMEOUTS: SharedStates<TimeoutSettings> = const { RefCell::new(Vec::new()) };
+}
 
-#[derive(Clone, Debug)]
-pub enum BluetoothAdapter {
-    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    Bluez(Arc<BluetoothAdapterBluez>),
-    #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    Android(Arc<BluetoothAdapterAndroid>),
-    #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    Mac(Arc<BluetoothAdapterMac>),
-    #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                  all(target_os = "android", feature = "bluetooth"),
-                  all(target_os = "macos", feature = "bluetooth"))))]
-    Empty(Arc<BluetoothAdapterEmpty>),
+fn shared_state<T: Default>(states: &'static LocalKey<SharedStates<T>>, key: &str) -> Arc<T> {
+    states.with(|states| {
+        let mut states = states.borrow_mut();
+        states.retain(|(_, state)| state.strong_count() > 0);
+        if let Some(state) = states.iter().find(|(state_key, _)| state_key == key).and_then(|(_, state)| state.upgrade()) {
+            return state;
+        }
+        let state = Arc::default();
+        states.push((key.to_owned(), Arc::downgrade(&state)));
+        state
+    })
+}
+
+// Identifies the adapter a backend object is a handle on. Mock adapters may share an id, so
+// they are told apart by identity instead.
+fn state_key(backend: &dyn BluetoothAdapterBackend) -> String {
     #[cfg(feature = "bluetooth-test")]
-    Mock(Arc<FakeBluetoothAdapter>)
+    {
+        if let Some(fake_adapter) = backend.as_mock() {
+            return format!("mock {:p}", Arc::as_ptr(fake_adapter));
+        }
+    }
+    backend.get_id()
+}
+
+#[derive(Clone, Debug)]
+pub struct BluetoothAdapter {
+    backend: Arc<dyn BluetoothAdapterBackend>,
+    discovery_sessions: Arc<DiscoverySessions>,
+    timeouts: Arc<TimeoutSettings>,
 }
 
 impl BluetoothAdapter {
     #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    pub fn new() -> Result<BluetoothAdapter, Box<dyn Error>> {
-        let bluez_adapter = BluetoothAdapterBluez::init()?;
-        Ok(Self::Bluez(Arc::new(bluez_adapter)))
+    pub fn new() -> Result<BluetoothAdapter, BluetoothError> {
+        let bluez_adapter = BluetoothAdapterBluez::init().map_err(bluez::map_error)?;
+        Ok(Self::from_backend(Arc::new(Arc::new(bluez_adapter))))
     }
 
     #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    pub fn new() -> Result<BluetoothAdapter, Box<dyn Error>> {
-        let blurdroid_adapter = BluetoothAdapterAndroid::get_adapter()?;
-        Ok(Self::Android(Arc::new(blurdroid_adapter)))
+    pub fn new() -> Result<BluetoothAdapter, BluetoothError> {
+        let blurdroid_adapter = BluetoothAdapterAndroid::get_adapter().map_err(android::map_error)?;
+        Ok(Self::from_backend(Arc::new(Arc::new(blurdroid_adapter))))
     }
 
     #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    pub fn new() -> Result<BluetoothAdapter, Box<dyn Error>> {
-        let mac_adapter = BluetoothAdapterMac::init()?;
-        Ok(Self::Mac(Arc::new(mac_adapter)))
+    pub fn new() -> Result<BluetoothAdapter, BluetoothError> {
+        let mac_adapter = BluetoothAdapterMac::init().map_err(mac::map_error)?;
+        Ok(Self::from_backend(Arc::new(Arc::new(mac_adapter))))
     }
 
     #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
                   all(target_os = "android", feature = "bluetooth"),
                   all(target_os = "macos", feature = "bluetooth"))))]
-    pub fn new() -> Result<BluetoothAdapter, Box<dyn Error>> {
+    pub fn new() -> Result<BluetoothAdapter, BluetoothError> {
         let adapter = BluetoothAdapterEmpty::init()?;
-        Ok(Self::Empty(Arc::new(adapter)))
+        Ok(Self::from_backend(Arc::new(adapter)))
+    }
+
+    /// Returns every adapter of the system, ordered by id.
+    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
+    pub fn list() -> Result<Vec<BluetoothAdapter>, BluetoothError> {
+        let adapter_paths = bluez::get_adapter_paths().map_err(bluez::map_error)?;
+        adapter_paths.into_iter().map(|adapter_path| {
+            let bluez_adapter = BluetoothAdapterBluez::create_adapter(adapter_path).map_err(bluez::map_error)?;
+            Ok(Self::from_backend(Arc::new(Arc::new(bluez_adapter))))
+        }).collect()
     }
 
+    /// Returns every adapter of the system.
+    // NOTE: Android and macOS only give access to the default adapter.
+    #[cfg(not(all(target_os = "linux", feature = "bluetooth")))]
+    pub fn list() -> Result<Vec<BluetoothAdapter>, BluetoothError> {
+        Ok(vec![Self::new()?])
+    }
+
+    pub fn with_id(id: String) -> Result<BluetoothAdapter, BluetoothError> {
+        Self::list()?.into_iter().find(|adapter| adapter.get_id() == id).ok_or(BluetoothError::AdapterNotFound)
+    }
+
+    /// Creates a mock adapter, which `list_mock` returns as long as it is alive.
     #[cfg(feature = "bluetooth-test")]
-    pub fn new_mock() -> Result<BluetoothAdapter, Box<dyn Error>> {
-        Ok(Self::Mock(FakeBluetoothAdapter::new_empty()))
+    pub fn new_mock() -> Result<BluetoothAdapter, BluetoothError> {
+        let fake_adapter = FakeBluetoothAdapter::new_empty();
+        mock::register_adapter(&fake_adapter);
+        Ok(Self::from_backend(Arc::new(fake_adapter)))
     }
 
-    pub fn get_id(&self) -> String {
-        get_inner_and_call!(self, BluetoothAdapter, get_id)
+    /// Returns the live mock adapters created on the current thread, in their creation order.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn list_mock() -> Result<Vec<BluetoothAdapter>, BluetoothError> {
+        Ok(mock::get_adapters().into_iter().map(|fake_adapter| Self::from_backend(Arc::new(fake_adapter))).collect())
     }
 
-    pub fn get_devices(&self)-> Result<Vec<BluetoothDevice>, Box<dyn Error>> {
-        match self {
-            #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-            BluetoothAdapter::Bluez(inner) => {
-                let device_list = inner.get_device_list()?;
-                Ok(device_list.into_iter().map(|device| BluetoothDevice::Bluez(BluetoothDeviceBluez::new_empty(self.0.clone(), device))).collect())
-            }
-            #[cfg(all(target_os = "android", feature = "bluetooth"))]
-            BluetoothAdapter::Android(inner) =>  {
-                let device_list = inner.get_device_list()?;
-                Ok(device_list.into_iter().map(|device| BluetoothDevice::Android(BluetoothDeviceAndroid::new_empty(self.0.clone(), device))).collect())
-            }
-            #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-            BluetoothAdapter::Mac(inner) => {
-                let device_list = inner.get_device_list()?;
-                Ok(device_list.into_iter().map(|device|  BluetoothDevice::Mac(Arc::new(BluetoothDeviceMac::new(inner.clone(),device)))).collect())
+    #[cfg(feature = "bluetooth-test")]
+    pub fn with_mock_id(id: String) -> Result<BluetoothAdapter, BluetoothError> {
+        Self::list_mock()?.into_iter().find(|adapter| adapter.get_id() == id).ok_or(BluetoothError::AdapterNotFound)
+    }
 
-            }
-            #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                        all(target_os = "android", feature = "bluetooth"),
-                        all(target_os = "macos", feature = "bluetooth"))))]
-            BluetoothAdapter::Empty(inner) => {
-                let device_list = inner.get_device_list()?;
-                Ok(device_list.into_iter().map(|device| BluetoothDevice::Empty(Arc::new(BluetoothDeviceEmpty::new(device)))).collect())
-            }
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothAdapter::Mock(inner) => {
-                let device_list = inner.get_device_list()?;
-                Ok(device_list.into_iter().map(|device| BluetoothDevice::Mock(FakeBluetoothDevice::new_empty(inner.clone(), device))).collect())
-            }
+    /// Handles on the same adapter share its discovery sessions and its operation timeouts.
+    pub fn from_backend(backend: Arc<dyn BluetoothAdapterBackend>) -> BluetoothAdapter {
+        let key = state_key(&*backend);
+        BluetoothAdapter {
+            backend,
+            discovery_sessions: shared_state(&DISCOVERY_SESSIONS, &key),
+            timeouts: shared_state(&TIMEOUTS, &key),
         }
     }
 
-    pub fn get_device(&self, address: String) -> Result<Option<BluetoothDevice>, Box<dyn Error>> {
+    pub(crate) fn backend(&self) -> &dyn BluetoothAdapterBackend {
+        &*self.backend
+    }
+
+    pub(crate) fn discovery_sessions(&self) -> &DiscoverySessions {
+        &self.discovery_sessions
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn mock(&self) -> Result<&Arc<FakeBluetoothAdapter>, BluetoothError> {
+        self.backend.as_mock().ok_or(BluetoothError::NotSupported)
+    }
+
+    pub fn get_id(&self) -> String {
+        self.backend.get_id()
+    }
+
+    pub fn get_devices(&self)-> Result<Vec<BluetoothDevice>, BluetoothError> {
+        let devices = self.backend.get_devices()?;
+        Ok(devices.into_iter().map(|device| BluetoothDevice::new(device, self.timeouts.clone())).collect())
+    }
+
+    pub fn get_bonded_devices(&self) -> Result<Vec<BluetoothDevice>, BluetoothError> {
+        let devices = self.backend.get_bonded_devices()?;
+        Ok(devices.into_iter().map(|device| BluetoothDevice::new(device, self.timeouts.clone())).collect())
+    }
+
+    /// Forgets the device and its bond. The device is reported again if it is discovered later.
+    pub fn remove_device(&self, device: &BluetoothDevice) -> Result<(), BluetoothError> {
+        self.backend.remove_device(device.get_id())
+    }
+
+    pub fn get_device(&self, address: String) -> Result<Option<BluetoothDevice>, BluetoothError> {
+        // NOTE: Not every backend reports a BD_ADDR (e.g. CoreBluetooth only exposes an identifier),
+        // so fall back to comparing the raw strings when the address does not parse.
+        let bd_addr = address.parse::<BdAddr>().ok();
         let devices = self.get_devices()?;
         for device in devices {
-            if device.get_address()? == address {
+            let device_address = device.get_address()?;
+            let matches = match bd_addr {
+                Some(bd_addr) => device_address.parse::<BdAddr>().ok() == Some(bd_addr),
+                None => device_address == address,
+            };
+            if matches {
                 return Ok(Some(device));
             }
         }
         Ok(None)
     }
 
-    pub fn create_mock_device(&self, _device: String) -> Result<BluetoothDevice, Box<dyn Error>> {
-        match self {
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothAdapter::Mock(inner) => {
-                Ok(BluetoothDevice::Mock(FakeBluetoothDevice::new_empty(inner.clone(), _device)))
-            }
-            _ => {
-                Err(Box::from("Error! Test functions are not supported on real devices!"))
+    pub fn create_mock_device(&self, _device: String) -> Result<BluetoothDevice, BluetoothError> {
+        #[cfg(feature = "bluetooth-test")]
+        {
+            if let Some(fake_adapter) = self.backend.as_mock() {
+                let is_new = fake_adapter.get_device(_device.clone()).is_err();
+                let fake_device = FakeBluetoothDevice::new_empty(fake_adapter.clone(), _device.clone());
+                if is_new {
+                    mock::emit_event(fake_adapter, AdapterEvent::DeviceDiscovered(_device));
+                }
+                return Ok(BluetoothDevice::new(Arc::new(fake_device), self.timeouts.clone()));
             }
         }
+        Err(BluetoothError::NotSupported)
     }
 
-    pub fn create_discovery_session(&self)-> Result<BluetoothDiscoverySession, Box<dyn Error>> {
-        let discovery_session = match self {
-            #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-            BluetoothAdapter::Bluez(inner) => {
-                BluetoothDiscoverySession::Bluez(Arc::new(BluetoothDiscoverySessionBluez::create_session(inner.get_id())?));
-            }
-            #[cfg(all(target_os = "android", feature = "bluetooth"))]
-            BluetoothAdapter::Android(inner) => {
-                BluetoothDiscoverySession::Android(Arc::new(BluetoothDiscoverySessionAndroid::create_session(inner.clone())?))
-            }
-            #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-            BluetoothAdapter::Mac(_) => {
-                BluetoothDiscoverySession::Mac(Arc::new(BluetoothDiscoverySessionMac{}))
-            }
-            #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                        all(target_os = "android", feature = "bluetooth"),
-                        all(target_os = "macos", feature = "bluetooth"))))]
-            BluetoothAdapter::Empty(_) => {
-                BluetoothDiscoverySession::Empty(Arc::new(BluetoothDiscoverySessionEmpty{}))
-            }
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothAdapter::Mock(inner) => {
-                BluetoothDiscoverySession::Mock(Arc::new(FakeBluetoothDiscoverySession::create_session(inner.clone())?))
-            }
-        };
-        Ok(discovery_session)
+    /// Creates a mock device advertising the beacon.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn create_mock_beacon(&self, device: String, beacon: &Beacon) -> Result<BluetoothDevice, BluetoothError> {
+        let advertisement_data = beacon.to_advertisement_data()?;
+        let device = self.create_mock_device(device)?;
+        device.set_advertisement_data(advertisement_data)?;
+        Ok(device)
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    pub fn remove_mock_device(&self, device: String) -> Result<(), BluetoothError> {
+        self.mock()?;
+        self.backend.remove_device(device)
     }
 
-    pub fn get_address(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_address)
+    pub fn subscribe_events(&self) -> Result<AdapterEventStream, BluetoothError> {
+        self.backend.subscribe_events()
     }
 
-    pub fn get_name(&self)-> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_name)
+    pub fn create_discovery_session(&self)-> Result<BluetoothDiscoverySession, BluetoothError> {
+        self.create_filtered_discovery_session(DiscoveryFilter::default())
     }
 
-    pub fn get_alias(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_alias)
+    /// The filter applies to the devices returned by the session's `get_devices`. While several
+    /// sessions are started, the adapter scans for the devices passing any of their filters.
+    pub fn create_filtered_discovery_session(&self, filter: DiscoveryFilter)
+                                             -> Result<BluetoothDiscoverySession, BluetoothError> {
+        Ok(BluetoothDiscoverySession::new(self.clone(), filter))
     }
 
-    pub fn get_class(&self)-> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_class)
+    pub fn get_address(&self) -> Result<String, BluetoothError> {
+        self.backend.get_address()
     }
 
-    pub fn is_powered(&self)-> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, is_powered)
+    pub fn get_name(&self)-> Result<String, BluetoothError> {
+        self.backend.get_name()
     }
 
-    pub fn is_discoverable(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, is_discoverable)
+    pub fn get_alias(&self) -> Result<String, BluetoothError> {
+        self.backend.get_alias()
     }
 
-    pub fn is_pairable(&self)-> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, is_pairable)
+    pub fn set_alias(&self, alias: String) -> Result<(), BluetoothError> {
+        self.backend.set_alias(alias)
     }
 
-    pub fn get_pairable_timeout(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_pairable_timeout)
+    pub fn get_class(&self)-> Result<ClassOfDevice, BluetoothError> {
+        self.backend.get_class().map(ClassOfDevice::from)
     }
 
-    pub fn get_discoverable_timeout(&self)-> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_discoverable_timeout)
+    pub fn is_powered(&self)-> Result<bool, BluetoothError> {
+        self.backend.is_powered()
     }
 
-    pub fn is_discovering(&self)-> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, is_discovering)
+    pub fn set_powered(&self, powered: bool) -> Result<(), BluetoothError> {
+        self.backend.set_powered(powered)
     }
 
-    pub fn get_uuids(&self)-> Result<Vec<String>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_uuids)
+    pub fn is_discoverable(&self) -> Result<bool, BluetoothError> {
+        self.backend.is_discoverable()
     }
 
-    pub fn get_vendor_id_source(&self)-> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_vendor_id_source)
+    pub fn set_discoverable(&self, discoverable: bool) -> Result<(), BluetoothError> {
+        self.backend.set_discoverable(discoverable)
     }
 
-    pub fn get_vendor_id(&self)-> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_vendor_id)
+    pub fn is_pairable(&self)-> Result<bool, BluetoothError> {
+        self.backend.is_pairable()
     }
 
-    pub fn get_product_id(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_product_id)
+    pub fn set_pairable(&self, pairable: bool) -> Result<(), BluetoothError> {
+        self.backend.set_pairable(pairable)
     }
 
-    pub fn get_device_id(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_device_id)
+    pub fn get_pairable_timeout(&self) -> Result<u32, BluetoothError> {
+        self.backend.get_pairable_timeout()
     }
 
-    pub fn get_modalias(&self) -> Result<(String, u32, u32, u32), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothAdapter, get_modalias)
+    pub fn set_pairable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        self.backend.set_pairable_timeout(timeout)
     }
 
-    #[cfg(feature = "bluetooth-test")]
-    pub fn set_id(&self, id: String)-> Result<(), Box<dyn Error>> {
-        match self {
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothAdapter::Mock(inner) => Ok(inner.set_id(id)),
-            _ => Err(Box::from("Error! Test functions are not supported on real devices!")),
+    pub fn get_discoverable_timeout(&self)-> Result<u32, BluetoothError> {
+        self.backend.get_discoverable_timeout()
+    }
+
+    pub fn set_discoverable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        self.backend.set_discoverable_timeout(timeout)
+    }
+
+    /// Answers the authentication requests of `pair()` with `agent`, which replaces the agent
+    /// registered before. `capability` is what the agent can display and take as input.
+    ///
+    /// On Linux, BlueZ makes the agent the default one of the system, so there is a single agent
+    /// for every adapter: it answers their pairings, and registering or unregistering an agent
+    /// on any adapter replaces or removes it.
+    pub fn register_pairing_agent(&self,
+                                  agent: Arc<dyn PairingAgent>,
+                                  capability: IoCapability)
+                                  -> Result<(), BluetoothError> {
+        self.backend.register_pairing_agent(agent, capability)
+    }
+
+    pub fn unregister_pairing_agent(&self) -> Result<(), BluetoothError> {
+        self.backend.unregister_pairing_agent()
+    }
+
+    pub fn get_operation_timeouts(&self) -> OperationTimeouts {
+        self.timeouts.get()
+    }
+
+    /// Sets the default timeouts of the blocking operations on the devices of the adapter,
+    /// including the ones already obtained from it, and on every handle on the adapter. An
+    /// operation can still be given its own timeouts with `with_operation_timeouts`, e.g.
+    /// `device.with_operation_timeouts(timeouts).connect()`, or with `BluetoothFuture::wait`.
+    pub fn set_operation_timeouts(&self, timeouts: OperationTimeouts) {
+        self.timeouts.set(timeouts);
+    }
+
+    /// Polls `condition` until it holds, e.g. `|adapter| adapter.is_powered()` to confirm that
+    /// `set_powered(true)` took effect, since backends may apply settings asynchronously.
+    /// Fails with `Timeout` if it does not hold in time.
+    pub fn wait_until<F>(&self, timeout: Duration, condition: F) -> Result<(), BluetoothError>
+        where F: Fn(&BluetoothAdapter) -> Result<bool, BluetoothError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if condition(self)? {
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(BluetoothError::Timeout);
+            }
+            thread::sleep(cmp::min(WAIT_POLL_INTERVAL, deadline - now));
         }
     }
 
-    #[cfg(feature = "bluetooth-test")]
-    pub fn set_address(&self, address: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_address, address)
+    pub fn is_discovering(&self)-> Result<bool, BluetoothError> {
+        self.backend.is_discovering()
     }
 
-    #[cfg(feature = "bluetooth-test")]
-    pub fn set_name(&self, name: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_name, name)
+    pub fn get_uuids(&self)-> Result<Vec<BluetoothUuid>, BluetoothError> {
+        self.backend.get_uuids()
     }
 
-    #[cfg(feature = "bluetooth-test")]
-    pub fn set_alias(&self, alias: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_alias, alias)
+    pub fn get_vendor_id_source(&self) -> Result<VendorIdSource, BluetoothError> {
+        Ok(self.get_modalias()?.source)
     }
 
-    #[cfg(feature = "bluetooth-test")]
-    pub fn set_class(&self, class: u32) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_class, class)
+    pub fn get_vendor_id(&self) -> Result<u16, BluetoothError> {
+        Ok(self.get_modalias()?.vendor_id)
+    }
+
+    pub fn get_product_id(&self) -> Result<u16, BluetoothError> {
+        Ok(self.get_modalias()?.product_id)
+    }
+
+    /// The release number of the product, `DeviceId::version`.
+    pub fn get_device_id(&self) -> Result<u16, BluetoothError> {
+        Ok(self.get_modalias()?.version)
+    }
+
+    /// Fails with `NotSupported` if there is no Device ID.
+    pub fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        self.backend.get_modalias()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_powered(&self, powered: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_powered, powered)
+    pub fn set_id(&self, id: String)-> Result<(), BluetoothError> {
+        self.mock()?.set_id(id);
+        Ok(())
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn is_present(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, is_present)
+    pub fn set_address(&self, address: String) -> Result<(), BluetoothError> {
+        self.mock()?.set_address(address).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_present(&self, present: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_present, present)
+    pub fn set_name(&self, name: String) -> Result<(), BluetoothError> {
+        self.mock()?.set_name(name).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_discoverable(&self, discoverable: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_discoverable, discoverable)
+    pub fn set_class(&self, class: ClassOfDevice) -> Result<(), BluetoothError> {
+        self.mock()?.set_class(class.into()).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_pairable(&self, pairable: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_pairable, pairable)
+    pub fn is_present(&self) -> Result<bool, BluetoothError> {
+        self.mock()?.is_present().map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_pairable_timeout(&self, timeout: u32) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_pairable_timeout, timeout)
+    pub fn set_present(&self, present: bool) -> Result<(), BluetoothError> {
+        self.mock()?.set_present(present).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_can_start_discovery(&self, can_start_discovery: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_can_start_discovery, can_start_discovery)
+    pub fn set_can_start_discovery(&self, can_start_discovery: bool) -> Result<(), BluetoothError> {
+        self.mock()?.set_can_start_discovery(can_start_discovery).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_discoverable_timeout(&self, timeout: u32) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_discoverable_timeout, timeout)
+    pub fn set_discovering(&self, discovering: bool) -> Result<(), BluetoothError> {
+        let fake_adapter = self.mock()?;
+        let was_discovering = FakeBluetoothAdapter::is_discovering(fake_adapter).map_err(mock::map_error)?;
+        fake_adapter.set_discovering(discovering).map_err(mock::map_error)?;
+        if discovering != was_discovering {
+            mock::emit_event(fake_adapter, AdapterEvent::DiscoveringChanged(discovering));
+        }
+        Ok(())
     }
 
+    /// The filter the mock adapter scans with, i.e. the merge of the filters of its started
+    /// discovery sessions, or `None` while it does not scan.
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_discovering(&self, discovering: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_discovering, discovering)
+    pub fn get_discovery_filter(&self) -> Result<Option<DiscoveryFilter>, BluetoothError> {
+        Ok(mock::get_discovery_filter(self.mock()?))
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_can_stop_discovery(&self, can_stop_discovery: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_can_stop_discovery, can_stop_discovery)
+    pub fn set_can_stop_discovery(&self, can_stop_discovery: bool) -> Result<(), BluetoothError> {
+        self.mock()?.set_can_stop_discovery(can_stop_discovery).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_uuids(&self, uuids: Vec<String>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_uuids, uuids)
+    pub fn set_uuids(&self, uuids: Vec<BluetoothUuid>) -> Result<(), BluetoothError> {
+        self.mock()?.set_uuids(uuids.iter().map(BluetoothUuid::to_string).collect()).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_modalias(&self, modalias: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_modalias, modalias)
+    pub fn set_modalias(&self, modalias: DeviceId) -> Result<(), BluetoothError> {
+        mock::set_adapter_modalias(self.mock()?, modalias);
+        Ok(())
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn get_ad_datas(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, get_ad_datas)
+    pub fn get_ad_datas(&self) -> Result<Vec<String>, BluetoothError> {
+        self.mock()?.get_ad_datas().map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_ad_datas(&self, ad_datas: Vec<String>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothAdapter, set_ad_datas, ad_datas)
+    pub fn set_ad_datas(&self, ad_datas: Vec<String>) -> Result<(), BluetoothError> {
+        self.mock()?.set_ad_datas(ad_datas).map_err(mock::map_error)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,137 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::error::BluetoothError;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The address type of an LE device (Bluetooth Core Specification, Vol 6, Part B, 1.3).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AddressType {
+    Public,
+    RandomStatic,
+    ResolvablePrivate,
+    NonResolvablePrivate,
+}
+
+impl AddressType {
+    /// Private addresses are rotated, so they do not identify a device across connections.
+    pub fn is_private(&self) -> bool {
+        match *self {
+            AddressType::ResolvablePrivate | AddressType::NonResolvablePrivate => true,
+            AddressType::Public | AddressType::RandomStatic => false,
+        }
+    }
+}
+
+/// A 48-bit Bluetooth device address, most significant byte first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BdAddr([u8; 6]);
+
+impl BdAddr {
+    pub fn new(address: [u8; 6]) -> BdAddr {
+        BdAddr(address)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 6] {
+        self.0
+    }
+
+    /// Classifies the address assuming it is a random address, based on its two most
+    /// significant bits. Returns `None` for the reserved `0b10` prefix.
+    pub fn random_address_type(&self) -> Option<AddressType> {
+        match self.0[0] >> 6 {
+            0b11 => Some(AddressType::RandomStatic),
+            0b01 => Some(AddressType::ResolvablePrivate),
+            0b00 => Some(AddressType::NonResolvablePrivate),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BdAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+               self.0[0], self.0[1], self.0[2], self.0[3], self.0[4], self.0[5])
+    }
+}
+
+impl FromStr for BdAddr {
+    type Err = BluetoothError;
+
+    /// Parses the colon separated form, e.g. `"01:2A:00:4d:00:04"`, in any case.
+    fn from_str(address: &str) -> Result<BdAddr, BluetoothError> {
+        let invalid = || BluetoothError::InvalidAddress(address.to_owned());
+        let mut bytes = [0u8; 6];
+        let mut parts = address.split(':');
+        for byte in bytes.iter_mut() {
+            let part = parts.next().ok_or_else(invalid)?;
+            if part.len() != 2 || !part.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            *byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(BdAddr(bytes))
+    }
+}
+
+impl From<[u8; 6]> for BdAddr {
+    fn from(address: [u8; 6]) -> BdAddr {
+        BdAddr(address)
+    }
+}
+
+impl From<BdAddr> for String {
+    fn from(address: BdAddr) -> String {
+        address.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddressType, BdAddr};
+
+    #[test]
+    fn parsing_ignores_case_and_display_is_upper_case() {
+        let address: BdAddr = "01:2a:00:4D:0f:F4".parse().unwrap();
+        assert_eq!(address.to_bytes(), [0x01, 0x2A, 0x00, 0x4D, 0x0F, 0xF4]);
+        assert_eq!(address, "01:2A:00:4D:0F:F4".parse().unwrap());
+        assert_eq!(address.to_string(), "01:2A:00:4D:0F:F4");
+        assert_eq!(String::from(BdAddr::from([0xab; 6])), "AB:AB:AB:AB:AB:AB");
+    }
+
+    #[test]
+    fn malformed_addresses_are_rejected() {
+        for address in &["", "01:2A:00:4D:0F", "01:2A:00:4D:0F:F4:00", "01-2A-00-4D-0F-F4",
+                         "1:2A:00:4D:0F:F4", "001:2A:00:4D:0F:F4", "0G:2A:00:4D:0F:F4",
+                         "+1:2A:00:4D:0F:F4", "01:2A:00:4D:0F:F4:"] {
+            assert!(address.parse::<BdAddr>().is_err(), "{}", address);
+        }
+    }
+
+    #[test]
+    fn random_address_type_follows_the_two_most_significant_bits() {
+        let with_first = |byte| BdAddr::new([byte, 0, 0, 0, 0, 0]);
+        assert_eq!(with_first(0xC0).random_address_type(), Some(AddressType::RandomStatic));
+        assert_eq!(with_first(0xFF).random_address_type(), Some(AddressType::RandomStatic));
+        assert_eq!(with_first(0x40).random_address_type(), Some(AddressType::ResolvablePrivate));
+        assert_eq!(with_first(0x7F).random_address_type(), Some(AddressType::ResolvablePrivate));
+        assert_eq!(with_first(0x00).random_address_type(), Some(AddressType::NonResolvablePrivate));
+        assert_eq!(with_first(0x3F).random_address_type(), Some(AddressType::NonResolvablePrivate));
+        assert_eq!(with_first(0x80).random_address_type(), None);
+        assert_eq!(with_first(0xBF).random_address_type(), None);
+    }
+
+    #[test]
+    fn only_resolvable_and_non_resolvable_addresses_are_private() {
+        assert!(!AddressType::Public.is_private());
+        assert!(!AddressType::RandomStatic.is_private());
+        assert!(AddressType::ResolvablePrivate.is_private());
+        assert!(AddressType::NonResolvablePrivate.is_private());
+    }
+}
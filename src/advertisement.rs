@@ -0,0 +1,326 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::appearance::Appearance;
+use super::class::ClassOfDevice;
+use super::error::BluetoothError;
+use super::uuid::BluetoothUuid;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+// AD types (Bluetooth Assigned Numbers, 2.3).
+pub const AD_TYPE_FLAGS: u8 = 0x01;
+pub const AD_TYPE_INCOMPLETE_UUIDS_16: u8 = 0x02;
+pub const AD_TYPE_COMPLETE_UUIDS_16: u8 = 0x03;
+pub const AD_TYPE_INCOMPLETE_UUIDS_32: u8 = 0x04;
+pub const AD_TYPE_COMPLETE_UUIDS_32: u8 = 0x05;
+pub const AD_TYPE_INCOMPLETE_UUIDS_128: u8 = 0x06;
+pub const AD_TYPE_COMPLETE_UUIDS_128: u8 = 0x07;
+pub const AD_TYPE_SHORTENED_LOCAL_NAME: u8 = 0x08;
+pub const AD_TYPE_COMPLETE_LOCAL_NAME: u8 = 0x09;
+pub const AD_TYPE_TX_POWER_LEVEL: u8 = 0x0A;
+pub const AD_TYPE_CLASS_OF_DEVICE: u8 = 0x0D;
+pub const AD_TYPE_SOLICITED_UUIDS_16: u8 = 0x14;
+pub const AD_TYPE_SOLICITED_UUIDS_128: u8 = 0x15;
+pub const AD_TYPE_SERVICE_DATA_16: u8 = 0x16;
+pub const AD_TYPE_APPEARANCE: u8 = 0x19;
+pub const AD_TYPE_LE_ROLE: u8 = 0x1C;
+pub const AD_TYPE_SOLICITED_UUIDS_32: u8 = 0x1F;
+pub const AD_TYPE_SERVICE_DATA_32: u8 = 0x20;
+pub const AD_TYPE_SERVICE_DATA_128: u8 = 0x21;
+pub const AD_TYPE_URI: u8 = 0x24;
+pub const AD_TYPE_MANUFACTURER_DATA: u8 = 0xFF;
+
+/// The LE roles a device supports, and the one it prefers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LeRole {
+    PeripheralOnly,
+    CentralOnly,
+    PeripheralPreferred,
+    CentralPreferred,
+}
+
+/// An AD structure, as found in advertising data, scan responses and EIR data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdStructure {
+    pub ad_type: u8,
+    pub data: Vec<u8>,
+}
+
+/// A URI from advertising data, whose scheme is coded on its first byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdvertisedUri {
+    pub scheme_code: u8,
+    /// The URI after its scheme name.
+    pub rest: String,
+}
+
+impl AdvertisedUri {
+    /// The scheme name, with its colon, if the code is one of the known schemes.
+    pub fn scheme(&self) -> Option<&'static str> {
+        uri_scheme(self.scheme_code)
+    }
+
+    /// The whole URI, if its scheme is known.
+    pub fn to_uri(&self) -> Option<String> {
+        Some(format!("{}{}", self.scheme()?, self.rest))
+    }
+}
+
+/// Advertising or EIR data (Bluetooth Core Specification, Vol 3, Part C, 11), along with what
+/// it says about the device. Structures that cannot be decoded are only kept in `structures`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AdvertisementData {
+    pub raw: Vec<u8>,
+    pub structures: Vec<AdStructure>,
+    pub flags: Option<u8>,
+    /// The complete local name, or else the shortened one.
+    pub local_name: Option<String>,
+    pub is_name_complete: bool,
+    /// The advertised services, from the complete and the incomplete lists.
+    pub service_uuids: Vec<BluetoothUuid>,
+    pub solicited_service_uuids: Vec<BluetoothUuid>,
+    pub service_data: HashMap<BluetoothUuid, Vec<u8>>,
+    pub manufacturer_data: HashMap<u16, Vec<u8>>,
+    /// The transmitted power level, in dBm.
+    pub tx_power: Option<i8>,
+    pub appearance: Option<Appearance>,
+    pub class_of_device: Option<ClassOfDevice>,
+    /// The URIs, kept along with their scheme code even when the scheme is unknown.
+    pub uris: Vec<AdvertisedUri>,
+    pub le_role: Option<LeRole>,
+}
+
+// The UUIDs of a list, each `width` bytes long and little-endian.
+fn uuids(data: &[u8], width: usize) -> Option<Vec<BluetoothUuid>> {
+    let chunks = data.chunks_exact(width);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks.map(uuid).collect()
+}
+
+fn uuid(bytes: &[u8]) -> Option<BluetoothUuid> {
+    match bytes.len() {
+        2 => Some(BluetoothUuid::from(u16::from_le_bytes(bytes.try_into().ok()?))),
+        4 => Some(BluetoothUuid::from(u32::from_le_bytes(bytes.try_into().ok()?))),
+        16 => Some(BluetoothUuid::from(u128::from_le_bytes(bytes.try_into().ok()?))),
+        _ => None,
+    }
+}
+
+// The URI scheme names coded on the first byte of a URI (Bluetooth Assigned Numbers, 2.7), for
+// the schemes a web browser cares about.
+fn uri_scheme(code: u8) -> Option<&'static str> {
+    match code {
+        0x01 => Some(""),
+        0x16 => Some("http:"),
+        0x17 => Some("https:"),
+        0x18 => Some("ipp:"),
+        0x19 => Some("ipps:"),
+        _ => None,
+    }
+}
+
+impl AdvertisementData {
+    /// Splits the data into its AD structures and decodes them. The data ends at the first
+    /// zero length, as the unused part of advertising data is zero-filled.
+    pub fn parse(raw: &[u8]) -> Result<AdvertisementData, BluetoothError> {
+        let mut advertisement = AdvertisementData {
+            raw: raw.to_vec(),
+            ..AdvertisementData::default()
+        };
+        let mut structures = vec![];
+        let mut rest = raw;
+        while let Some((&length, tail)) = rest.split_first() {
+            if length == 0 {
+                break;
+            }
+            if tail.len() < length as usize {
+                return Err(BluetoothError::InvalidAdvertisementData(raw.to_vec()));
+            }
+            let (structure, tail) = tail.split_at(length as usize);
+            structures.push(AdStructure {
+                ad_type: structure[0],
+                data: structure[1..].to_vec(),
+            });
+            rest = tail;
+        }
+        for structure in &structures {
+            advertisement.decode(structure.ad_type, &structure.data);
+        }
+        advertisement.structures = structures;
+        Ok(advertisement)
+    }
+
+    fn decode(&mut self, ad_type: u8, data: &[u8]) {
+        match ad_type {
+            AD_TYPE_FLAGS => self.flags = data.first().cloned(),
+            AD_TYPE_INCOMPLETE_UUIDS_16 | AD_TYPE_COMPLETE_UUIDS_16 => self.add_service_uuids(data, 2),
+            AD_TYPE_INCOMPLETE_UUIDS_32 | AD_TYPE_COMPLETE_UUIDS_32 => self.add_service_uuids(data, 4),
+            AD_TYPE_INCOMPLETE_UUIDS_128 | AD_TYPE_COMPLETE_UUIDS_128 => self.add_service_uuids(data, 16),
+            AD_TYPE_SOLICITED_UUIDS_16 | AD_TYPE_SOLICITED_UUIDS_32 | AD_TYPE_SOLICITED_UUIDS_128 => {
+                let width = match ad_type {
+                    AD_TYPE_SOLICITED_UUIDS_16 => 2,
+                    AD_TYPE_SOLICITED_UUIDS_32 => 4,
+                    _ => 16,
+                };
+                self.solicited_service_uuids.extend(uuids(data, width).unwrap_or_default());
+            },
+            AD_TYPE_SHORTENED_LOCAL_NAME if !self.is_name_complete => {
+                self.local_name = Some(String::from_utf8_lossy(data).into_owned());
+            },
+            AD_TYPE_COMPLETE_LOCAL_NAME => {
+                self.local_name = Some(String::from_utf8_lossy(data).into_owned());
+                self.is_name_complete = true;
+            },
+            AD_TYPE_TX_POWER_LEVEL => self.tx_power = data.first().map(|&tx_power| tx_power as i8),
+            AD_TYPE_CLASS_OF_DEVICE if data.len() == 3 => {
+                self.class_of_device = Some(ClassOfDevice::from(u32::from_le_bytes([data[0], data[1], data[2], 0])));
+            },
+            AD_TYPE_SERVICE_DATA_16 | AD_TYPE_SERVICE_DATA_32 | AD_TYPE_SERVICE_DATA_128 => {
+                let width = match ad_type {
+                    AD_TYPE_SERVICE_DATA_16 => 2,
+                    AD_TYPE_SERVICE_DATA_32 => 4,
+                    _ => 16,
+                };
+                if data.len() >= width {
+                    if let Some(uuid) = uuid(&data[..width]) {
+                        self.service_data.insert(uuid, data[width..].to_vec());
+                    }
+                }
+            },
+            AD_TYPE_APPEARANCE if data.len() == 2 => self.appearance = Some(Appearance::from(u16::from_le_bytes([data[0], data[1]]))),
+            AD_TYPE_LE_ROLE => {
+                self.le_role = match data.first() {
+                    Some(0x00) => Some(LeRole::PeripheralOnly),
+                    Some(0x01) => Some(LeRole::CentralOnly),
+                    Some(0x02) => Some(LeRole::PeripheralPreferred),
+                    Some(0x03) => Some(LeRole::CentralPreferred),
+                    _ => self.le_role,
+                };
+            },
+            AD_TYPE_URI => {
+                if let Some((&scheme_code, rest)) = data.split_first() {
+                    self.uris.push(AdvertisedUri {
+                        scheme_code,
+                        rest: String::from_utf8_lossy(rest).into_owned(),
+                    });
+                }
+            },
+            AD_TYPE_MANUFACTURER_DATA if data.len() >= 2 => {
+                self.manufacturer_data.insert(u16::from_le_bytes([data[0], data[1]]), data[2..].to_vec());
+            },
+            _ => {},
+        }
+    }
+
+    fn add_service_uuids(&mut self, data: &[u8], width: usize) {
+        for uuid in uuids(data, width).unwrap_or_default() {
+            if !self.service_uuids.contains(&uuid) {
+                self.service_uuids.push(uuid);
+            }
+        }
+    }
+
+    /// Whether the device advertises in LE General or Limited Discoverable Mode.
+    pub fn is_discoverable(&self) -> bool {
+        self.flags.is_some_and(|flags| flags & 0x03 != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AdStructure, AdvertisedUri, AdvertisementData, LeRole};
+    use super::{BluetoothError, BluetoothUuid};
+
+    fn parse(raw: &[u8]) -> AdvertisementData {
+        AdvertisementData::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn truncated_structures_are_rejected() {
+        for raw in &[&[0x02][..], &[0x02, 0x01], &[0x02, 0x01, 0x06, 0x03, 0x03, 0x0D], &[0xFF, 0xFF]] {
+            match AdvertisementData::parse(raw) {
+                Err(BluetoothError::InvalidAdvertisementData(data)) => assert_eq!(data, raw.to_vec()),
+                result => panic!("{:?} parsed as {:?}", raw, result),
+            }
+        }
+    }
+
+    #[test]
+    fn data_ends_at_the_first_zero_length() {
+        assert_eq!(parse(&[]), AdvertisementData::default());
+        let advertisement = parse(&[0x02, 0x01, 0x06, 0x00, 0x05, 0xFF]);
+        assert_eq!(advertisement.structures, vec![AdStructure { ad_type: 0x01, data: vec![0x06] }]);
+        assert_eq!(advertisement.flags, Some(0x06));
+        assert!(advertisement.is_discoverable());
+        assert!(!parse(&[0x02, 0x01, 0x04]).is_discoverable());
+    }
+
+    #[test]
+    fn structures_with_only_a_type_are_kept_but_not_decoded() {
+        let advertisement = parse(&[0x01, 0x01, 0x01, 0xFF, 0x01, 0x19]);
+        assert_eq!(advertisement.structures.len(), 3);
+        assert_eq!(advertisement.flags, None);
+        assert!(advertisement.manufacturer_data.is_empty());
+        assert_eq!(advertisement.appearance, None);
+    }
+
+    #[test]
+    fn uuid_lists_must_hold_whole_uuids() {
+        let advertisement = parse(&[0x05, 0x03, 0x0D, 0x18, 0x0F, 0x18, 0x04, 0x02, 0x00, 0x18, 0x01]);
+        assert_eq!(advertisement.service_uuids, vec![BluetoothUuid::from_u16(0x180D), BluetoothUuid::from_u16(0x180F)]);
+        assert_eq!(advertisement.structures.len(), 2);
+
+        let advertisement = parse(&[0x05, 0x05, 0x0D, 0x18, 0x00, 0x00, 0x04, 0x15, 0x00, 0x00, 0x00]);
+        assert_eq!(advertisement.service_uuids, vec![BluetoothUuid::from_u16(0x180D)]);
+        assert!(advertisement.solicited_service_uuids.is_empty());
+    }
+
+    #[test]
+    fn names_prefer_the_complete_one() {
+        let advertisement = parse(&[0x03, 0x09, b'a', b'b', 0x02, 0x08, b'a']);
+        assert_eq!(advertisement.local_name, Some("ab".to_owned()));
+        assert!(advertisement.is_name_complete);
+        let advertisement = parse(&[0x02, 0x08, b'a']);
+        assert_eq!(advertisement.local_name, Some("a".to_owned()));
+        assert!(!advertisement.is_name_complete);
+    }
+
+    #[test]
+    fn short_data_structures_are_ignored() {
+        let advertisement = parse(&[0x02, 0xFF, 0xE0, 0x02, 0x16, 0x0D, 0x03, 0x0D, 0x00, 0x01, 0x02, 0x19, 0x00]);
+        assert!(advertisement.manufacturer_data.is_empty());
+        assert!(advertisement.service_data.is_empty());
+        assert_eq!(advertisement.class_of_device, None);
+        assert_eq!(advertisement.appearance, None);
+        assert_eq!(advertisement.structures.len(), 4);
+
+        let advertisement = parse(&[0x03, 0xFF, 0xE0, 0x00, 0x03, 0x16, 0x0D, 0x18, 0x02, 0x0A, 0xF6]);
+        assert_eq!(advertisement.manufacturer_data.get(&0x00E0), Some(&vec![]));
+        assert_eq!(advertisement.service_data.get(&BluetoothUuid::from_u16(0x180D)), Some(&vec![]));
+        assert_eq!(advertisement.tx_power, Some(-10));
+    }
+
+    #[test]
+    fn le_roles_ignore_unknown_values() {
+        assert_eq!(parse(&[0x02, 0x1C, 0x03]).le_role, Some(LeRole::CentralPreferred));
+        assert_eq!(parse(&[0x02, 0x1C, 0x04]).le_role, None);
+        assert_eq!(parse(&[0x01, 0x1C]).le_role, None);
+    }
+
+    #[test]
+    fn uris_keep_unknown_schemes() {
+        let advertisement = parse(&[0x05, 0x24, 0x17, b'/', b'/', b'a', 0x04, 0x24, 0x02, b'x', b'y', 0x01, 0x24]);
+        assert_eq!(advertisement.uris, vec![
+            AdvertisedUri { scheme_code: 0x17, rest: "//a".to_owned() },
+            AdvertisedUri { scheme_code: 0x02, rest: "xy".to_owned() },
+        ]);
+        assert_eq!(advertisement.uris[0].to_uri(), Some("https://a".to_owned()));
+        assert_eq!(advertisement.uris[1].scheme(), None);
+        assert_eq!(advertisement.uris[1].to_uri(), None);
+    }
+}
@@ -0,0 +1,349 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use blurdroid::bluetooth_adapter::Adapter as BluetoothAdapterAndroid;
+use blurdroid::bluetooth_device::Device as BluetoothDeviceAndroid;
+use blurdroid::bluetooth_discovery_session::DiscoverySession as BluetoothDiscoverySessionAndroid;
+use blurdroid::bluetooth_gatt_characteristic::Characteristic as BluetoothGATTCharacteristicAndroid;
+use blurdroid::bluetooth_gatt_descriptor::Descriptor as BluetoothGATTDescriptorAndroid;
+use blurdroid::bluetooth_gatt_service::Service as BluetoothGATTServiceAndroid;
+
+use super::backend::{BluetoothAdapterBackend, BluetoothDeviceBackend, BluetoothDiscoverySessionBackend,
+                     BluetoothGATTCharacteristicBackend, BluetoothGATTDescriptorBackend, BluetoothGATTServiceBackend};
+use super::device_id::DeviceId;
+use super::error::BluetoothError;
+use super::uuid::BluetoothUuid;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+const NOT_SUPPORTED_ERROR: &str = "Error! Not supported function!";
+
+pub(crate) fn map_error(error: Box<dyn Error>) -> BluetoothError {
+    match error.to_string().as_str() {
+        NOT_SUPPORTED_ERROR => BluetoothError::NotSupported,
+        "Connect error!" => BluetoothError::ConnectionFailed,
+        message => BluetoothError::Other(message.to_owned()),
+    }
+}
+
+impl BluetoothAdapterBackend for Arc<BluetoothAdapterAndroid> {
+    fn get_id(&self) -> String {
+        BluetoothAdapterAndroid::get_id(self)
+    }
+
+    // blurdroid objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_devices(&self) -> Result<Vec<Arc<dyn BluetoothDeviceBackend>>, BluetoothError> {
+        let device_list = self.get_device_list().map_err(map_error)?;
+        Ok(device_list.into_iter()
+                      .map(|device| Arc::new(Arc::new(BluetoothDeviceAndroid::new(self.clone(), device))) as Arc<dyn BluetoothDeviceBackend>)
+                      .collect())
+    }
+
+    fn create_discovery_session(&self) -> Result<Arc<dyn BluetoothDiscoverySessionBackend>, BluetoothError> {
+        let session = BluetoothDiscoverySessionAndroid::create_session(self.clone()).map_err(map_error)?;
+        Ok(Arc::new(Arc::new(session)))
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterAndroid::get_address(self).map_err(map_error)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterAndroid::get_name(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterAndroid::get_alias(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterAndroid::get_class(self).map_err(map_error)
+    }
+
+    fn is_powered(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterAndroid::is_powered(self).map_err(map_error)
+    }
+
+    fn is_discoverable(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterAndroid::is_discoverable(self).map_err(map_error)
+    }
+
+    fn is_pairable(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterAndroid::is_pairable(self).map_err(map_error)
+    }
+
+    fn get_pairable_timeout(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterAndroid::get_pairable_timeout(self).map_err(map_error)
+    }
+
+    fn get_discoverable_timeout(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterAndroid::get_discoverable_timeout(self).map_err(map_error)
+    }
+
+    fn is_discovering(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterAndroid::is_discovering(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        BluetoothAdapterAndroid::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        DeviceId::from_parts(BluetoothAdapterAndroid::get_modalias(self).map_err(map_error)?)
+    }
+
+    fn set_alias(&self, alias: String) -> Result<(), BluetoothError> {
+        BluetoothAdapterAndroid::set_alias(self, alias).map_err(map_error)
+    }
+
+    fn set_powered(&self, powered: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterAndroid::set_powered(self, powered).map_err(map_error)
+    }
+
+    fn set_discoverable(&self, discoverable: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterAndroid::set_discoverable(self, discoverable).map_err(map_error)
+    }
+
+    fn set_pairable(&self, pairable: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterAndroid::set_pairable(self, pairable).map_err(map_error)
+    }
+
+    fn set_pairable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        BluetoothAdapterAndroid::set_pairable_timeout(self, timeout).map_err(map_error)
+    }
+
+    fn set_discoverable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        BluetoothAdapterAndroid::set_discoverable_timeout(self, timeout).map_err(map_error)
+    }
+}
+
+impl BluetoothDiscoverySessionBackend for Arc<BluetoothDiscoverySessionAndroid> {
+    fn start_discovery(&self) -> Result<(), BluetoothError> {
+        BluetoothDiscoverySessionAndroid::start_discovery(self).map_err(map_error)
+    }
+
+    fn stop_discovery(&self) -> Result<(), BluetoothError> {
+        BluetoothDiscoverySessionAndroid::stop_discovery(self).map_err(map_error)
+    }
+}
+
+impl BluetoothDeviceBackend for Arc<BluetoothDeviceAndroid> {
+    fn get_id(&self) -> String {
+        BluetoothDeviceAndroid::get_id(self)
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceAndroid::get_address(self).map_err(map_error)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceAndroid::get_name(self).map_err(map_error)
+    }
+
+    fn get_icon(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceAndroid::get_icon(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        BluetoothDeviceAndroid::get_class(self).map_err(map_error)
+    }
+
+    fn get_appearance(&self) -> Result<u16, BluetoothError> {
+        BluetoothDeviceAndroid::get_appearance(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        BluetoothDeviceAndroid::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn is_paired(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceAndroid::is_paired(self).map_err(map_error)
+    }
+
+    fn is_connected(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceAndroid::is_connected(self).map_err(map_error)
+    }
+
+    fn is_trusted(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceAndroid::is_trusted(self).map_err(map_error)
+    }
+
+    fn is_blocked(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceAndroid::is_blocked(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceAndroid::get_alias(self).map_err(map_error)
+    }
+
+    fn is_legacy_pairing(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceAndroid::is_legacy_pairing(self).map_err(map_error)
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        DeviceId::from_parts(BluetoothDeviceAndroid::get_modalias(self).map_err(map_error)?)
+    }
+
+    fn get_rssi(&self) -> Result<i16, BluetoothError> {
+        BluetoothDeviceAndroid::get_rssi(self).map_err(map_error)
+    }
+
+    fn get_tx_power(&self) -> Result<i16, BluetoothError> {
+        BluetoothDeviceAndroid::get_tx_power(self).map_err(map_error)
+    }
+
+    fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError> {
+        BluetoothDeviceAndroid::get_manufacturer_data(self).map_err(map_error)
+    }
+
+    fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError> {
+        BluetoothDeviceAndroid::get_service_data(self).map_err(map_error)?
+            .into_iter()
+            .map(|(uuid, data)| Ok((uuid.parse()?, data)))
+            .collect()
+    }
+
+    // blurdroid objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_gatt_services(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        let services = BluetoothDeviceAndroid::get_gatt_services(self).map_err(map_error)?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(Arc::new(BluetoothGATTServiceAndroid::new(self.clone(), service))) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    fn connect(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceAndroid::connect(self).map_err(map_error)
+    }
+
+    fn disconnect(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceAndroid::disconnect(self).map_err(map_error)
+    }
+
+    fn connect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        BluetoothDeviceAndroid::connect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn disconnect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        BluetoothDeviceAndroid::disconnect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn pair(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceAndroid::pair(self).map_err(map_error)
+    }
+
+    fn cancel_pairing(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceAndroid::cancel_pairing(self).map_err(map_error)
+    }
+}
+
+impl BluetoothGATTServiceBackend for Arc<BluetoothGATTServiceAndroid> {
+    fn get_id(&self) -> String {
+        BluetoothGATTServiceAndroid::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTServiceAndroid::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn is_primary(&self) -> Result<bool, BluetoothError> {
+        BluetoothGATTServiceAndroid::is_primary(self).map_err(map_error)
+    }
+
+    fn get_includes(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        // NOTE: blurdroid does not support included services, and a service does not know its device,
+        // so there is nothing to build the included services from.
+        BluetoothGATTServiceAndroid::get_includes(self).map_err(map_error)?;
+        Ok(vec![])
+    }
+
+    // blurdroid objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_gatt_characteristics(&self) -> Result<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>, BluetoothError> {
+        let characteristics = BluetoothGATTServiceAndroid::get_gatt_characteristics(self).map_err(map_error)?;
+        Ok(characteristics.into_iter()
+                          .map(|characteristic|
+                              Arc::new(Arc::new(BluetoothGATTCharacteristicAndroid::new(self.clone(), characteristic)))
+                                  as Arc<dyn BluetoothGATTCharacteristicBackend>)
+                          .collect())
+    }
+}
+
+impl BluetoothGATTCharacteristicBackend for Arc<BluetoothGATTCharacteristicAndroid> {
+    fn get_id(&self) -> String {
+        BluetoothGATTCharacteristicAndroid::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::get_value(self).map_err(map_error)
+    }
+
+    fn is_notifying(&self) -> Result<bool, BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::is_notifying(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::get_flags(self).map_err(map_error)
+    }
+
+    // blurdroid objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_gatt_descriptors(&self) -> Result<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>, BluetoothError> {
+        let descriptors = BluetoothGATTCharacteristicAndroid::get_gatt_descriptors(self).map_err(map_error)?;
+        Ok(descriptors.into_iter()
+                      .map(|descriptor|
+                          Arc::new(Arc::new(BluetoothGATTDescriptorAndroid::new(self.clone(), descriptor)))
+                              as Arc<dyn BluetoothGATTDescriptorBackend>)
+                      .collect())
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::read_value(self).map_err(map_error)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::write_value(self, values).map_err(map_error)
+    }
+
+    fn start_notify(&self) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::start_notify(self).map_err(map_error)
+    }
+
+    fn stop_notify(&self) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicAndroid::stop_notify(self).map_err(map_error)
+    }
+}
+
+impl BluetoothGATTDescriptorBackend for Arc<BluetoothGATTDescriptorAndroid> {
+    fn get_id(&self) -> String {
+        BluetoothGATTDescriptorAndroid::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTDescriptorAndroid::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorAndroid::get_value(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        BluetoothGATTDescriptorAndroid::get_flags(self).map_err(map_error)
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorAndroid::read_value(self).map_err(map_error)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTDescriptorAndroid::write_value(self, values).map_err(map_error)
+    }
+}
@@ -0,0 +1,285 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::fmt;
+
+type Subcategories = &'static [(u8, &'static str)];
+
+// The appearance categories and sub-categories (Bluetooth Assigned Numbers, 2.6).
+const CATEGORIES: &[(u16, &str, Subcategories)] = &[
+    (0x000, "Unknown", &[]),
+    (0x001, "Phone", &[]),
+    (0x002, "Computer", &[
+        (0x01, "Desktop Workstation"), (0x02, "Server-class Computer"), (0x03, "Laptop"),
+        (0x04, "Handheld PC/PDA (clamshell)"), (0x05, "Palm-size PC/PDA"), (0x06, "Wearable computer (watch size)"),
+        (0x07, "Tablet"), (0x08, "Docking Station"), (0x09, "All in One"), (0x0A, "Blade Server"),
+        (0x0B, "Convertible"), (0x0C, "Detachable"), (0x0D, "IoT Gateway"), (0x0E, "Mini PC"), (0x0F, "Stick PC"),
+    ]),
+    (0x003, "Watch", &[(0x01, "Sports Watch"), (0x02, "Smartwatch")]),
+    (0x004, "Clock", &[]),
+    (0x005, "Display", &[]),
+    (0x006, "Remote Control", &[]),
+    (0x007, "Eye-glasses", &[]),
+    (0x008, "Tag", &[]),
+    (0x009, "Keyring", &[]),
+    (0x00A, "Media Player", &[]),
+    (0x00B, "Barcode Scanner", &[]),
+    (0x00C, "Thermometer", &[(0x01, "Ear Thermometer")]),
+    (0x00D, "Heart Rate Sensor", &[(0x01, "Heart Rate Belt")]),
+    (0x00E, "Blood Pressure", &[(0x01, "Arm Blood Pressure"), (0x02, "Wrist Blood Pressure")]),
+    (0x00F, "Human Interface Device", &[
+        (0x01, "Keyboard"), (0x02, "Mouse"), (0x03, "Joystick"), (0x04, "Gamepad"), (0x05, "Digitizer Tablet"),
+        (0x06, "Card Reader"), (0x07, "Digital Pen"), (0x08, "Barcode Scanner"), (0x09, "Touchpad"),
+        (0x0A, "Presentation Remote"),
+    ]),
+    (0x010, "Glucose Meter", &[]),
+    (0x011, "Running Walking Sensor", &[
+        (0x01, "In-Shoe Running Walking Sensor"), (0x02, "On-Shoe Running Walking Sensor"),
+        (0x03, "On-Hip Running Walking Sensor"),
+    ]),
+    (0x012, "Cycling", &[
+        (0x01, "Cycling Computer"), (0x02, "Speed Sensor"), (0x03, "Cadence Sensor"), (0x04, "Power Sensor"),
+        (0x05, "Speed and Cadence Sensor"),
+    ]),
+    (0x013, "Control Device", &[
+        (0x01, "Switch"), (0x02, "Multi-switch"), (0x03, "Button"), (0x04, "Slider"), (0x05, "Rotary Switch"),
+        (0x06, "Touch Panel"), (0x07, "Single Switch"), (0x08, "Double Switch"), (0x09, "Triple Switch"),
+        (0x0A, "Battery Switch"), (0x0B, "Energy Harvesting Switch"), (0x0C, "Push Button"), (0x0D, "Dial"),
+    ]),
+    (0x014, "Network Device", &[(0x01, "Access Point"), (0x02, "Mesh Device"), (0x03, "Mesh Network Proxy")]),
+    (0x015, "Sensor", &[
+        (0x01, "Motion Sensor"), (0x02, "Air quality Sensor"), (0x03, "Temperature Sensor"), (0x04, "Humidity Sensor"),
+        (0x05, "Leak Sensor"), (0x06, "Smoke Sensor"), (0x07, "Occupancy Sensor"), (0x08, "Contact Sensor"),
+        (0x09, "Carbon Monoxide Sensor"), (0x0A, "Carbon Dioxide Sensor"), (0x0B, "Ambient Light Sensor"),
+        (0x0C, "Energy Sensor"), (0x0D, "Color Light Sensor"), (0x0E, "Rain Sensor"), (0x0F, "Fire Sensor"),
+        (0x10, "Wind Sensor"), (0x11, "Proximity Sensor"), (0x12, "Multi-Sensor"),
+    ]),
+    (0x016, "Light Fixtures", &[
+        (0x01, "Wall Light"), (0x02, "Ceiling Light"), (0x03, "Floor Light"), (0x04, "Cabinet Light"),
+        (0x05, "Desk Light"), (0x06, "Troffer Light"), (0x07, "Pendant Light"), (0x08, "In-ground Light"),
+        (0x09, "Flood Light"), (0x0A, "Underwater Light"), (0x0B, "Bollard with Light"), (0x0C, "Pathway Light"),
+        (0x0D, "Garden Light"), (0x0E, "Pole-top Light"), (0x0F, "Spotlight"), (0x10, "Linear Light"),
+        (0x11, "Street Light"), (0x12, "Shelves Light"), (0x13, "Bay Light"), (0x14, "Emergency Exit Light"),
+        (0x15, "Light Controller"), (0x16, "Light Driver"), (0x17, "Bulb"), (0x18, "Low-bay Light"),
+        (0x19, "High-bay Light"),
+    ]),
+    (0x017, "Fan", &[
+        (0x01, "Ceiling Fan"), (0x02, "Axial Fan"), (0x03, "Exhaust Fan"), (0x04, "Pedestal Fan"), (0x05, "Desk Fan"),
+        (0x06, "Wall Fan"),
+    ]),
+    (0x018, "HVAC", &[
+        (0x01, "Thermostat"), (0x02, "Humidifier"), (0x03, "De-humidifier"), (0x04, "Heater"), (0x05, "Radiator"),
+        (0x06, "Boiler"), (0x07, "Heat Pump"), (0x08, "Infrared Heater"), (0x09, "Radiant Panel Heater"),
+        (0x0A, "Fan Heater"), (0x0B, "Air Curtain"),
+    ]),
+    (0x019, "Air Conditioning", &[]),
+    (0x01A, "Humidifier", &[]),
+    (0x01B, "Heating", &[
+        (0x01, "Radiator"), (0x02, "Boiler"), (0x03, "Heat Pump"), (0x04, "Infrared Heater"),
+        (0x05, "Radiant Panel Heater"), (0x06, "Fan Heater"), (0x07, "Air Curtain"),
+    ]),
+    (0x01C, "Access Control", &[
+        (0x01, "Access Door"), (0x02, "Garage Door"), (0x03, "Emergency Exit Door"), (0x04, "Access Lock"),
+        (0x05, "Elevator"), (0x06, "Window"), (0x07, "Entrance Gate"), (0x08, "Door Lock"), (0x09, "Locker"),
+    ]),
+    (0x01D, "Motorized Device", &[
+        (0x01, "Motorized Gate"), (0x02, "Awning"), (0x03, "Blinds or Shades"), (0x04, "Curtains"), (0x05, "Screen"),
+    ]),
+    (0x01E, "Power Device", &[
+        (0x01, "Power Outlet"), (0x02, "Power Strip"), (0x03, "Plug"), (0x04, "Power Supply"), (0x05, "LED Driver"),
+        (0x06, "Fluorescent Lamp Gear"), (0x07, "HID Lamp Gear"), (0x08, "Charge Case"), (0x09, "Power Bank"),
+    ]),
+    (0x01F, "Light Source", &[
+        (0x01, "Incandescent Light Bulb"), (0x02, "LED Lamp"), (0x03, "HID Lamp"), (0x04, "Fluorescent Lamp"),
+        (0x05, "LED Array"), (0x06, "Multi-Color LED Array"), (0x07, "Low voltage halogen"),
+        (0x08, "Organic light emitting diode (OLED)"),
+    ]),
+    (0x020, "Window Covering", &[
+        (0x01, "Window Shades"), (0x02, "Window Blinds"), (0x03, "Window Awning"), (0x04, "Window Curtain"),
+        (0x05, "Exterior Shutter"), (0x06, "Exterior Screen"),
+    ]),
+    (0x021, "Audio Sink", &[
+        (0x01, "Standalone Speaker"), (0x02, "Soundbar"), (0x03, "Bookshelf Speaker"), (0x04, "Standmounted Speaker"),
+        (0x05, "Speakerphone"),
+    ]),
+    (0x022, "Audio Source", &[
+        (0x01, "Microphone"), (0x02, "Alarm"), (0x03, "Bell"), (0x04, "Horn"), (0x05, "Broadcasting Device"),
+        (0x06, "Service Desk"), (0x07, "Kiosk"), (0x08, "Broadcasting Room"), (0x09, "Auditorium"),
+    ]),
+    (0x023, "Motorized Vehicle", &[
+        (0x01, "Car"), (0x02, "Large Goods Vehicle"), (0x03, "2-Wheeled Vehicle"), (0x04, "Motorbike"),
+        (0x05, "Scooter"), (0x06, "Moped"), (0x07, "3-Wheeled Vehicle"), (0x08, "Light Vehicle"), (0x09, "Quad Bike"),
+        (0x0A, "Minibus"), (0x0B, "Bus"), (0x0C, "Trolley"), (0x0D, "Agricultural Vehicle"),
+        (0x0E, "Camper / Caravan"), (0x0F, "Recreational Vehicle / Motor Home"),
+    ]),
+    (0x024, "Domestic Appliance", &[
+        (0x01, "Refrigerator"), (0x02, "Freezer"), (0x03, "Oven"), (0x04, "Microwave"), (0x05, "Toaster"),
+        (0x06, "Washing Machine"), (0x07, "Dryer"), (0x08, "Coffee maker"), (0x09, "Clothes iron"),
+        (0x0A, "Curling iron"), (0x0B, "Hair dryer"), (0x0C, "Vacuum cleaner"), (0x0D, "Robotic vacuum cleaner"),
+        (0x0E, "Rice cooker"), (0x0F, "Clothes steamer"),
+    ]),
+    (0x025, "Wearable Audio Device", &[(0x01, "Earbud"), (0x02, "Headset"), (0x03, "Headphones"), (0x04, "Neck Band")]),
+    (0x026, "Aircraft", &[
+        (0x01, "Light Aircraft"), (0x02, "Microlight"), (0x03, "Paraglider"), (0x04, "Large Passenger Aircraft"),
+    ]),
+    (0x027, "AV Equipment", &[
+        (0x01, "Amplifier"), (0x02, "Receiver"), (0x03, "Radio"), (0x04, "Tuner"), (0x05, "Turntable"),
+        (0x06, "CD Player"), (0x07, "DVD Player"), (0x08, "Bluray Player"), (0x09, "Optical Disc Player"),
+        (0x0A, "Set-Top Box"),
+    ]),
+    (0x028, "Display Equipment", &[(0x01, "Television"), (0x02, "Monitor"), (0x03, "Projector")]),
+    (0x029, "Hearing aid", &[(0x01, "In-ear hearing aid"), (0x02, "Behind-ear hearing aid"), (0x03, "Cochlear Implant")]),
+    (0x02A, "Gaming", &[(0x01, "Home Video Game Console"), (0x02, "Portable handheld console")]),
+    (0x02B, "Signage", &[(0x01, "Digital Signage"), (0x02, "Electronic Label")]),
+    (0x031, "Pulse Oximeter", &[(0x01, "Fingertip Pulse Oximeter"), (0x02, "Wrist Worn Pulse Oximeter")]),
+    (0x032, "Weight Scale", &[]),
+    (0x033, "Personal Mobility Device", &[(0x01, "Powered Wheelchair"), (0x02, "Mobility Scooter")]),
+    (0x034, "Continuous Glucose Monitor", &[]),
+    (0x035, "Insulin Pump", &[(0x01, "Insulin Pump, durable pump"), (0x04, "Insulin Pump, patch pump"), (0x08, "Insulin Pen")]),
+    (0x036, "Medication Delivery", &[]),
+    (0x037, "Spirometer", &[(0x01, "Handheld Spirometer")]),
+    (0x051, "Outdoor Sports Activity", &[
+        (0x01, "Location Display"), (0x02, "Location and Navigation Display"), (0x03, "Location Pod"),
+        (0x04, "Location and Navigation Pod"),
+    ]),
+];
+
+/// The external appearance of a device (GAP Appearance characteristic): a category in the upper
+/// 10 bits, and a sub-category in the lower 6 bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Appearance(u16);
+
+impl Appearance {
+    pub const fn new(category: u16, subcategory: u8) -> Appearance {
+        Appearance((category << 6) | (subcategory as u16 & 0x3F))
+    }
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    pub fn category(&self) -> u16 {
+        self.0 >> 6
+    }
+
+    /// The sub-category, where 0 stands for a generic device of the category.
+    pub fn subcategory(&self) -> u8 {
+        (self.0 & 0x3F) as u8
+    }
+
+    pub fn category_name(&self) -> Option<&'static str> {
+        CATEGORIES.iter().find(|&&(category, _, _)| category == self.category()).map(|&(_, name, _)| name)
+    }
+
+    pub fn subcategory_name(&self) -> Option<&'static str> {
+        let &(_, _, subcategories) = CATEGORIES.iter().find(|&&(category, _, _)| category == self.category())?;
+        subcategories.iter().find(|&&(subcategory, _)| subcategory == self.subcategory()).map(|&(_, name)| name)
+    }
+
+    /// The name of an icon in the freedesktop.org naming specification, as BlueZ picks them for
+    /// the `Icon` of LE devices.
+    pub fn icon(&self) -> Option<&'static str> {
+        match (self.category(), self.subcategory()) {
+            (0x001, _) => Some("phone"),
+            (0x002, _) => Some("computer"),
+            (0x005, _) | (0x028, _) => Some("video-display"),
+            (0x00A, _) => Some("multimedia-player"),
+            (0x00B, _) | (0x00F, 0x08) => Some("scanner"),
+            (0x00F, 0x01) => Some("input-keyboard"),
+            (0x00F, 0x02) => Some("input-mouse"),
+            (0x00F, 0x03) | (0x00F, 0x04) | (0x02A, _) => Some("input-gaming"),
+            (0x00F, 0x05) => Some("input-tablet"),
+            (0x021, _) => Some("audio-speakers"),
+            (0x022, 0x01) => Some("audio-input-microphone"),
+            (0x025, 0x02) => Some("audio-headset"),
+            (0x025, _) => Some("audio-headphones"),
+            _ => None,
+        }
+    }
+}
+
+impl From<u16> for Appearance {
+    fn from(appearance: u16) -> Appearance {
+        Appearance(appearance)
+    }
+}
+
+impl From<Appearance> for u16 {
+    fn from(appearance: Appearance) -> u16 {
+        appearance.0
+    }
+}
+
+/// The category name, followed by the sub-category name if it is not generic, e.g.
+/// "Heart Rate Sensor: Heart Rate Belt".
+impl fmt::Display for Appearance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.category_name(), self.subcategory_name()) {
+            (Some(category), Some(subcategory)) => write!(f, "{}: {}", category, subcategory),
+            (Some(category), None) => write!(f, "{}", category),
+            (None, _) => write!(f, "Unknown (0x{:04X})", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Appearance, CATEGORIES};
+
+    #[test]
+    fn category_and_subcategory_split_the_value() {
+        let appearance = Appearance::from(0x0341);
+        assert_eq!(appearance.category(), 0x00D);
+        assert_eq!(appearance.subcategory(), 0x01);
+        assert_eq!(appearance, Appearance::new(0x00D, 0x01));
+        assert_eq!(u16::from(appearance), 0x0341);
+        assert_eq!(Appearance::new(0x3FF, 0x3F).value(), 0xFFFF);
+        assert_eq!(Appearance::new(0x001, 0xFF), Appearance::new(0x001, 0x3F));
+    }
+
+    #[test]
+    fn names_come_from_the_assigned_numbers() {
+        let belt = Appearance::new(0x00D, 0x01);
+        assert_eq!(belt.category_name(), Some("Heart Rate Sensor"));
+        assert_eq!(belt.subcategory_name(), Some("Heart Rate Belt"));
+        assert_eq!(Appearance::new(0x00D, 0x00).subcategory_name(), None);
+        assert_eq!(Appearance::new(0x00D, 0x02).subcategory_name(), None);
+        assert_eq!(Appearance::new(0x02C, 0x01).category_name(), None);
+        assert_eq!(Appearance::new(0x02C, 0x01).subcategory_name(), None);
+    }
+
+    #[test]
+    fn display_leaves_out_generic_and_unknown_subcategories() {
+        assert_eq!(Appearance::new(0x00D, 0x01).to_string(), "Heart Rate Sensor: Heart Rate Belt");
+        assert_eq!(Appearance::new(0x00D, 0x00).to_string(), "Heart Rate Sensor");
+        assert_eq!(Appearance::new(0x00D, 0x3F).to_string(), "Heart Rate Sensor");
+        assert_eq!(Appearance::default().to_string(), "Unknown");
+        assert_eq!(Appearance::from(0x0B01).to_string(), "Unknown (0x0B01)");
+    }
+
+    #[test]
+    fn icons_follow_bluez() {
+        assert_eq!(Appearance::new(0x001, 0x00).icon(), Some("phone"));
+        assert_eq!(Appearance::new(0x00F, 0x01).icon(), Some("input-keyboard"));
+        assert_eq!(Appearance::new(0x00F, 0x08).icon(), Some("scanner"));
+        assert_eq!(Appearance::new(0x00F, 0x09).icon(), None);
+        assert_eq!(Appearance::new(0x02A, 0x02).icon(), Some("input-gaming"));
+        assert_eq!(Appearance::new(0x025, 0x02).icon(), Some("audio-headset"));
+        assert_eq!(Appearance::new(0x025, 0x01).icon(), Some("audio-headphones"));
+        assert_eq!(Appearance::new(0x022, 0x02).icon(), None);
+        assert_eq!(Appearance::default().icon(), None);
+    }
+
+    #[test]
+    fn the_table_is_ordered_and_fits_in_the_bits() {
+        for (index, &(category, _, subcategories)) in CATEGORIES.iter().enumerate() {
+            assert!(category < 0x400);
+            assert!(CATEGORIES[..index].iter().all(|&(other, _, _)| other < category), "{:#05X}", category);
+            for (index, &(subcategory, _)) in subcategories.iter().enumerate() {
+                assert!(subcategory > 0 && subcategory < 0x40);
+                assert!(subcategories[..index].iter().all(|&(other, _)| other < subcategory), "{:#05X}", category);
+            }
+        }
+    }
+}
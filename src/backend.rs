@@ -0,0 +1,383 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Backend traits behind the public `BluetoothAdapter`, `BluetoothDevice`, ... types.
+//!
+//! Every platform (BlueZ, Android, macOS, the `Empty` fallback and the `bluetooth-test` mock)
+//! implements these traits, and a third-party backend can be plugged in through
+//! `BluetoothAdapter::from_backend`.
+
+#[cfg(feature = "bluetooth-test")]
+use blurmock::fake_adapter::FakeBluetoothAdapter;
+#[cfg(feature = "bluetooth-test")]
+use blurmock::fake_characteristic::FakeBluetoothGATTCharacteristic;
+#[cfg(feature = "bluetooth-test")]
+use blurmock::fake_descriptor::FakeBluetoothGATTDescriptor;
+#[cfg(feature = "bluetooth-test")]
+use blurmock::fake_device::FakeBluetoothDevice;
+#[cfg(feature = "bluetooth-test")]
+use blurmock::fake_discovery_session::FakeBluetoothDiscoverySession;
+#[cfg(feature = "bluetooth-test")]
+use blurmock::fake_service::FakeBluetoothGATTService;
+
+use super::address::AddressType;
+use super::connection::{ConnectionParameters, ConnectionPriority, Phy};
+use super::device_id::DeviceId;
+use super::discovery::DiscoveryFilter;
+use super::error::BluetoothError;
+use super::event::AdapterEventStream;
+use super::future::BluetoothFuture;
+use super::gatt::WriteType;
+use super::notification::NotificationSubscription;
+use super::pairing::{IoCapability, PairingAgent};
+use super::uuid::BluetoothUuid;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+pub trait BluetoothAdapterBackend: Debug {
+    fn get_id(&self) -> String;
+    fn get_devices(&self) -> Result<Vec<Arc<dyn BluetoothDeviceBackend>>, BluetoothError>;
+    fn create_discovery_session(&self) -> Result<Arc<dyn BluetoothDiscoverySessionBackend>, BluetoothError>;
+    fn get_address(&self) -> Result<String, BluetoothError>;
+    fn get_name(&self) -> Result<String, BluetoothError>;
+    fn get_alias(&self) -> Result<String, BluetoothError>;
+    fn get_class(&self) -> Result<u32, BluetoothError>;
+    fn is_powered(&self) -> Result<bool, BluetoothError>;
+    fn is_discoverable(&self) -> Result<bool, BluetoothError>;
+    fn is_pairable(&self) -> Result<bool, BluetoothError>;
+    fn get_pairable_timeout(&self) -> Result<u32, BluetoothError>;
+    fn get_discoverable_timeout(&self) -> Result<u32, BluetoothError>;
+    fn is_discovering(&self) -> Result<bool, BluetoothError>;
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError>;
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError>;
+
+    fn set_alias(&self, _alias: String) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_powered(&self, _powered: bool) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_discoverable(&self, _discoverable: bool) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_pairable(&self, _pairable: bool) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_pairable_timeout(&self, _timeout: u32) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_discoverable_timeout(&self, _timeout: u32) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Returns a stream of the adapter and device changes that happen after this call.
+    fn subscribe_events(&self) -> Result<AdapterEventStream, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// The devices the adapter keeps the keys of.
+    fn get_bonded_devices(&self) -> Result<Vec<Arc<dyn BluetoothDeviceBackend>>, BluetoothError> {
+        let mut bonded = vec![];
+        for device in self.get_devices()? {
+            if device.is_paired()? {
+                bonded.push(device);
+            }
+        }
+        Ok(bonded)
+    }
+
+    /// Forgets the device, along with its bond.
+    fn remove_device(&self, _device_id: String) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Answers the authentication requests of the pairings with `agent`, in place of the
+    /// agent registered before.
+    fn register_pairing_agent(&self,
+                              _agent: Arc<dyn PairingAgent>,
+                              _capability: IoCapability)
+                              -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn unregister_pairing_agent(&self) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothAdapter>> {
+        None
+    }
+}
+
+pub trait BluetoothDiscoverySessionBackend: Debug {
+    fn start_discovery(&self) -> Result<(), BluetoothError>;
+    fn stop_discovery(&self) -> Result<(), BluetoothError>;
+
+    /// Applies the filter to the scan natively. The filter is still applied in software to
+    /// the discovered devices, so backends without native filtering keep the default.
+    fn set_discovery_filter(&self, _filter: &DiscoveryFilter) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothDiscoverySession>> {
+        None
+    }
+}
+
+pub trait BluetoothDeviceBackend: Debug {
+    fn get_id(&self) -> String;
+    fn get_address(&self) -> Result<String, BluetoothError>;
+
+    fn get_address_type(&self) -> Result<AddressType, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError>;
+    fn get_icon(&self) -> Result<String, BluetoothError>;
+    fn get_class(&self) -> Result<u32, BluetoothError>;
+    fn get_appearance(&self) -> Result<u16, BluetoothError>;
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError>;
+    fn is_paired(&self) -> Result<bool, BluetoothError>;
+    fn is_connected(&self) -> Result<bool, BluetoothError>;
+    fn is_trusted(&self) -> Result<bool, BluetoothError>;
+    fn is_blocked(&self) -> Result<bool, BluetoothError>;
+    fn get_alias(&self) -> Result<String, BluetoothError>;
+    fn is_legacy_pairing(&self) -> Result<bool, BluetoothError>;
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError>;
+    fn get_rssi(&self) -> Result<i16, BluetoothError>;
+    fn get_tx_power(&self) -> Result<i16, BluetoothError>;
+    fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError>;
+    fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError>;
+    fn get_gatt_services(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError>;
+    fn connect(&self) -> Result<(), BluetoothError>;
+    fn disconnect(&self) -> Result<(), BluetoothError>;
+    fn connect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError>;
+    fn disconnect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError>;
+    fn pair(&self) -> Result<(), BluetoothError>;
+    fn cancel_pairing(&self) -> Result<(), BluetoothError>;
+
+    fn get_mtu(&self) -> Result<u16, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Asks the device for an ATT MTU of at most `mtu`, and returns the negotiated one.
+    fn request_mtu(&self, _mtu: u16) -> Result<u16, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Removes the bond. Backends which cannot do it without forgetting the device do so.
+    fn unpair(&self) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_trusted(&self, _trusted: bool) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn set_blocked(&self, _blocked: bool) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn request_connection_priority(&self, _priority: ConnectionPriority) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn get_connection_parameters(&self) -> Result<ConnectionParameters, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Asks for the PHY to be used in both directions. The device may not accept it.
+    fn set_preferred_phy(&self, _phy: Phy) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn get_phy(&self) -> Result<Phy, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// The AD structures of the last advertisement received from the device, along with its
+    /// scan response.
+    fn get_advertisement_data(&self) -> Result<Vec<u8>, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// The asynchronous variants default to `BluetoothFuture::unavailable`, for the backends
+    /// whose objects cannot be sent to another thread: the callers then run the blocking
+    /// operation, and hand it the timeout through `future::blocking_timeout`. Backends whose
+    /// objects can be sent run the operation there instead.
+    fn connect_async(&self) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn disconnect_async(&self) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn pair_async(&self) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn get_gatt_services_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTServiceBackend>>> {
+        BluetoothFuture::unavailable()
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothDevice>> {
+        None
+    }
+}
+
+pub trait BluetoothGATTServiceBackend: Debug {
+    fn get_id(&self) -> String;
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError>;
+    fn is_primary(&self) -> Result<bool, BluetoothError>;
+    fn get_includes(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError>;
+    fn get_gatt_characteristics(&self) -> Result<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>, BluetoothError>;
+
+    fn get_includes_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTServiceBackend>>> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn get_gatt_characteristics_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>> {
+        BluetoothFuture::unavailable()
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothGATTService>> {
+        None
+    }
+}
+
+pub trait BluetoothGATTCharacteristicBackend: Debug {
+    fn get_id(&self) -> String;
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError>;
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError>;
+    fn is_notifying(&self) -> Result<bool, BluetoothError>;
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError>;
+    fn get_gatt_descriptors(&self) -> Result<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>, BluetoothError>;
+    /// Reads the whole value, blocking or asynchronously alike: the platform stacks run the
+    /// long procedures themselves, and backends that send single ATT requests read long
+    /// values with `gatt::read_long`.
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError>;
+    /// Writes the whole value, with `gatt::write_long` on backends that send single ATT
+    /// requests.
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError>;
+    fn start_notify(&self) -> Result<(), BluetoothError>;
+    fn stop_notify(&self) -> Result<(), BluetoothError>;
+
+    /// Returns a subscription to the values notified after this call. Starting the
+    /// notifications on the remote device is done separately, with `start_notify`.
+    fn subscribe(&self) -> Result<NotificationSubscription, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Writes with the given procedure. The caller checked that the characteristic's flags
+    /// permit it.
+    fn write_value_with_type(&self, _values: Vec<u8>, _write_type: WriteType) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Reads the value from `offset` on, with as many requests as its length takes.
+    fn read_value_at(&self, _offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Writes over the value from `offset` on, with as many requests as the length of `values`
+    /// takes.
+    fn write_value_at(&self, _offset: u16, _values: Vec<u8>) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn get_gatt_descriptors_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn write_value_async(&self, _values: Vec<u8>) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn write_value_with_type_async(&self, _values: Vec<u8>, _write_type: WriteType) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn read_value_at_async(&self, _offset: u16) -> BluetoothFuture<Vec<u8>> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn write_value_at_async(&self, _offset: u16, _values: Vec<u8>) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn start_notify_async(&self) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn stop_notify_async(&self) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothGATTCharacteristic>> {
+        None
+    }
+}
+
+pub trait BluetoothGATTDescriptorBackend: Debug {
+    fn get_id(&self) -> String;
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError>;
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError>;
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError>;
+    /// Reads the whole value, blocking or asynchronously alike: the platform stacks run the
+    /// long procedures themselves, and backends that send single ATT requests read long
+    /// values with `gatt::read_long`.
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError>;
+    /// Writes the whole value, with `gatt::write_long` on backends that send single ATT
+    /// requests.
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError>;
+
+    /// Reads the value from `offset` on, with as many requests as its length takes.
+    fn read_value_at(&self, _offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    /// Writes over the value from `offset` on, with as many requests as the length of `values`
+    /// takes.
+    fn write_value_at(&self, _offset: u16, _values: Vec<u8>) -> Result<(), BluetoothError> {
+        Err(BluetoothError::NotSupported)
+    }
+
+    fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn write_value_async(&self, _values: Vec<u8>) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn read_value_at_async(&self, _offset: u16) -> BluetoothFuture<Vec<u8>> {
+        BluetoothFuture::unavailable()
+    }
+
+    fn write_value_at_async(&self, _offset: u16, _values: Vec<u8>) -> BluetoothFuture<()> {
+        BluetoothFuture::unavailable()
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothGATTDescriptor>> {
+        None
+    }
+}
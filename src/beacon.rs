@@ -0,0 +1,467 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::advertisement::{AD_TYPE_COMPLETE_UUIDS_16, AD_TYPE_FLAGS, AD_TYPE_MANUFACTURER_DATA, AD_TYPE_SERVICE_DATA_16};
+use super::bluetooth::BluetoothDevice;
+use super::error::BluetoothError;
+use super::uuid::BluetoothUuid;
+
+use std::convert::{TryFrom, TryInto};
+use std::time::Duration;
+
+pub const APPLE_COMPANY_IDENTIFIER: u16 = 0x004C;
+pub const EDDYSTONE_SERVICE_UUID: BluetoothUuid = BluetoothUuid::from_u16(EDDYSTONE_UUID_16);
+
+const EDDYSTONE_UUID_16: u16 = 0xFEAA;
+
+const IBEACON_PREFIX: [u8; 2] = [0x02, 0x15];
+const ALTBEACON_PREFIX: [u8; 2] = [0xBE, 0xAC];
+
+const EDDYSTONE_UID: u8 = 0x00;
+const EDDYSTONE_URL: u8 = 0x10;
+const EDDYSTONE_TLM: u8 = 0x20;
+const EDDYSTONE_EID: u8 = 0x30;
+
+// The longest URL an Eddystone-URL frame carries, once encoded.
+const MAX_ENCODED_URL_LENGTH: usize = 17;
+// The temperature an Eddystone-TLM frame reports when the beacon has no sensor.
+const NO_TEMPERATURE: i16 = i16::MIN;
+
+// The URL scheme prefixes and expansions of Eddystone-URL, indexed by their code. Expansions
+// ending in a slash come first, so that encoding prefers them.
+const URL_SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+const URL_EXPANSIONS: [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/",
+    ".com", ".org", ".edu", ".net", ".info", ".biz", ".gov",
+];
+
+/// An Apple iBeacon, advertised as manufacturer data of Apple.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct IBeacon {
+    pub proximity_uuid: BluetoothUuid,
+    pub major: u16,
+    pub minor: u16,
+    /// The RSSI at 1 meter, in dBm.
+    pub measured_power: i8,
+}
+
+impl IBeacon {
+    /// Decodes the manufacturer data of Apple.
+    pub fn decode(data: &[u8]) -> Option<IBeacon> {
+        if data.len() != 23 || data[..2] != IBEACON_PREFIX {
+            return None;
+        }
+        Some(IBeacon {
+            proximity_uuid: BluetoothUuid::from(u128::from_be_bytes(data[2..18].try_into().ok()?)),
+            major: u16::from_be_bytes([data[18], data[19]]),
+            minor: u16::from_be_bytes([data[20], data[21]]),
+            measured_power: data[22] as i8,
+        })
+    }
+
+    /// Encodes the manufacturer data of Apple.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = IBEACON_PREFIX.to_vec();
+        data.extend_from_slice(&self.proximity_uuid.to_bytes());
+        data.extend_from_slice(&self.major.to_be_bytes());
+        data.extend_from_slice(&self.minor.to_be_bytes());
+        data.push(self.measured_power as u8);
+        data
+    }
+}
+
+/// An AltBeacon (https://github.com/AltBeacon/spec), advertised as manufacturer data of any
+/// company.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AltBeacon {
+    pub company_identifier: u16,
+    /// Usually an organization UUID, followed by 4 bytes identifying the beacon.
+    pub beacon_id: [u8; 20],
+    /// The RSSI at 1 meter, in dBm.
+    pub reference_rssi: i8,
+    pub reserved: u8,
+}
+
+impl AltBeacon {
+    /// Decodes the manufacturer data of `company_identifier`.
+    pub fn decode(company_identifier: u16, data: &[u8]) -> Option<AltBeacon> {
+        if data.len() != 24 || data[..2] != ALTBEACON_PREFIX {
+            return None;
+        }
+        Some(AltBeacon {
+            company_identifier,
+            beacon_id: data[2..22].try_into().ok()?,
+            reference_rssi: data[22] as i8,
+            reserved: data[23],
+        })
+    }
+
+    /// Encodes the manufacturer data of `company_identifier`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = ALTBEACON_PREFIX.to_vec();
+        data.extend_from_slice(&self.beacon_id);
+        data.push(self.reference_rssi as u8);
+        data.push(self.reserved);
+        data
+    }
+}
+
+/// An Eddystone frame (https://github.com/google/eddystone), advertised as service data of
+/// `EDDYSTONE_SERVICE_UUID`. The `tx_power` of the frames is the RSSI at 0 meters, in dBm.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EddystoneFrame {
+    Uid {
+        tx_power: i8,
+        namespace: [u8; 10],
+        instance: [u8; 6],
+    },
+    Url {
+        tx_power: i8,
+        url: String,
+    },
+    /// Unencrypted telemetry. Encrypted telemetry can only be decoded with the beacon's key.
+    Tlm {
+        /// The battery voltage, in mV, or 0 if unknown.
+        battery_voltage: u16,
+        /// The temperature, in 1/256 °C, if the beacon measures it.
+        temperature: Option<i16>,
+        advertising_count: u32,
+        /// The time since the beacon booted, to the 100 ms.
+        uptime: Duration,
+    },
+    Eid {
+        tx_power: i8,
+        eid: [u8; 8],
+    },
+}
+
+impl EddystoneFrame {
+    /// Decodes the service data of `EDDYSTONE_SERVICE_UUID`.
+    pub fn decode(data: &[u8]) -> Option<EddystoneFrame> {
+        let (&frame_type, data) = data.split_first()?;
+        match frame_type {
+            // The UID frame ends with two reserved bytes, which some beacons leave out.
+            EDDYSTONE_UID if data.len() == 17 || data.len() == 19 => Some(EddystoneFrame::Uid {
+                tx_power: data[0] as i8,
+                namespace: data[1..11].try_into().ok()?,
+                instance: data[11..17].try_into().ok()?,
+            }),
+            EDDYSTONE_URL if data.len() >= 2 => Some(EddystoneFrame::Url {
+                tx_power: data[0] as i8,
+                url: decode_url(&data[1..])?,
+            }),
+            EDDYSTONE_TLM if data.len() == 13 && data[0] == 0x00 => {
+                let temperature = i16::from_be_bytes([data[3], data[4]]);
+                Some(EddystoneFrame::Tlm {
+                    battery_voltage: u16::from_be_bytes([data[1], data[2]]),
+                    temperature: if temperature == NO_TEMPERATURE { None } else { Some(temperature) },
+                    advertising_count: u32::from_be_bytes(data[5..9].try_into().ok()?),
+                    uptime: Duration::from_millis(u32::from_be_bytes(data[9..13].try_into().ok()?) as u64 * 100),
+                })
+            },
+            EDDYSTONE_EID if data.len() == 9 => Some(EddystoneFrame::Eid {
+                tx_power: data[0] as i8,
+                eid: data[1..9].try_into().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Encodes the service data of `EDDYSTONE_SERVICE_UUID`. Fails if the URL of a URL frame
+    /// cannot be encoded in the frame.
+    pub fn encode(&self) -> Result<Vec<u8>, BluetoothError> {
+        let mut data = vec![];
+        match *self {
+            EddystoneFrame::Uid { tx_power, ref namespace, ref instance } => {
+                data.extend_from_slice(&[EDDYSTONE_UID, tx_power as u8]);
+                data.extend_from_slice(namespace);
+                data.extend_from_slice(instance);
+                data.extend_from_slice(&[0x00, 0x00]);
+            },
+            EddystoneFrame::Url { tx_power, ref url } => {
+                data.extend_from_slice(&[EDDYSTONE_URL, tx_power as u8]);
+                data.extend(encode_url(url)?);
+            },
+            EddystoneFrame::Tlm { battery_voltage, temperature, advertising_count, uptime } => {
+                let uptime = u32::try_from(uptime.as_millis() / 100).unwrap_or(u32::MAX);
+                data.extend_from_slice(&[EDDYSTONE_TLM, 0x00]);
+                data.extend_from_slice(&battery_voltage.to_be_bytes());
+                data.extend_from_slice(&temperature.unwrap_or(NO_TEMPERATURE).to_be_bytes());
+                data.extend_from_slice(&advertising_count.to_be_bytes());
+                data.extend_from_slice(&uptime.to_be_bytes());
+            },
+            EddystoneFrame::Eid { tx_power, ref eid } => {
+                data.extend_from_slice(&[EDDYSTONE_EID, tx_power as u8]);
+                data.extend_from_slice(eid);
+            },
+        }
+        Ok(data)
+    }
+}
+
+fn decode_url(data: &[u8]) -> Option<String> {
+    let (&scheme, rest) = data.split_first()?;
+    let mut url = URL_SCHEMES.get(scheme as usize)?.to_string();
+    for &byte in rest {
+        match byte {
+            0x00..=0x0D => url.push_str(URL_EXPANSIONS[byte as usize]),
+            0x21..=0x7E => url.push(byte as char),
+            _ => return None,
+        }
+    }
+    Some(url)
+}
+
+fn encode_url(url: &str) -> Result<Vec<u8>, BluetoothError> {
+    let invalid = || BluetoothError::InvalidBeacon(format!("Cannot encode the URL {} in an Eddystone-URL frame", url));
+    // The longest scheme prefix, which puts the ones with "www." first.
+    let (scheme, _) = URL_SCHEMES.iter()
+                                 .enumerate()
+                                 .filter(|(_, scheme)| url.starts_with(*scheme))
+                                 .max_by_key(|(_, scheme)| scheme.len())
+                                 .ok_or_else(invalid)?;
+    let mut data = vec![scheme as u8];
+    let mut rest = &url[URL_SCHEMES[scheme].len()..];
+    while let Some(byte) = rest.bytes().next() {
+        match URL_EXPANSIONS.iter().position(|expansion| rest.starts_with(expansion)) {
+            Some(code) => {
+                data.push(code as u8);
+                rest = &rest[URL_EXPANSIONS[code].len()..];
+            },
+            None if (0x21..=0x7E).contains(&byte) => {
+                data.push(byte);
+                rest = &rest[1..];
+            },
+            None => return Err(invalid()),
+        }
+    }
+    if data.len() - 1 > MAX_ENCODED_URL_LENGTH {
+        return Err(invalid());
+    }
+    Ok(data)
+}
+
+/// A beacon advertised by a device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Beacon {
+    IBeacon(IBeacon),
+    AltBeacon(AltBeacon),
+    Eddystone(EddystoneFrame),
+}
+
+impl Beacon {
+    /// Decodes the beacons in the manufacturer data and service data of the device, ordered by
+    /// company identifier, with Eddystone last.
+    pub fn from_device(device: &BluetoothDevice) -> Vec<Beacon> {
+        let mut manufacturer_data: Vec<_> = device.get_manufacturer_data().unwrap_or_default().into_iter().collect();
+        manufacturer_data.sort();
+        let mut beacons = vec![];
+        for (company_identifier, data) in manufacturer_data {
+            if company_identifier == APPLE_COMPANY_IDENTIFIER {
+                if let Some(ibeacon) = IBeacon::decode(&data) {
+                    beacons.push(Beacon::IBeacon(ibeacon));
+                    continue;
+                }
+            }
+            if let Some(altbeacon) = AltBeacon::decode(company_identifier, &data) {
+                beacons.push(Beacon::AltBeacon(altbeacon));
+            }
+        }
+        let service_data = device.get_service_data().unwrap_or_default();
+        if let Some(frame) = service_data.get(&EDDYSTONE_SERVICE_UUID).and_then(|data| EddystoneFrame::decode(data)) {
+            beacons.push(Beacon::Eddystone(frame));
+        }
+        beacons
+    }
+
+    /// Encodes the advertising data of a beacon, as AD structures: the flags of an LE-only
+    /// general discoverable device, and the beacon.
+    pub fn to_advertisement_data(&self) -> Result<Vec<u8>, BluetoothError> {
+        let mut structures = vec![(AD_TYPE_FLAGS, vec![0x06])];
+        match *self {
+            Beacon::IBeacon(ref ibeacon) => {
+                let mut data = APPLE_COMPANY_IDENTIFIER.to_le_bytes().to_vec();
+                data.extend(ibeacon.encode());
+                structures.push((AD_TYPE_MANUFACTURER_DATA, data));
+            },
+            Beacon::AltBeacon(ref altbeacon) => {
+                let mut data = altbeacon.company_identifier.to_le_bytes().to_vec();
+                data.extend(altbeacon.encode());
+                structures.push((AD_TYPE_MANUFACTURER_DATA, data));
+            },
+            Beacon::Eddystone(ref frame) => {
+                let uuid = EDDYSTONE_UUID_16.to_le_bytes().to_vec();
+                let mut data = uuid.clone();
+                data.extend(frame.encode()?);
+                structures.push((AD_TYPE_COMPLETE_UUIDS_16, uuid));
+                structures.push((AD_TYPE_SERVICE_DATA_16, data));
+            },
+        }
+        let mut raw = vec![];
+        for (ad_type, data) in structures {
+            raw.push(data.len() as u8 + 1);
+            raw.push(ad_type);
+            raw.extend(data);
+        }
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AltBeacon, Beacon, EddystoneFrame, IBeacon};
+    use super::{APPLE_COMPANY_IDENTIFIER, EDDYSTONE_SERVICE_UUID};
+    use crate::advertisement::AdvertisementData;
+    use crate::uuid::BluetoothUuid;
+
+    use std::time::Duration;
+
+    fn ibeacon() -> IBeacon {
+        IBeacon {
+            proximity_uuid: "e2c56db5-dffb-48d2-b060-d0f5a71096e0".parse().unwrap(),
+            major: 0x0102,
+            minor: 0x0304,
+            measured_power: -59,
+        }
+    }
+
+    fn url(url: &str) -> EddystoneFrame {
+        EddystoneFrame::Url { tx_power: -20, url: url.to_owned() }
+    }
+
+    #[test]
+    fn ibeacons_are_big_endian() {
+        let data = ibeacon().encode();
+        assert_eq!(data.len(), 23);
+        assert_eq!(data[..4], [0x02, 0x15, 0xE2, 0xC5]);
+        assert_eq!(data[18..], [0x01, 0x02, 0x03, 0x04, 0xC5]);
+        assert_eq!(IBeacon::decode(&data), Some(ibeacon()));
+    }
+
+    #[test]
+    fn malformed_ibeacons_are_rejected() {
+        let data = ibeacon().encode();
+        assert_eq!(IBeacon::decode(&data[..22]), None);
+        assert_eq!(IBeacon::decode(&[&data[..], &[0x00]].concat()), None);
+        assert_eq!(IBeacon::decode(&[&[0x02, 0x16], &data[2..]].concat()), None);
+        assert_eq!(IBeacon::decode(&[]), None);
+    }
+
+    #[test]
+    fn altbeacons_round_trip() {
+        let altbeacon = AltBeacon { company_identifier: 0x0118, beacon_id: [7; 20], reference_rssi: -65, reserved: 0x42 };
+        let data = altbeacon.encode();
+        assert_eq!(data.len(), 24);
+        assert_eq!(data[..2], [0xBE, 0xAC]);
+        assert_eq!(AltBeacon::decode(0x0118, &data), Some(altbeacon));
+        assert_eq!(AltBeacon::decode(0x0118, &data[..23]), None);
+        assert_eq!(AltBeacon::decode(0x0118, &[&[0xAC, 0xBE], &data[2..]].concat()), None);
+    }
+
+    #[test]
+    fn uid_frames_may_leave_out_the_reserved_bytes() {
+        let frame = EddystoneFrame::Uid { tx_power: -20, namespace: [1; 10], instance: [2; 6] };
+        let data = frame.encode().unwrap();
+        assert_eq!(data.len(), 20);
+        assert_eq!(EddystoneFrame::decode(&data), Some(frame.clone()));
+        assert_eq!(EddystoneFrame::decode(&data[..18]), Some(frame));
+        assert_eq!(EddystoneFrame::decode(&data[..19]), None);
+    }
+
+    #[test]
+    fn urls_use_the_longest_scheme_and_the_expansions() {
+        let data = url("https://www.example.com/a").encode().unwrap();
+        assert_eq!(data, [&[0x10, 0xEC, 0x01][..], b"example", &[0x00], b"a"].concat());
+        assert_eq!(EddystoneFrame::decode(&data), Some(url("https://www.example.com/a")));
+
+        let data = url("http://a.org").encode().unwrap();
+        assert_eq!(data, [0x10, 0xEC, 0x02, b'a', 0x08]);
+        assert_eq!(EddystoneFrame::decode(&data), Some(url("http://a.org")));
+    }
+
+    #[test]
+    fn urls_that_do_not_fit_are_rejected() {
+        assert!(url("https://www.seventeen-chars.a").encode().is_ok());
+        assert!(url("https://www.eighteen-chars.abc").encode().is_err());
+        assert!(url("ftp://example.com").encode().is_err());
+        assert!(url("https://exa mple.com").encode().is_err());
+        assert!(url("https://exämple.com").encode().is_err());
+    }
+
+    #[test]
+    fn malformed_urls_are_not_decoded() {
+        assert_eq!(EddystoneFrame::decode(&[0x10, 0xEC]), None);
+        assert_eq!(EddystoneFrame::decode(&[0x10, 0xEC, 0x04, b'a']), None);
+        assert_eq!(EddystoneFrame::decode(&[0x10, 0xEC, 0x00, 0x0E]), None);
+        assert_eq!(EddystoneFrame::decode(&[0x10, 0xEC, 0x00, 0x20]), None);
+        assert_eq!(EddystoneFrame::decode(&[0x10, 0xEC, 0x00, 0x0D]), Some(url("http://www..gov")));
+    }
+
+    #[test]
+    fn tlm_frames_round_trip_and_may_have_no_temperature() {
+        let frame = EddystoneFrame::Tlm {
+            battery_voltage: 3000,
+            temperature: Some(-256),
+            advertising_count: 1234,
+            uptime: Duration::from_millis(98_700),
+        };
+        let data = frame.encode().unwrap();
+        assert_eq!(data[..6], [0x20, 0x00, 0x0B, 0xB8, 0xFF, 0x00]);
+        assert_eq!(data[10..], 987u32.to_be_bytes());
+        assert_eq!(EddystoneFrame::decode(&data), Some(frame));
+
+        let frame = EddystoneFrame::Tlm {
+            battery_voltage: 0,
+            temperature: None,
+            advertising_count: 0,
+            uptime: Duration::from_millis(0),
+        };
+        let data = frame.encode().unwrap();
+        assert_eq!(data[4..6], [0x80, 0x00]);
+        assert_eq!(EddystoneFrame::decode(&data), Some(frame));
+    }
+
+    #[test]
+    fn encrypted_tlm_and_unknown_frames_are_not_decoded() {
+        let mut data = EddystoneFrame::Tlm {
+            battery_voltage: 0,
+            temperature: None,
+            advertising_count: 0,
+            uptime: Duration::from_secs(0),
+        }.encode().unwrap();
+        data[1] = 0x01;
+        assert_eq!(EddystoneFrame::decode(&data), None);
+        assert_eq!(EddystoneFrame::decode(&[0x40, 0x00]), None);
+        assert_eq!(EddystoneFrame::decode(&[]), None);
+    }
+
+    #[test]
+    fn eid_frames_round_trip() {
+        let frame = EddystoneFrame::Eid { tx_power: 4, eid: [9; 8] };
+        let data = frame.encode().unwrap();
+        assert_eq!(data.len(), 10);
+        assert_eq!(EddystoneFrame::decode(&data), Some(frame));
+        assert_eq!(EddystoneFrame::decode(&data[..9]), None);
+    }
+
+    #[test]
+    fn advertisement_data_holds_the_flags_and_the_beacon() {
+        let advertisement = AdvertisementData::parse(&Beacon::IBeacon(ibeacon()).to_advertisement_data().unwrap()).unwrap();
+        assert_eq!(advertisement.flags, Some(0x06));
+        let data = &advertisement.manufacturer_data[&APPLE_COMPANY_IDENTIFIER];
+        assert_eq!(IBeacon::decode(data), Some(ibeacon()));
+
+        let frame = url("https://example.com");
+        let advertisement = AdvertisementData::parse(&Beacon::Eddystone(frame.clone()).to_advertisement_data().unwrap()).unwrap();
+        assert_eq!(advertisement.service_uuids, vec![EDDYSTONE_SERVICE_UUID]);
+        assert_eq!(EddystoneFrame::decode(&advertisement.service_data[&EDDYSTONE_SERVICE_UUID]), Some(frame));
+        assert_eq!(EDDYSTONE_SERVICE_UUID, BluetoothUuid::from_u16(0xFEAA));
+    }
+
+    #[test]
+    fn advertisement_data_rejects_urls_that_do_not_fit() {
+        let frame = url("https://www.a-much-too-long-address.com/");
+        assert!(Beacon::Eddystone(frame).to_advertisement_data().is_err());
+    }
+}
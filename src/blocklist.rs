@@ -0,0 +1,387 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::bluetooth::{BluetoothDevice, BluetoothGATTCharacteristic, BluetoothGATTDescriptor, BluetoothGATTService};
+use super::error::BluetoothError;
+use super::future::BluetoothFuture;
+use super::gatt::WriteType;
+use super::notification::NotificationSubscription;
+use super::uuid::BluetoothUuid;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// What the GATT blocklist forbids on the attributes with a UUID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Blocklisted {
+    /// The attribute is hidden altogether.
+    Exclude,
+    ExcludeReads,
+    ExcludeWrites,
+}
+
+/// The Web Bluetooth GATT blocklist, which keeps pages from reaching the attributes that would
+/// let them take over a device, see https://github.com/WebBluetoothCG/registries.
+///
+/// The methods named after the GATT operations are the filtering layer: they hide the excluded
+/// attributes from the lists, and fail with `BluetoothError::Blocklisted` on the others. Every
+/// read, write and notification of an attribute has to go through them, including the
+/// `*_at`, `*_with_type` and `*_async` variants.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GattBlocklist {
+    entries: HashMap<BluetoothUuid, Blocklisted>,
+}
+
+impl GattBlocklist {
+    pub fn new() -> GattBlocklist {
+        GattBlocklist::default()
+    }
+
+    /// Parses the text format of the upstream `gatt_blocklist.txt`: a UUID per line, optionally
+    /// followed by `exclude`, `exclude-reads` or `exclude-writes`, which defaults to `exclude`.
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn parse(text: &str) -> Result<GattBlocklist, BluetoothError> {
+        let mut blocklist = GattBlocklist::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || BluetoothError::InvalidBlocklist(line.to_owned());
+            let mut tokens = line.split_whitespace();
+            let uuid = tokens.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+            let blocklisted = match tokens.next() {
+                None | Some("exclude") => Blocklisted::Exclude,
+                Some("exclude-reads") => Blocklisted::ExcludeReads,
+                Some("exclude-writes") => Blocklisted::ExcludeWrites,
+                Some(_) => return Err(invalid()),
+            };
+            if tokens.next().is_some() {
+                return Err(invalid());
+            }
+            blocklist.insert(uuid, blocklisted);
+        }
+        Ok(blocklist)
+    }
+
+    pub fn insert(&mut self, uuid: BluetoothUuid, blocklisted: Blocklisted) {
+        self.entries.insert(uuid, blocklisted);
+    }
+
+    pub fn get(&self, uuid: &BluetoothUuid) -> Option<Blocklisted> {
+        self.entries.get(uuid).cloned()
+    }
+
+    pub fn is_excluded(&self, uuid: &BluetoothUuid) -> bool {
+        self.get(uuid) == Some(Blocklisted::Exclude)
+    }
+
+    pub fn is_excluded_from_reads(&self, uuid: &BluetoothUuid) -> bool {
+        matches!(self.get(uuid), Some(Blocklisted::Exclude) | Some(Blocklisted::ExcludeReads))
+    }
+
+    pub fn is_excluded_from_writes(&self, uuid: &BluetoothUuid) -> bool {
+        matches!(self.get(uuid), Some(Blocklisted::Exclude) | Some(Blocklisted::ExcludeWrites))
+    }
+
+    fn check(&self, uuid: BluetoothUuid, excluded: fn(&GattBlocklist, &BluetoothUuid) -> bool) -> Result<(), BluetoothError> {
+        if excluded(self, &uuid) {
+            return Err(BluetoothError::Blocklisted);
+        }
+        Ok(())
+    }
+
+    // Starts the operation, unless the blocklist forbids it on the attribute with `uuid`.
+    fn check_async<T, F>(&self,
+                         uuid: Result<BluetoothUuid, BluetoothError>,
+                         excluded: fn(&GattBlocklist, &BluetoothUuid) -> bool,
+                         operation: F)
+                         -> BluetoothFuture<T>
+        where T: 'static, F: FnOnce() -> BluetoothFuture<T> {
+        match uuid.and_then(|uuid| self.check(uuid, excluded)) {
+            Ok(()) => operation(),
+            Err(error) => BluetoothFuture::failed(error),
+        }
+    }
+
+    fn without_excluded<A>(&self,
+                           attributes: Vec<A>,
+                           uuid: fn(&A) -> Result<BluetoothUuid, BluetoothError>)
+                           -> Result<Vec<A>, BluetoothError> {
+        let mut allowed = vec![];
+        for attribute in attributes {
+            if !self.is_excluded(&uuid(&attribute)?) {
+                allowed.push(attribute);
+            }
+        }
+        Ok(allowed)
+    }
+
+    /// The services of the device which are not excluded.
+    pub fn get_gatt_services(&self, device: &BluetoothDevice) -> Result<Vec<BluetoothGATTService>, BluetoothError> {
+        self.without_excluded(device.get_gatt_services()?, BluetoothGATTService::get_uuid)
+    }
+
+    pub fn get_gatt_services_async(&self, device: &BluetoothDevice) -> BluetoothFuture<Vec<BluetoothGATTService>> {
+        let blocklist = self.clone();
+        device.get_gatt_services_async()
+              .and_then(move |services| blocklist.without_excluded(services, BluetoothGATTService::get_uuid))
+    }
+
+    /// The included services of the service which are not excluded.
+    pub fn get_includes(&self, service: &BluetoothGATTService) -> Result<Vec<BluetoothGATTService>, BluetoothError> {
+        self.check(service.get_uuid()?, GattBlocklist::is_excluded)?;
+        self.without_excluded(service.get_includes()?, BluetoothGATTService::get_uuid)
+    }
+
+    pub fn get_includes_async(&self, service: &BluetoothGATTService) -> BluetoothFuture<Vec<BluetoothGATTService>> {
+        let blocklist = self.clone();
+        self.check_async(service.get_uuid(), GattBlocklist::is_excluded, || {
+            service.get_includes_async()
+                   .and_then(move |services| blocklist.without_excluded(services, BluetoothGATTService::get_uuid))
+        })
+    }
+
+    pub fn get_gatt_characteristics(&self,
+                                    service: &BluetoothGATTService)
+                                    -> Result<Vec<BluetoothGATTCharacteristic>, BluetoothError> {
+        self.check(service.get_uuid()?, GattBlocklist::is_excluded)?;
+        self.without_excluded(service.get_gatt_characteristics()?, BluetoothGATTCharacteristic::get_uuid)
+    }
+
+    pub fn get_gatt_characteristics_async(&self,
+                                          service: &BluetoothGATTService)
+                                          -> BluetoothFuture<Vec<BluetoothGATTCharacteristic>> {
+        let blocklist = self.clone();
+        self.check_async(service.get_uuid(), GattBlocklist::is_excluded, || {
+            service.get_gatt_characteristics_async().and_then(move |characteristics| {
+                blocklist.without_excluded(characteristics, BluetoothGATTCharacteristic::get_uuid)
+            })
+        })
+    }
+
+    pub fn get_gatt_descriptors(&self,
+                                characteristic: &BluetoothGATTCharacteristic)
+                                -> Result<Vec<BluetoothGATTDescriptor>, BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded)?;
+        self.without_excluded(characteristic.get_gatt_descriptors()?, BluetoothGATTDescriptor::get_uuid)
+    }
+
+    pub fn get_gatt_descriptors_async(&self,
+                                      characteristic: &BluetoothGATTCharacteristic)
+                                      -> BluetoothFuture<Vec<BluetoothGATTDescriptor>> {
+        let blocklist = self.clone();
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded, || {
+            characteristic.get_gatt_descriptors_async().and_then(move |descriptors| {
+                blocklist.without_excluded(descriptors, BluetoothGATTDescriptor::get_uuid)
+            })
+        })
+    }
+
+    pub fn read_value(&self, characteristic: &BluetoothGATTCharacteristic) -> Result<Vec<u8>, BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_reads)?;
+        characteristic.read_value()
+    }
+
+    pub fn read_value_async(&self, characteristic: &BluetoothGATTCharacteristic) -> BluetoothFuture<Vec<u8>> {
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded_from_reads, || characteristic.read_value_async())
+    }
+
+    pub fn read_value_at(&self, characteristic: &BluetoothGATTCharacteristic, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_reads)?;
+        characteristic.read_value_at(offset)
+    }
+
+    pub fn read_value_at_async(&self, characteristic: &BluetoothGATTCharacteristic, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded_from_reads, || {
+            characteristic.read_value_at_async(offset)
+        })
+    }
+
+    pub fn write_value(&self, characteristic: &BluetoothGATTCharacteristic, values: Vec<u8>) -> Result<(), BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_writes)?;
+        characteristic.write_value(values)
+    }
+
+    pub fn write_value_async(&self, characteristic: &BluetoothGATTCharacteristic, values: Vec<u8>) -> BluetoothFuture<()> {
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded_from_writes, || {
+            characteristic.write_value_async(values)
+        })
+    }
+
+    pub fn write_value_at(&self,
+                          characteristic: &BluetoothGATTCharacteristic,
+                          offset: u16,
+                          values: Vec<u8>)
+                          -> Result<(), BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_writes)?;
+        characteristic.write_value_at(offset, values)
+    }
+
+    pub fn write_value_at_async(&self,
+                                characteristic: &BluetoothGATTCharacteristic,
+                                offset: u16,
+                                values: Vec<u8>)
+                                -> BluetoothFuture<()> {
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded_from_writes, || {
+            characteristic.write_value_at_async(offset, values)
+        })
+    }
+
+    pub fn write_value_with_type(&self,
+                                 characteristic: &BluetoothGATTCharacteristic,
+                                 values: Vec<u8>,
+                                 write_type: WriteType)
+                                 -> Result<(), BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_writes)?;
+        characteristic.write_value_with_type(values, write_type)
+    }
+
+    pub fn write_value_with_type_async(&self,
+                                       characteristic: &BluetoothGATTCharacteristic,
+                                       values: Vec<u8>,
+                                       write_type: WriteType)
+                                       -> BluetoothFuture<()> {
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded_from_writes, || {
+            characteristic.write_value_with_type_async(values, write_type)
+        })
+    }
+
+    /// Notifications carry the value, so the characteristics excluded from reads cannot
+    /// notify either.
+    pub fn start_notify(&self, characteristic: &BluetoothGATTCharacteristic) -> Result<(), BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_reads)?;
+        characteristic.start_notify()
+    }
+
+    pub fn start_notify_async(&self, characteristic: &BluetoothGATTCharacteristic) -> BluetoothFuture<()> {
+        self.check_async(characteristic.get_uuid(), GattBlocklist::is_excluded_from_reads, || characteristic.start_notify_async())
+    }
+
+    pub fn subscribe(&self, characteristic: &BluetoothGATTCharacteristic) -> Result<NotificationSubscription, BluetoothError> {
+        self.check(characteristic.get_uuid()?, GattBlocklist::is_excluded_from_reads)?;
+        characteristic.subscribe()
+    }
+
+    pub fn read_descriptor_value(&self, descriptor: &BluetoothGATTDescriptor) -> Result<Vec<u8>, BluetoothError> {
+        self.check(descriptor.get_uuid()?, GattBlocklist::is_excluded_from_reads)?;
+        descriptor.read_value()
+    }
+
+    pub fn read_descriptor_value_async(&self, descriptor: &BluetoothGATTDescriptor) -> BluetoothFuture<Vec<u8>> {
+        self.check_async(descriptor.get_uuid(), GattBlocklist::is_excluded_from_reads, || descriptor.read_value_async())
+    }
+
+    pub fn read_descriptor_value_at(&self, descriptor: &BluetoothGATTDescriptor, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        self.check(descriptor.get_uuid()?, GattBlocklist::is_excluded_from_reads)?;
+        descriptor.read_value_at(offset)
+    }
+
+    pub fn read_descriptor_value_at_async(&self, descriptor: &BluetoothGATTDescriptor, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        self.check_async(descriptor.get_uuid(), GattBlocklist::is_excluded_from_reads, || {
+            descriptor.read_value_at_async(offset)
+        })
+    }
+
+    pub fn write_descriptor_value(&self, descriptor: &BluetoothGATTDescriptor, values: Vec<u8>) -> Result<(), BluetoothError> {
+        self.check(descriptor.get_uuid()?, GattBlocklist::is_excluded_from_writes)?;
+        descriptor.write_value(values)
+    }
+
+    pub fn write_descriptor_value_async(&self, descriptor: &BluetoothGATTDescriptor, values: Vec<u8>) -> BluetoothFuture<()> {
+        self.check_async(descriptor.get_uuid(), GattBlocklist::is_excluded_from_writes, || {
+            descriptor.write_value_async(values)
+        })
+    }
+
+    pub fn write_descriptor_value_at(&self,
+                                     descriptor: &BluetoothGATTDescriptor,
+                                     offset: u16,
+                                     values: Vec<u8>)
+                                     -> Result<(), BluetoothError> {
+        self.check(descriptor.get_uuid()?, GattBlocklist::is_excluded_from_writes)?;
+        descriptor.write_value_at(offset, values)
+    }
+
+    pub fn write_descriptor_value_at_async(&self,
+                                           descriptor: &BluetoothGATTDescriptor,
+                                           offset: u16,
+                                           values: Vec<u8>)
+                                           -> BluetoothFuture<()> {
+        self.check_async(descriptor.get_uuid(), GattBlocklist::is_excluded_from_writes, || {
+            descriptor.write_value_at_async(offset, values)
+        })
+    }
+}
+
+impl FromStr for GattBlocklist {
+    type Err = BluetoothError;
+
+    fn from_str(text: &str) -> Result<GattBlocklist, BluetoothError> {
+        GattBlocklist::parse(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BluetoothError, BluetoothUuid, Blocklisted, GattBlocklist};
+
+    const HEART_RATE_SERVICE: BluetoothUuid = BluetoothUuid::from_u16(0x180D);
+    const HEART_RATE_CONTROL_POINT: BluetoothUuid = BluetoothUuid::from_u16(0x2A39);
+    const CLIENT_CHARACTERISTIC_CONFIGURATION: BluetoothUuid = BluetoothUuid::from_u16(0x2902);
+
+    #[test]
+    fn the_upstream_format_is_parsed() {
+        let text = "# The comment lines and the empty ones are skipped.\n\
+                    \n\
+                    0000180d-0000-1000-8000-00805f9b34fb\n\
+                    00002a39-0000-1000-8000-00805f9b34fb exclude-writes\n  \
+                    00002902-0000-1000-8000-00805f9b34fb   exclude-reads  \n";
+        let blocklist = GattBlocklist::parse(text).unwrap();
+        assert_eq!(blocklist.get(&HEART_RATE_SERVICE), Some(Blocklisted::Exclude));
+        assert_eq!(blocklist.get(&HEART_RATE_CONTROL_POINT), Some(Blocklisted::ExcludeWrites));
+        assert_eq!(blocklist.get(&CLIENT_CHARACTERISTIC_CONFIGURATION), Some(Blocklisted::ExcludeReads));
+        assert_eq!(text.parse(), Ok(blocklist));
+    }
+
+    #[test]
+    fn exclude_is_explicit_or_implied() {
+        let explicit = GattBlocklist::parse("0000180d-0000-1000-8000-00805f9b34fb exclude").unwrap();
+        let implied = GattBlocklist::parse("0000180d-0000-1000-8000-00805f9b34fb").unwrap();
+        assert_eq!(explicit, implied);
+        assert_eq!(GattBlocklist::parse(""), Ok(GattBlocklist::new()));
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        for line in &["not-a-uuid",
+                      "0000180d-0000-1000-8000-00805f9b34fb exclude-notifications",
+                      "0000180d-0000-1000-8000-00805f9b34fb exclude extra"] {
+            assert_eq!(GattBlocklist::parse(line), Err(BluetoothError::InvalidBlocklist(line.to_string())));
+        }
+    }
+
+    #[test]
+    fn exclusions_cover_what_they_name() {
+        let mut blocklist = GattBlocklist::new();
+        blocklist.insert(HEART_RATE_SERVICE, Blocklisted::Exclude);
+        blocklist.insert(HEART_RATE_CONTROL_POINT, Blocklisted::ExcludeWrites);
+        blocklist.insert(CLIENT_CHARACTERISTIC_CONFIGURATION, Blocklisted::ExcludeReads);
+
+        assert!(blocklist.is_excluded(&HEART_RATE_SERVICE));
+        assert!(blocklist.is_excluded_from_reads(&HEART_RATE_SERVICE));
+        assert!(blocklist.is_excluded_from_writes(&HEART_RATE_SERVICE));
+
+        assert!(!blocklist.is_excluded(&HEART_RATE_CONTROL_POINT));
+        assert!(!blocklist.is_excluded_from_reads(&HEART_RATE_CONTROL_POINT));
+        assert!(blocklist.is_excluded_from_writes(&HEART_RATE_CONTROL_POINT));
+
+        assert!(!blocklist.is_excluded(&CLIENT_CHARACTERISTIC_CONFIGURATION));
+        assert!(blocklist.is_excluded_from_reads(&CLIENT_CHARACTERISTIC_CONFIGURATION));
+        assert!(!blocklist.is_excluded_from_writes(&CLIENT_CHARACTERISTIC_CONFIGURATION));
+
+        let unlisted = BluetoothUuid::from_u16(0x180F);
+        assert_eq!(blocklist.get(&unlisted), None);
+        assert!(!blocklist.is_excluded_from_reads(&unlisted) && !blocklist.is_excluded_from_writes(&unlisted));
+    }
+}
@@ -3,686 +3,931 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 pub use super::adapter::BluetoothAdapter;
+pub use super::address::{AddressType, BdAddr};
+pub use super::advertisement::{AdStructure, AdvertisementData, LeRole};
+pub use super::appearance::Appearance;
+pub use super::beacon::{AltBeacon, Beacon, EddystoneFrame, IBeacon};
+pub use super::blocklist::{Blocklisted, GattBlocklist};
+pub use super::class::{ClassOfDevice, MajorDeviceClass, MajorServiceClass};
+pub use super::connection::{ConnectionParameters, ConnectionPriority, Phy};
+pub use super::device_id::{DeviceId, VendorIdSource};
+pub use super::discovery::{DiscoveryFilter, DiscoveryTransport};
+pub use super::error::{AttError, BluetoothError};
+pub use super::event::{AdapterEvent, AdapterEventStream, DeviceProperty};
+pub use super::future::{BluetoothFuture, CancellationToken, Completion, OperationTimeouts};
+pub use super::gatt::WriteType;
+pub use super::notification::{NotificationSubscription, NotificationValue};
+pub use super::pairing::{IoCapability, PairingAgent, PairingMethod};
+pub use super::request::{BluetoothDataFilter, BluetoothLEScanFilter, BluetoothManufacturerDataFilter};
+pub use super::request::{BluetoothServiceDataFilter, RequestDeviceOptions};
+pub use super::uuid::BluetoothUuid;
 
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_device::BluetoothDevice as BluetoothDeviceBluez;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_device::Device as BluetoothDeviceAndroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothDevice as BluetoothDeviceMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothDevice as BluetoothDeviceEmpty;
-#[cfg(feature = "bluetooth-test")]
-use blurmock::fake_device::FakeBluetoothDevice;
-
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_gatt_characteristic::BluetoothGATTCharacteristic as BluetoothGATTCharacteristicBluez;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_gatt_characteristic::Characteristic as BluetoothGATTCharacteristicAndroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothGATTCharacteristic as BluetoothGATTCharacteristicMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothGATTCharacteristic as BluetoothGATTCharacteristicEmpty;
 #[cfg(feature = "bluetooth-test")]
 use blurmock::fake_characteristic::FakeBluetoothGATTCharacteristic;
-
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_gatt_descriptor::BluetoothGATTDescriptor as BluetoothGATTDescriptorBluez;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_gatt_descriptor::Descriptor as BluetoothGATTDescriptorAndroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothGATTDescriptor as BluetoothGATTDescriptorMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothGATTDescriptor as BluetoothGATTDescriptorEmpty;
 #[cfg(feature = "bluetooth-test")]
 use blurmock::fake_descriptor::FakeBluetoothGATTDescriptor;
-
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_gatt_service::BluetoothGATTService as BluetoothGATTServiceBluez;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_gatt_service::Service as BluetoothGATTServiceAndroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothGATTService as BluetoothGATTServiceMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothGATTService as BluetoothGATTServiceEmpty;
 #[cfg(feature = "bluetooth-test")]
-use blurmock::fake_service::FakeBluetoothGATTService;
-
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-use blurz::bluetooth_discovery_session::BluetoothDiscoverySession as BluetoothDiscoverySessionBluez;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-use blurdroid::bluetooth_discovery_session::DiscoverySession as BluetoothDiscoverySessionAndroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-use blurmac::BluetoothDiscoverySession as BluetoothDiscoverySessionMac;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-use super::empty::BluetoothDiscoverySession as BluetoothDiscoverySessionEmpty;
+use blurmock::fake_device::FakeBluetoothDevice;
 #[cfg(feature = "bluetooth-test")]
-use blurmock::fake_discovery_session::FakeBluetoothDiscoverySession;
+use blurmock::fake_service::FakeBluetoothGATTService;
 
-use super::macros::get_inner_and_call;
+use super::backend::{BluetoothDeviceBackend, BluetoothGATTCharacteristicBackend};
+use super::backend::{BluetoothGATTDescriptorBackend, BluetoothGATTServiceBackend};
+use super::future::{self, TimeoutSettings};
 #[cfg(feature = "bluetooth-test")]
-use super::macros::get_inner_and_call_test_func;
+use super::mock;
 
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::error::Error;
-
-#[cfg(feature = "bluetooth-test")]
-const NOT_SUPPORTED_ON_MOCK_ERROR: &'static str = "Error! The first parameter must be a mock structure!";
 
 
+/// A request for the adapter to scan, which lasts from `start_discovery` until `stop_discovery`
+/// or until the session is dropped. The adapter scans while at least one of its sessions is
+/// started.
 #[derive(Debug)]
-pub enum BluetoothDiscoverySession {
-    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    Bluez(Arc<BluetoothDiscoverySessionBluez>),
-    #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    Android(Arc<BluetoothDiscoverySessionAndroid>),
-    #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    Mac(Arc<BluetoothDiscoverySessionMac>),
-    #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                  all(target_os = "android", feature = "bluetooth"),
-                  all(target_os = "macos", feature = "bluetooth"))))]
-    Empty(Arc<BluetoothDiscoverySessionEmpty>),
-    #[cfg(feature = "bluetooth-test")]
-    Mock(Arc<FakeBluetoothDiscoverySession>),
+pub struct BluetoothDiscoverySession {
+    adapter: BluetoothAdapter,
+    id: usize,
+    filter: DiscoveryFilter,
 }
 
 #[derive(Clone, Debug)]
-pub enum BluetoothDevice {
-    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    Bluez(Arc<BluetoothDeviceBluez>),
-    #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    Android(Arc<BluetoothDeviceAndroid>),
-    #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    Mac(Arc<BluetoothDeviceMac>),
-    #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                  all(target_os = "android", feature = "bluetooth"),
-                  all(target_os = "macos", feature = "bluetooth"))))]
-    Empty(Arc<BluetoothDeviceEmpty>),
-    #[cfg(feature = "bluetooth-test")]
-    Mock(Arc<FakeBluetoothDevice>),
-}
+pub struct BluetoothDevice(Arc<dyn BluetoothDeviceBackend>, Arc<TimeoutSettings>);
 
 #[derive(Clone, Debug)]
-pub enum BluetoothGATTService {
-    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    Bluez(Arc<BluetoothGATTServiceBluez>),
-    #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    Android(Arc<BluetoothGATTServiceAndroid>),
-    #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    Mac(Arc<BluetoothGATTServiceMac>),
-    #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                  all(target_os = "android", feature = "bluetooth"),
-                  all(target_os = "macos", feature = "bluetooth"))))]
-    Empty(Arc<BluetoothGATTServiceEmpty>),
-    #[cfg(feature = "bluetooth-test")]
-    Mock(Arc<FakeBluetoothGATTService>),
-}
+pub struct BluetoothGATTService(Arc<dyn BluetoothGATTServiceBackend>, Arc<TimeoutSettings>);
 
 #[derive(Clone, Debug)]
-pub enum BluetoothGATTCharacteristic {
-    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    Bluez(Arc<BluetoothGATTCharacteristicBluez>),
-    #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    Android(Arc<BluetoothGATTCharacteristicAndroid>),
-    #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    Mac(Arc<BluetoothGATTCharacteristicMac>),
-    #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                  all(target_os = "android", feature = "bluetooth"),
-                  all(target_os = "macos", feature = "bluetooth"))))]
-    Empty(Arc<BluetoothGATTCharacteristicEmpty>),
-    #[cfg(feature = "bluetooth-test")]
-    Mock(Arc<FakeBluetoothGATTCharacteristic>),
-}
+pub struct BluetoothGATTCharacteristic(Arc<dyn BluetoothGATTCharacteristicBackend>, Arc<TimeoutSettings>);
 
 #[derive(Clone, Debug)]
-pub enum BluetoothGATTDescriptor {
-    #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-    Bluez(Arc<BluetoothGATTDescriptorBluez>),
-    #[cfg(all(target_os = "android", feature = "bluetooth"))]
-    Android(Arc<BluetoothGATTDescriptorAndroid>),
-    #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-    Mac(Arc<BluetoothGATTDescriptorMac>),
-    #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                  all(target_os = "android", feature = "bluetooth"),
-                  all(target_os = "macos", feature = "bluetooth"))))]
-    Empty(Arc<BluetoothGATTDescriptorEmpty>),
-    #[cfg(feature = "bluetooth-test")]
-    Mock(Arc<FakeBluetoothGATTDescriptor>),
-}
+pub struct BluetoothGATTDescriptor(Arc<dyn BluetoothGATTDescriptorBackend>, Arc<TimeoutSettings>);
 
 impl BluetoothDiscoverySession {
-    pub fn start_discovery(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDiscoverySession, start_discovery)
+    pub(crate) fn new(adapter: BluetoothAdapter, filter: DiscoveryFilter) -> BluetoothDiscoverySession {
+        let id = adapter.discovery_sessions().new_id();
+        BluetoothDiscoverySession { adapter, id, filter }
+    }
+
+    pub fn start_discovery(&self) -> Result<(), BluetoothError> {
+        self.adapter.discovery_sessions().start(self.adapter.backend(), self.id, &self.filter)
+    }
+
+    pub fn stop_discovery(&self) -> Result<(), BluetoothError> {
+        self.adapter.discovery_sessions().stop(self.id)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.adapter.discovery_sessions().is_active(self.id)
+    }
+
+    pub fn get_filter(&self) -> &DiscoveryFilter {
+        &self.filter
+    }
+
+    /// Returns the devices of the adapter that pass the filter of the session.
+    pub fn get_devices(&self) -> Result<Vec<BluetoothDevice>, BluetoothError> {
+        let devices = self.adapter.get_devices()?;
+        Ok(devices.into_iter().filter(|device| self.filter.matches(device)).collect())
     }
+}
 
-    pub fn stop_discovery(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDiscoverySession, stop_discovery)
+impl Drop for BluetoothDiscoverySession {
+    fn drop(&mut self) {
+        let _ = self.stop_discovery();
     }
 }
 
 impl BluetoothDevice {
+    pub fn from_backend(backend: Arc<dyn BluetoothDeviceBackend>) -> BluetoothDevice {
+        BluetoothDevice(backend, Arc::default())
+    }
+
+    pub(crate) fn new(backend: Arc<dyn BluetoothDeviceBackend>, timeouts: Arc<TimeoutSettings>) -> BluetoothDevice {
+        BluetoothDevice(backend, timeouts)
+    }
+
+    /// Returns a handle on the same device whose blocking operations, and the ones of the
+    /// attributes obtained from it, use `timeouts` instead of the adapter's.
+    pub fn with_operation_timeouts(&self, timeouts: OperationTimeouts) -> BluetoothDevice {
+        BluetoothDevice(self.0.clone(), Arc::new(TimeoutSettings::new(timeouts)))
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    fn mock(&self) -> Result<&Arc<FakeBluetoothDevice>, BluetoothError> {
+        self.0.as_mock().ok_or(BluetoothError::NotSupported)
+    }
 
     pub fn get_id(&self) -> String {
-        get_inner_and_call!(self, BluetoothDevice, get_id)
+        self.0.get_id()
     }
 
     #[cfg(feature = "bluetooth-test")]
     pub fn set_id(&self, id: String) {
-        match self {
-            &BluetoothDevice::Mock(ref fake_adapter) => fake_adapter.set_id(id),
-            _ => (),
+        if let Some(fake_device) = self.0.as_mock() {
+            fake_device.set_id(id);
         }
     }
 
-    pub fn get_address(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_address)
+    pub fn get_address(&self) -> Result<String, BluetoothError> {
+        self.0.get_address()
+    }
+
+    #[cfg(feature = "bluetooth-test")]
+    pub fn set_address(&self, address: String) -> Result<(), BluetoothError> {
+        self.mock()?.set_address(address).map_err(mock::map_error)
+    }
+
+    /// Only BlueZ and the mock know the address type; on Android and macOS this fails with
+    /// `NotSupported`.
+    pub fn get_address_type(&self) -> Result<AddressType, BluetoothError> {
+        self.0.get_address_type()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_address(&self, address: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_address, address)
+    pub fn set_address_type(&self, address_type: AddressType) -> Result<(), BluetoothError> {
+        mock::set_address_type(self.mock()?, address_type);
+        Ok(())
     }
 
-    pub fn get_name(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_name)
+    pub fn get_name(&self) -> Result<String, BluetoothError> {
+        self.0.get_name()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_name(&self, name: Option<String>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_name, name)
+    pub fn set_name(&self, name: Option<String>) -> Result<(), BluetoothError> {
+        let fake_device = self.mock()?;
+        fake_device.set_name(name).map_err(mock::map_error)?;
+        mock::device_updated(fake_device, DeviceProperty::Name);
+        Ok(())
     }
 
-    pub fn get_icon(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_icon)
+    pub fn get_icon(&self) -> Result<String, BluetoothError> {
+        self.0.get_icon()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_icon(&self, icon: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_icon, icon)
+    pub fn set_icon(&self, icon: String) -> Result<(), BluetoothError> {
+        self.mock()?.set_icon(icon).map_err(mock::map_error)
     }
 
-    pub fn get_class(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_class)
+    pub fn get_class(&self) -> Result<ClassOfDevice, BluetoothError> {
+        self.0.get_class().map(ClassOfDevice::from)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_class(&self, class: u32) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_class, class)
+    pub fn set_class(&self, class: ClassOfDevice) -> Result<(), BluetoothError> {
+        self.mock()?.set_class(class.into()).map_err(mock::map_error)
     }
 
-    pub fn get_appearance(&self) -> Result<u16, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_appearance)
+    pub fn get_appearance(&self) -> Result<Appearance, BluetoothError> {
+        self.0.get_appearance().map(Appearance::from)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_appearance(&self, appearance: u16) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_appearance, Some(appearance))
+    pub fn set_appearance(&self, appearance: Appearance) -> Result<(), BluetoothError> {
+        self.mock()?.set_appearance(Some(appearance.into())).map_err(mock::map_error)
     }
 
-    pub fn get_uuids(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_uuids)
+    pub fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        self.0.get_uuids()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_uuids(&self, uuids: Vec<String>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_uuids, uuids)
+    pub fn set_uuids(&self, uuids: Vec<BluetoothUuid>) -> Result<(), BluetoothError> {
+        let fake_device = self.mock()?;
+        fake_device.set_uuids(uuids.iter().map(BluetoothUuid::to_string).collect()).map_err(mock::map_error)?;
+        mock::device_updated(fake_device, DeviceProperty::Uuids);
+        Ok(())
     }
 
-    pub fn is_paired(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, is_paired)
+    pub fn is_paired(&self) -> Result<bool, BluetoothError> {
+        self.0.is_paired()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_paired(&self, paired: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_paired, paired)
+    pub fn set_paired(&self, paired: bool) -> Result<(), BluetoothError> {
+        mock::set_paired(self.mock()?, paired)
     }
 
-    pub fn is_connected(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, is_connected)
+    /// Removes the bond with the device. BlueZ forgets the device too, see
+    /// `BluetoothAdapter::remove_device`.
+    pub fn unpair(&self) -> Result<(), BluetoothError> {
+        self.0.unpair()
+    }
+
+    pub fn is_connected(&self) -> Result<bool, BluetoothError> {
+        self.0.is_connected()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_connected(&self, connected: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_connected, connected)
+    pub fn set_connected(&self, connected: bool) -> Result<(), BluetoothError> {
+        mock::set_connected(self.mock()?, connected)
     }
 
+    /// Simulates an unresponsive device: the operations which reach it (connecting, the
+    /// attribute discovery, reads and writes, ...), on it or on its attributes, block until
+    /// `set_hung(false)` is called.
     #[cfg(feature = "bluetooth-test")]
-    pub fn is_connectable(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, is_connectable)
+    pub fn set_hung(&self, hung: bool) -> Result<(), BluetoothError> {
+        mock::set_hung(self.mock()?, hung);
+        Ok(())
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_connectable(&self, connectable: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_connectable, connectable)
+    pub fn set_mtu(&self, mtu: u16) -> Result<(), BluetoothError> {
+        mock::set_mtu(self.mock()?, mtu);
+        Ok(())
     }
 
-    pub fn is_trusted(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, is_trusted)
+    /// Sets how the mock device authenticates the next pairings.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn set_pairing_method(&self, method: PairingMethod) -> Result<(), BluetoothError> {
+        mock::set_pairing_method(self.mock()?, method);
+        Ok(())
     }
 
+    /// Makes the next pairings with the mock device fail with `error`, or succeed again.
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_trusted(&self, trusted: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_trusted, trusted)
+    pub fn set_pairing_error(&self, error: Option<BluetoothError>) -> Result<(), BluetoothError> {
+        mock::set_pairing_error(self.mock()?, error);
+        Ok(())
     }
 
-    pub fn is_blocked(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, is_blocked)
+    #[cfg(feature = "bluetooth-test")]
+    pub fn is_connectable(&self) -> Result<bool, BluetoothError> {
+        self.mock()?.is_connectable().map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_blocked(&self, blocked: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_blocked, blocked)
+    pub fn set_connectable(&self, connectable: bool) -> Result<(), BluetoothError> {
+        self.mock()?.set_connectable(connectable).map_err(mock::map_error)
+    }
+
+    pub fn is_trusted(&self) -> Result<bool, BluetoothError> {
+        self.0.is_trusted()
     }
 
-    pub fn get_alias(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_alias)
+    /// Trusted devices connect without asking the pairing agent for an authorization.
+    pub fn set_trusted(&self, trusted: bool) -> Result<(), BluetoothError> {
+        self.0.set_trusted(trusted)
+    }
+
+    pub fn is_blocked(&self) -> Result<bool, BluetoothError> {
+        self.0.is_blocked()
+    }
+
+    /// Blocking a device disconnects it, and rejects its connections until it is unblocked.
+    pub fn set_blocked(&self, blocked: bool) -> Result<(), BluetoothError> {
+        self.0.set_blocked(blocked)
+    }
+
+    pub fn get_alias(&self) -> Result<String, BluetoothError> {
+        self.0.get_alias()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_alias(&self, alias: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_alias, alias)
+    pub fn set_alias(&self, alias: String) -> Result<(), BluetoothError> {
+        self.mock()?.set_alias(alias).map_err(mock::map_error)
     }
 
-    pub fn is_legacy_pairing(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, is_legacy_pairing)
+    pub fn is_legacy_pairing(&self) -> Result<bool, BluetoothError> {
+        self.0.is_legacy_pairing()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_legacy_pairing(&self, legacy_pairing: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_legacy_pairing, legacy_pairing)
+    pub fn set_legacy_pairing(&self, legacy_pairing: bool) -> Result<(), BluetoothError> {
+        self.mock()?.set_legacy_pairing(legacy_pairing).map_err(mock::map_error)
     }
 
-    pub fn get_vendor_id_source(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_vendor_id_source)
+    pub fn get_vendor_id_source(&self) -> Result<VendorIdSource, BluetoothError> {
+        Ok(self.get_modalias()?.source)
     }
 
-    pub fn get_vendor_id(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_vendor_id)
+    pub fn get_vendor_id(&self) -> Result<u16, BluetoothError> {
+        Ok(self.get_modalias()?.vendor_id)
     }
 
-    pub fn get_product_id(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_product_id)
+    pub fn get_product_id(&self) -> Result<u16, BluetoothError> {
+        Ok(self.get_modalias()?.product_id)
     }
 
-    pub fn get_device_id(&self) -> Result<u32, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_device_id)
+    /// The release number of the product, `DeviceId::version`.
+    pub fn get_device_id(&self) -> Result<u16, BluetoothError> {
+        Ok(self.get_modalias()?.version)
     }
 
-    pub fn get_modalias(&self) -> Result<(String, u32, u32, u32), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_modalias)
+    /// Fails with `NotSupported` if there is no Device ID.
+    pub fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        self.0.get_modalias()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_modalias(&self, modalias: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_modalias, modalias)
+    pub fn set_modalias(&self, modalias: DeviceId) -> Result<(), BluetoothError> {
+        mock::set_modalias(self.mock()?, modalias);
+        Ok(())
     }
 
-    pub fn get_rssi(&self) -> Result<i16, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_rssi)
+    pub fn get_rssi(&self) -> Result<i16, BluetoothError> {
+        self.0.get_rssi()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_rssi(&self, rssi: i16) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_rssi, Some(rssi))
+    pub fn set_rssi(&self, rssi: i16) -> Result<(), BluetoothError> {
+        let fake_device = self.mock()?;
+        fake_device.set_rssi(Some(rssi)).map_err(mock::map_error)?;
+        mock::device_updated(fake_device, DeviceProperty::Rssi);
+        Ok(())
     }
 
-    pub fn get_tx_power(&self) -> Result<i16, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_tx_power)
+    pub fn get_tx_power(&self) -> Result<i16, BluetoothError> {
+        self.0.get_tx_power()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_tx_power(&self, tx_power: i16) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_tx_power, Some(tx_power))
+    pub fn set_tx_power(&self, tx_power: i16) -> Result<(), BluetoothError> {
+        let fake_device = self.mock()?;
+        fake_device.set_tx_power(Some(tx_power)).map_err(mock::map_error)?;
+        mock::device_updated(fake_device, DeviceProperty::TxPower);
+        Ok(())
     }
 
-    pub fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_manufacturer_data)
+    pub fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError> {
+        self.0.get_manufacturer_data()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_manufacturer_data(&self, manufacturer_data: HashMap<u16, Vec<u8>>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_manufacturer_data, Some(manufacturer_data))
+    pub fn set_manufacturer_data(&self, manufacturer_data: HashMap<u16, Vec<u8>>) -> Result<(), BluetoothError> {
+        let fake_device = self.mock()?;
+        fake_device.set_manufacturer_data(Some(manufacturer_data)).map_err(mock::map_error)?;
+        mock::device_updated(fake_device, DeviceProperty::ManufacturerData);
+        Ok(())
     }
 
-    pub fn get_service_data(&self) -> Result<HashMap<String, Vec<u8>>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, get_service_data)
+    pub fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError> {
+        self.0.get_service_data()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_service_data(&self, service_data: HashMap<String, Vec<u8>>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothDevice, set_service_data, Some(service_data))
+    pub fn set_service_data(&self, service_data: HashMap<BluetoothUuid, Vec<u8>>) -> Result<(), BluetoothError> {
+        let service_data = service_data.into_iter().map(|(uuid, data)| (uuid.to_string(), data)).collect();
+        let fake_device = self.mock()?;
+        fake_device.set_service_data(Some(service_data)).map_err(mock::map_error)?;
+        mock::device_updated(fake_device, DeviceProperty::ServiceData);
+        Ok(())
+    }
+
+    pub fn get_gatt_services(&self) -> Result<Vec<BluetoothGATTService>, BluetoothError> {
+        let services = future::run_with_timeout(self.1.get().gatt, || self.0.get_gatt_services(), || self.0.get_gatt_services_async())?;
+        Ok(services.into_iter().map(|service| BluetoothGATTService::new(service, self.1.clone())).collect())
+    }
+
+    pub fn get_gatt_services_async(&self) -> BluetoothFuture<Vec<BluetoothGATTService>> {
+        let timeouts = self.1.clone();
+        self.0.get_gatt_services_async().map(move |services| {
+            services.into_iter().map(|service| BluetoothGATTService::new(service, timeouts.clone())).collect()
+        })
+    }
+
+    pub fn connect(&self) -> Result<(), BluetoothError> {
+        future::run_with_timeout(self.1.get().connect, || self.0.connect(), || self.0.connect_async())
+    }
+
+    pub fn connect_async(&self) -> BluetoothFuture<()> {
+        future::start_async(self.1.get().connect, || self.0.connect(), || self.0.connect_async())
+    }
+
+    pub fn disconnect(&self) -> Result<(), BluetoothError> {
+        future::run_with_timeout(self.1.get().connect, || self.0.disconnect(), || self.0.disconnect_async())
+    }
+
+    pub fn disconnect_async(&self) -> BluetoothFuture<()> {
+        future::start_async(self.1.get().connect, || self.0.disconnect(), || self.0.disconnect_async())
+    }
+
+    pub fn connect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        self.0.connect_profile(uuid)
+    }
+
+    pub fn disconnect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        self.0.disconnect_profile(uuid)
+    }
+
+    pub fn pair(&self) -> Result<(), BluetoothError> {
+        future::run_with_timeout(self.1.get().connect, || self.0.pair(), || self.0.pair_async())
     }
 
-    pub fn get_gatt_services(&self) -> Result<Vec<BluetoothGATTService>, Box<dyn Error>> {
-        let services = get_inner_and_call!(self, BluetoothDevice, get_gatt_services)?;
-        Ok(services.into_iter().map(|service| BluetoothGATTService::create_service(self.clone(), service)).collect())
+    pub fn pair_async(&self) -> BluetoothFuture<()> {
+        future::start_async(self.1.get().connect, || self.0.pair(), || self.0.pair_async())
     }
 
-    pub fn connect(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, connect)
+    pub fn cancel_pairing(&self) -> Result<(), BluetoothError> {
+        self.0.cancel_pairing()
     }
 
-    pub fn disconnect(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, disconnect)
+    /// The ATT MTU of the connection. A write without response carries at most `MTU - 3` bytes.
+    ///
+    /// Supported on Linux, macOS and by the mock backend, and fails with `NotSupported` on
+    /// Android. On Linux the device must be connected with its services resolved, since BlueZ
+    /// exposes the MTU on the characteristics; before that it fails with `NotConnected`.
+    pub fn get_mtu(&self) -> Result<u16, BluetoothError> {
+        self.0.get_mtu()
     }
 
-    pub fn connect_profile(&self, uuid: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, connect_profile, uuid)
+    /// Negotiates an ATT MTU of at most `mtu` with the connected device, and returns the
+    /// resulting one.
+    ///
+    /// Only the mock backend supports it; the others fail with `NotSupported`. BlueZ negotiates
+    /// the MTU by itself when connecting.
+    pub fn request_mtu(&self, mtu: u16) -> Result<u16, BluetoothError> {
+        self.0.request_mtu(mtu)
     }
 
-    pub fn disconnect_profile(&self, uuid: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, disconnect_profile, uuid)
+    /// Asks the connected device to switch to the parameters of `priority`. The device has the
+    /// final say, see `get_connection_parameters`.
+    pub fn request_connection_priority(&self, priority: ConnectionPriority) -> Result<(), BluetoothError> {
+        self.0.request_connection_priority(priority)
     }
 
-    pub fn pair(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, pair)
+    /// The parameters the connection currently uses.
+    pub fn get_connection_parameters(&self) -> Result<ConnectionParameters, BluetoothError> {
+        self.0.get_connection_parameters()
     }
 
-    pub fn cancel_pairing(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothDevice, cancel_pairing)
+    pub fn set_preferred_phy(&self, phy: Phy) -> Result<(), BluetoothError> {
+        self.0.set_preferred_phy(phy)
+    }
+
+    pub fn get_phy(&self) -> Result<Phy, BluetoothError> {
+        self.0.get_phy()
+    }
+
+    /// The last advertisement received from the device, with its scan response. Backends which
+    /// only get the decoded advertisement rebuild the AD structures they know of.
+    pub fn get_advertisement_data(&self) -> Result<AdvertisementData, BluetoothError> {
+        AdvertisementData::parse(&self.0.get_advertisement_data()?)
+    }
+
+    /// Receives an advertisement, given as AD structures, and updates the properties it carries.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn set_advertisement_data(&self, raw: Vec<u8>) -> Result<(), BluetoothError> {
+        let advertisement = AdvertisementData::parse(&raw)?;
+        mock::set_advertisement_data(self.mock()?, raw);
+        if let Some(name) = advertisement.local_name {
+            self.set_name(Some(name))?;
+        }
+        if let Some(tx_power) = advertisement.tx_power {
+            self.set_tx_power(tx_power as i16)?;
+        }
+        if let Some(appearance) = advertisement.appearance {
+            self.set_appearance(appearance)?;
+        }
+        if let Some(class) = advertisement.class_of_device {
+            self.set_class(class)?;
+        }
+        if !advertisement.service_uuids.is_empty() {
+            self.set_uuids(advertisement.service_uuids)?;
+        }
+        if !advertisement.manufacturer_data.is_empty() {
+            self.set_manufacturer_data(advertisement.manufacturer_data)?;
+        }
+        if !advertisement.service_data.is_empty() {
+            self.set_service_data(advertisement.service_data)?;
+        }
+        Ok(())
     }
 }
 
 impl BluetoothGATTService {
-    fn create_service(device: BluetoothDevice, service: String) -> BluetoothGATTService {
-        match device {
-            #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-            BluetoothDevice::Bluez(_bluez_device) => {
-                BluetoothGATTService::Bluez(Arc::new(BluetoothGATTServiceBluez::new(service)))
-            },
-            #[cfg(all(target_os = "android", feature = "bluetooth"))]
-            BluetoothDevice::Android(android_device) => {
-                BluetoothGATTService::Android(Arc::new(BluetoothGATTServiceAndroid::new(android_device, service)))
-            },
-            #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-            BluetoothDevice::Mac(mac_device) => {
-                BluetoothGATTService::Mac(Arc::new(BluetoothGATTServiceMac::new(mac_device, service)))
-            },
-            #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                          all(target_os = "android", feature = "bluetooth"),
-                          all(target_os = "macos", feature = "bluetooth"))))]
-            BluetoothDevice::Empty(_device) => {
-                BluetoothGATTService::Empty(Arc::new(BluetoothGATTServiceEmpty::new(service)))
-            },
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothDevice::Mock(fake_device) => {
-                BluetoothGATTService::Mock(FakeBluetoothGATTService::new_empty(fake_device, service))
-            },
-        }
+    pub fn from_backend(backend: Arc<dyn BluetoothGATTServiceBackend>) -> BluetoothGATTService {
+        BluetoothGATTService(backend, Arc::default())
+    }
+
+    pub(crate) fn new(backend: Arc<dyn BluetoothGATTServiceBackend>, timeouts: Arc<TimeoutSettings>) -> BluetoothGATTService {
+        BluetoothGATTService(backend, timeouts)
+    }
+
+    /// Returns a handle on the same service whose blocking operations, and the ones of the
+    /// attributes obtained from it, use `timeouts` instead of the adapter's.
+    pub fn with_operation_timeouts(&self, timeouts: OperationTimeouts) -> BluetoothGATTService {
+        BluetoothGATTService(self.0.clone(), Arc::new(TimeoutSettings::new(timeouts)))
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn create_mock_service(device: BluetoothDevice, service: String) -> Result<BluetoothGATTService, Box<dyn Error>> {
-        match device {
-            BluetoothDevice::Mock(fake_device) => {
-                Ok(BluetoothGATTService::Mock(FakeBluetoothGATTService::new_empty(fake_device, service)))
-            },
-            _ => {
-                Err(Box::from("Error! The first parameter must be a mock structure!"))
+    pub fn create_mock_service(device: BluetoothDevice, service: String) -> Result<BluetoothGATTService, BluetoothError> {
+        match device.0.as_mock() {
+            Some(fake_device) => {
+                let fake_service = FakeBluetoothGATTService::new_empty(fake_device.clone(), service);
+                Ok(BluetoothGATTService::new(Arc::new(fake_service), device.1.clone()))
             },
+            None => Err(BluetoothError::NotSupported),
         }
     }
 
+    #[cfg(feature = "bluetooth-test")]
+    fn mock(&self) -> Result<&Arc<FakeBluetoothGATTService>, BluetoothError> {
+        self.0.as_mock().ok_or(BluetoothError::NotSupported)
+    }
+
     pub fn get_id(&self) -> String {
-        get_inner_and_call!(self, BluetoothGATTService, get_id)
+        self.0.get_id()
     }
 
     #[cfg(feature = "bluetooth-test")]
     pub fn set_id(&self, id: String) {
-        match self {
-            &BluetoothGATTService::Mock(ref fake_service) => fake_service.set_id(id),
-            _ => (),
+        if let Some(fake_service) = self.0.as_mock() {
+            fake_service.set_id(id);
         }
     }
 
-    pub fn get_uuid(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTService, get_uuid)
+    pub fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        self.0.get_uuid()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_uuid(&self, uuid: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTService, set_uuid, uuid)
+    pub fn set_uuid(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        self.mock()?.set_uuid(uuid.to_string()).map_err(mock::map_error)
+    }
+
+    pub fn is_primary(&self) -> Result<bool, BluetoothError> {
+        self.0.is_primary()
     }
 
-    pub fn is_primary(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTService, is_primary)
+    #[cfg(feature = "bluetooth-test")]
+    pub fn set_primary(&self, primary: bool) -> Result<(), BluetoothError> {
+        self.mock()?.set_is_primary(primary).map_err(mock::map_error)
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_primary(&self, primary: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTService, set_is_primary, primary)
+    pub fn add_included_service(&self, service: &BluetoothGATTService) -> Result<(), BluetoothError> {
+        self.mock()?.add_included_service(service.mock()?.clone()).map_err(mock::map_error)
+    }
+
+    pub fn get_includes(&self) -> Result<Vec<BluetoothGATTService>, BluetoothError> {
+        let services = future::run_with_timeout(self.1.get().gatt, || self.0.get_includes(), || self.0.get_includes_async())?;
+        Ok(services.into_iter().map(|service| BluetoothGATTService::new(service, self.1.clone())).collect())
+    }
+
+    pub fn get_includes_async(&self) -> BluetoothFuture<Vec<BluetoothGATTService>> {
+        let timeouts = self.1.clone();
+        self.0.get_includes_async().map(move |services| {
+            services.into_iter().map(|service| BluetoothGATTService::new(service, timeouts.clone())).collect()
+        })
     }
 
-    pub fn get_includes(&self, device: BluetoothDevice) -> Result<Vec<BluetoothGATTService>, Box<dyn Error>> {
-        let services = get_inner_and_call!(self, BluetoothGATTService, get_includes)?;
-        Ok(services.into_iter().map(|service| BluetoothGATTService::create_service(device.clone(), service)).collect())
+    pub fn get_gatt_characteristics(&self) -> Result<Vec<BluetoothGATTCharacteristic>, BluetoothError> {
+        let characteristics = future::run_with_timeout(self.1.get().gatt, || self.0.get_gatt_characteristics(), || self.0.get_gatt_characteristics_async())?;
+        Ok(characteristics.into_iter().map(|characteristic| BluetoothGATTCharacteristic::new(characteristic, self.1.clone())).collect())
     }
 
-    pub fn get_gatt_characteristics(&self) -> Result<Vec<BluetoothGATTCharacteristic>, Box<dyn Error>> {
-        let characteristics = get_inner_and_call!(self, BluetoothGATTService, get_gatt_characteristics)?;
-        Ok(characteristics.into_iter()
-                          .map(|characteristic|
-                              BluetoothGATTCharacteristic::create_characteristic(self.clone(), characteristic))
-                          .collect())
+    pub fn get_gatt_characteristics_async(&self) -> BluetoothFuture<Vec<BluetoothGATTCharacteristic>> {
+        let timeouts = self.1.clone();
+        self.0.get_gatt_characteristics_async().map(move |characteristics| {
+            characteristics.into_iter().map(|characteristic| BluetoothGATTCharacteristic::new(characteristic, timeouts.clone())).collect()
+        })
     }
 }
 
 impl BluetoothGATTCharacteristic {
-    fn create_characteristic(service: BluetoothGATTService, characteristic: String) -> BluetoothGATTCharacteristic {
-        match service {
-            #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-            BluetoothGATTService::Bluez(_bluez_service) => {
-                BluetoothGATTCharacteristic::Bluez(Arc::new(BluetoothGATTCharacteristicBluez::new(characteristic)))
-            },
-            #[cfg(all(target_os = "android", feature = "bluetooth"))]
-            BluetoothGATTService::Android(android_service) => {
-                BluetoothGATTCharacteristic::Android(
-                    Arc::new(BluetoothGATTCharacteristicAndroid::new(android_service, characteristic)))
-            },
-            #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-            BluetoothGATTService::Mac(mac_service) => {
-                BluetoothGATTCharacteristic::Mac(Arc::new(BluetoothGATTCharacteristicMac::new(mac_service, characteristic)))
-            },
-            #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                          all(target_os = "android", feature = "bluetooth"),
-                          all(target_os = "macos", feature = "bluetooth"))))]
-            BluetoothGATTService::Empty(_service) => {
-                BluetoothGATTCharacteristic::Empty(Arc::new(BluetoothGATTCharacteristicEmpty::new(characteristic)))
-            },
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothGATTService::Mock(fake_service) => {
-                BluetoothGATTCharacteristic::Mock(
-                    FakeBluetoothGATTCharacteristic::new_empty(fake_service, characteristic))
-            },
-        }
+    pub fn from_backend(backend: Arc<dyn BluetoothGATTCharacteristicBackend>) -> BluetoothGATTCharacteristic {
+        BluetoothGATTCharacteristic(backend, Arc::default())
+    }
+
+    pub(crate) fn new(backend: Arc<dyn BluetoothGATTCharacteristicBackend>, timeouts: Arc<TimeoutSettings>) -> BluetoothGATTCharacteristic {
+        BluetoothGATTCharacteristic(backend, timeouts)
+    }
+
+    /// Returns a handle on the same characteristic whose blocking operations, and the ones of the
+    /// descriptors obtained from it, use `timeouts` instead of the adapter's.
+    pub fn with_operation_timeouts(&self, timeouts: OperationTimeouts) -> BluetoothGATTCharacteristic {
+        BluetoothGATTCharacteristic(self.0.clone(), Arc::new(TimeoutSettings::new(timeouts)))
     }
 
     #[cfg(feature = "bluetooth-test")]
     pub fn create_mock_characteristic(service: BluetoothGATTService,
                                       characteristic: String)
-                                      -> Result<BluetoothGATTCharacteristic, Box<dyn Error>> {
-        match service {
-            BluetoothGATTService::Mock(fake_service) => {
-                Ok(BluetoothGATTCharacteristic::Mock(
-                    FakeBluetoothGATTCharacteristic::new_empty(fake_service, characteristic)))
-            },
-            _ => {
-                Err(Box::from("Error! The first parameter must be a mock structure!"))
+                                      -> Result<BluetoothGATTCharacteristic, BluetoothError> {
+        match service.0.as_mock() {
+            Some(fake_service) => {
+                let fake_characteristic =
+                    FakeBluetoothGATTCharacteristic::new_empty(fake_service.clone(), characteristic);
+                Ok(BluetoothGATTCharacteristic::new(Arc::new(fake_characteristic), service.1.clone()))
             },
+            None => Err(BluetoothError::NotSupported),
         }
     }
 
+    #[cfg(feature = "bluetooth-test")]
+    fn mock(&self) -> Result<&Arc<FakeBluetoothGATTCharacteristic>, BluetoothError> {
+        self.0.as_mock().ok_or(BluetoothError::NotSupported)
+    }
+
     pub fn get_id(&self) -> String {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, get_id)
+        self.0.get_id()
     }
 
     #[cfg(feature = "bluetooth-test")]
     pub fn set_id(&self, id: String) {
-        match self {
-            &BluetoothGATTCharacteristic::Mock(ref fake_characteristic) => fake_characteristic.set_id(id),
-            _ => (),
+        if let Some(fake_characteristic) = self.0.as_mock() {
+            fake_characteristic.set_id(id);
         }
     }
 
-    pub fn get_uuid(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, get_uuid)
+    pub fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        self.0.get_uuid()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_uuid(&self, uuid: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTCharacteristic, set_uuid, uuid)
+    pub fn set_uuid(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        self.mock()?.set_uuid(uuid.to_string()).map_err(mock::map_error)
     }
 
-    pub fn get_value(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, get_value)
+    pub fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        self.0.get_value()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_value(&self, value: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTCharacteristic, set_value, Some(value))
+    pub fn set_value(&self, value: Vec<u8>) -> Result<(), BluetoothError> {
+        self.mock()?.set_value(Some(value)).map_err(mock::map_error)
     }
 
-    pub fn is_notifying(&self) -> Result<bool, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, is_notifying)
+    pub fn is_notifying(&self) -> Result<bool, BluetoothError> {
+        self.0.is_notifying()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_notifying(&self, notifying: bool) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTCharacteristic, set_notifying, notifying)
+    pub fn set_notifying(&self, notifying: bool) -> Result<(), BluetoothError> {
+        let fake_characteristic = self.mock()?;
+        fake_characteristic.set_notifying(notifying).map_err(mock::map_error)?;
+        if !notifying {
+            mock::stop_notifications(fake_characteristic);
+        }
+        Ok(())
     }
 
-    pub fn get_flags(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, get_flags)
+    /// Simulates a notification: updates the value and delivers it to the subscriptions,
+    /// if the characteristic is notifying.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn notify_value(&self, value: Vec<u8>) -> Result<(), BluetoothError> {
+        mock::notify_value(self.mock()?, value)
+    }
+
+    pub fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        self.0.get_flags()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_flags(&self, flags: Vec<String>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTCharacteristic, set_flags, flags)
+    pub fn set_flags(&self, flags: Vec<String>) -> Result<(), BluetoothError> {
+        self.mock()?.set_flags(flags).map_err(mock::map_error)
     }
 
-    pub fn get_gatt_descriptors(&self) -> Result<Vec<BluetoothGATTDescriptor>, Box<dyn Error>> {
-        let descriptors = get_inner_and_call!(self, BluetoothGATTCharacteristic, get_gatt_descriptors)?;
-        Ok(descriptors.into_iter()
-                      .map(|descriptor| BluetoothGATTDescriptor::create_descriptor(self.clone(), descriptor))
-                      .collect())
+    /// Sets the longest value the mock attribute accepts, or the ATT maximum of 512 bytes if
+    /// `None`. Longer writes fail with `AttError::InvalidAttributeValueLength`.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn set_max_value_length(&self, max_value_length: Option<u16>) -> Result<(), BluetoothError> {
+        mock::set_characteristic_max_value_length(self.mock()?, max_value_length);
+        Ok(())
     }
 
-    pub fn read_value(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        get_inner_and_call!(@with_bluez_offset, self, BluetoothGATTCharacteristic, read_value)
+    pub fn get_gatt_descriptors(&self) -> Result<Vec<BluetoothGATTDescriptor>, BluetoothError> {
+        let descriptors = future::run_with_timeout(self.1.get().gatt, || self.0.get_gatt_descriptors(), || self.0.get_gatt_descriptors_async())?;
+        Ok(descriptors.into_iter().map(|descriptor| BluetoothGATTDescriptor::new(descriptor, self.1.clone())).collect())
     }
 
-    pub fn write_value(&self, values: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(@with_bluez_offset, self, BluetoothGATTCharacteristic, write_value, values)
+    pub fn get_gatt_descriptors_async(&self) -> BluetoothFuture<Vec<BluetoothGATTDescriptor>> {
+        let timeouts = self.1.clone();
+        self.0.get_gatt_descriptors_async().map(move |descriptors| {
+            descriptors.into_iter().map(|descriptor| BluetoothGATTDescriptor::new(descriptor, timeouts.clone())).collect()
+        })
     }
 
-    pub fn start_notify(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, start_notify)
+    /// Reads the value, with as many requests as its length takes. The GATT timeout bounds
+    /// the whole read.
+    pub fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        future::run_with_timeout(self.1.get().gatt, || self.0.read_value(), || self.0.read_value_async())
     }
 
-    pub fn stop_notify(&self) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTCharacteristic, stop_notify)
+    pub fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        future::start_async(self.1.get().gatt, || self.0.read_value(), || self.0.read_value_async())
     }
-}
 
-impl BluetoothGATTDescriptor {
-    fn create_descriptor(characteristic: BluetoothGATTCharacteristic, descriptor: String) -> BluetoothGATTDescriptor {
-        match characteristic {
-            #[cfg(all(target_os = "linux", feature = "bluetooth"))]
-            BluetoothGATTCharacteristic::Bluez(_bluez_characteristic) => {
-                BluetoothGATTDescriptor::Bluez(Arc::new(BluetoothGATTDescriptorBluez::new(descriptor)))
-            },
-            #[cfg(all(target_os = "android", feature = "bluetooth"))]
-            BluetoothGATTCharacteristic::Android(android_characteristic) => {
-                BluetoothGATTDescriptor::Android(
-                    Arc::new(BluetoothGATTDescriptorAndroid::new(android_characteristic, descriptor)))
-            },
-            #[cfg(all(target_os = "macos", feature = "bluetooth"))]
-            BluetoothGATTCharacteristic::Mac(_mac_characteristic) => {
-                BluetoothGATTDescriptor::Mac(Arc::new(BluetoothGATTDescriptorMac::new(descriptor)))
-            },
-            #[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-                          all(target_os = "android", feature = "bluetooth"),
-                          all(target_os = "macos", feature = "bluetooth"))))]
-            BluetoothGATTCharacteristic::Empty(_characteristic) => {
-                BluetoothGATTDescriptor::Empty(Arc::new(BluetoothGATTDescriptorEmpty::new(descriptor)))
-            },
-            #[cfg(feature = "bluetooth-test")]
-            BluetoothGATTCharacteristic::Mock(fake_characteristic) => {
-                BluetoothGATTDescriptor::Mock(FakeBluetoothGATTDescriptor::new_empty(fake_characteristic, descriptor))
+    /// Writes the value, with as many requests as its length takes: values longer than
+    /// `MTU - 3` bytes are long attributes. The GATT timeout bounds the whole write.
+    pub fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        let async_values = values.clone();
+        future::run_with_timeout(self.1.get().gatt, move || self.0.write_value(values), move || self.0.write_value_async(async_values))
+    }
+
+    pub fn write_value_async(&self, values: Vec<u8>) -> BluetoothFuture<()> {
+        let async_values = values.clone();
+        future::start_async(self.1.get().gatt, move || self.0.write_value(values), move || self.0.write_value_async(async_values))
+    }
+
+    /// Reads the value from `offset` on, with as many requests as its length takes.
+    pub fn read_value_at(&self, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        future::run_with_timeout(self.1.get().gatt, || self.0.read_value_at(offset), || self.0.read_value_at_async(offset))
+    }
+
+    pub fn read_value_at_async(&self, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        future::start_async(self.1.get().gatt, || self.0.read_value_at(offset), || self.0.read_value_at_async(offset))
+    }
+
+    /// Writes `values` over the value from `offset` on, with as many requests as their length
+    /// takes.
+    pub fn write_value_at(&self, offset: u16, values: Vec<u8>) -> Result<(), BluetoothError> {
+        let async_values = values.clone();
+        future::run_with_timeout(self.1.get().gatt, move || self.0.write_value_at(offset, values), move || self.0.write_value_at_async(offset, async_values))
+    }
+
+    pub fn write_value_at_async(&self, offset: u16, values: Vec<u8>) -> BluetoothFuture<()> {
+        let async_values = values.clone();
+        future::start_async(self.1.get().gatt, move || self.0.write_value_at(offset, values), move || self.0.write_value_at_async(offset, async_values))
+    }
+
+    /// Writes with the given procedure. Fails with `NotSupported` if the characteristic's flags
+    /// do not permit it, see `WriteType::flag`, or if the backend cannot use it.
+    pub fn write_value_with_type(&self, values: Vec<u8>, write_type: WriteType) -> Result<(), BluetoothError> {
+        self.check_write_type(write_type)?;
+        let async_values = values.clone();
+        future::run_with_timeout(self.1.get().gatt, move || self.0.write_value_with_type(values, write_type), move || self.0.write_value_with_type_async(async_values, write_type))
+    }
+
+    pub fn write_value_with_type_async(&self, values: Vec<u8>, write_type: WriteType) -> BluetoothFuture<()> {
+        match self.check_write_type(write_type) {
+            Ok(()) => {
+                let async_values = values.clone();
+                future::start_async(self.1.get().gatt,
+                                    move || self.0.write_value_with_type(values, write_type),
+                                    move || self.0.write_value_with_type_async(async_values, write_type))
             },
+            Err(error) => BluetoothFuture::ready(Err(error)),
+        }
+    }
+
+    fn check_write_type(&self, write_type: WriteType) -> Result<(), BluetoothError> {
+        if self.0.get_flags()?.iter().any(|flag| flag == write_type.flag()) {
+            Ok(())
+        } else {
+            Err(BluetoothError::NotSupported)
         }
     }
 
+    /// Returns the write type of the last write, or `None` if it used `write_value`.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn get_last_write_type(&self) -> Result<Option<WriteType>, BluetoothError> {
+        Ok(mock::get_last_write_type(self.mock()?))
+    }
+
+    /// Returns how many ATT requests the mock characteristic received, counting every Read
+    /// Blob and Prepare Write request of the long values.
+    #[cfg(feature = "bluetooth-test")]
+    pub fn get_request_count(&self) -> Result<u32, BluetoothError> {
+        Ok(mock::get_request_count(self.mock()?))
+    }
+
+    pub fn start_notify(&self) -> Result<(), BluetoothError> {
+        future::run_with_timeout(self.1.get().gatt, || self.0.start_notify(), || self.0.start_notify_async())
+    }
+
+    pub fn start_notify_async(&self) -> BluetoothFuture<()> {
+        future::start_async(self.1.get().gatt, || self.0.start_notify(), || self.0.start_notify_async())
+    }
+
+    /// Starts the notifications and returns a subscription to the notified values.
+    pub fn subscribe(&self) -> Result<NotificationSubscription, BluetoothError> {
+        let subscription = self.0.subscribe()?;
+        self.start_notify()?;
+        Ok(subscription)
+    }
+
+    pub fn stop_notify(&self) -> Result<(), BluetoothError> {
+        future::run_with_timeout(self.1.get().gatt, || self.0.stop_notify(), || self.0.stop_notify_async())
+    }
+
+    pub fn stop_notify_async(&self) -> BluetoothFuture<()> {
+        future::start_async(self.1.get().gatt, || self.0.stop_notify(), || self.0.stop_notify_async())
+    }
+}
+
+impl BluetoothGATTDescriptor {
+    pub fn from_backend(backend: Arc<dyn BluetoothGATTDescriptorBackend>) -> BluetoothGATTDescriptor {
+        BluetoothGATTDescriptor(backend, Arc::default())
+    }
+
+    pub(crate) fn new(backend: Arc<dyn BluetoothGATTDescriptorBackend>, timeouts: Arc<TimeoutSettings>) -> BluetoothGATTDescriptor {
+        BluetoothGATTDescriptor(backend, timeouts)
+    }
+
+    /// Returns a handle on the same descriptor whose blocking operations use `timeouts` instead of
+    /// the adapter's.
+    pub fn with_operation_timeouts(&self, timeouts: OperationTimeouts) -> BluetoothGATTDescriptor {
+        BluetoothGATTDescriptor(self.0.clone(), Arc::new(TimeoutSettings::new(timeouts)))
+    }
+
     #[cfg(feature = "bluetooth-test")]
     pub fn create_mock_descriptor(characteristic: BluetoothGATTCharacteristic,
                                   descriptor: String)
-                                  -> Result<BluetoothGATTDescriptor, Box<dyn Error>> {
-        match characteristic {
-            BluetoothGATTCharacteristic::Mock(fake_characteristic) => {
-                Ok(BluetoothGATTDescriptor::Mock(
-                    FakeBluetoothGATTDescriptor::new_empty(fake_characteristic, descriptor)))
-            },
-            _ => {
-                Err(Box::from(NOT_SUPPORTED_ON_MOCK_ERROR))
+                                  -> Result<BluetoothGATTDescriptor, BluetoothError> {
+        match characteristic.0.as_mock() {
+            Some(fake_characteristic) => {
+                let fake_descriptor = FakeBluetoothGATTDescriptor::new_empty(fake_characteristic.clone(), descriptor);
+                Ok(BluetoothGATTDescriptor::new(Arc::new(fake_descriptor), characteristic.1.clone()))
             },
+            None => Err(BluetoothError::NotSupported),
         }
     }
 
+    #[cfg(feature = "bluetooth-test")]
+    fn mock(&self) -> Result<&Arc<FakeBluetoothGATTDescriptor>, BluetoothError> {
+        self.0.as_mock().ok_or(BluetoothError::NotSupported)
+    }
+
     pub fn get_id(&self) -> String {
-        get_inner_and_call!(self, BluetoothGATTDescriptor, get_id)
+        self.0.get_id()
     }
 
     #[cfg(feature = "bluetooth-test")]
     pub fn set_id(&self, id: String) {
-        match self {
-            &BluetoothGATTDescriptor::Mock(ref fake_descriptor) => fake_descriptor.set_id(id),
-            _ => (),
+        if let Some(fake_descriptor) = self.0.as_mock() {
+            fake_descriptor.set_id(id);
         }
     }
 
-    pub fn get_uuid(&self) -> Result<String, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTDescriptor, get_uuid)
+    pub fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        self.0.get_uuid()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_uuid(&self, uuid: String) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTDescriptor, set_uuid, uuid)
+    pub fn set_uuid(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        self.mock()?.set_uuid(uuid.to_string()).map_err(mock::map_error)
     }
 
-    pub fn get_value(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTDescriptor, get_value)
+    pub fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        self.0.get_value()
     }
 
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_value(&self, value: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTDescriptor, set_value, Some(value))
+    pub fn set_value(&self, value: Vec<u8>) -> Result<(), BluetoothError> {
+        self.mock()?.set_value(Some(value)).map_err(mock::map_error)
+    }
+
+    pub fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        self.0.get_flags()
     }
 
-    pub fn get_flags(&self) -> Result<Vec<String>, Box<dyn Error>> {
-        get_inner_and_call!(self, BluetoothGATTDescriptor, get_flags)
+    #[cfg(feature = "bluetooth-test")]
+    pub fn set_flags(&self, flags: Vec<String>) -> Result<(), BluetoothError> {
+        self.mock()?.set_flags(flags).map_err(mock::map_error)
     }
 
+    /// Sets the longest value the mock attribute accepts, or the ATT maximum of 512 bytes if
+    /// `None`. Longer writes fail with `AttError::InvalidAttributeValueLength`.
     #[cfg(feature = "bluetooth-test")]
-    pub fn set_flags(&self, flags: Vec<String>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call_test_func!(self, BluetoothGATTDescriptor, set_flags, flags)
+    pub fn set_max_value_length(&self, max_value_length: Option<u16>) -> Result<(), BluetoothError> {
+        mock::set_descriptor_max_value_length(self.mock()?, max_value_length);
+        Ok(())
+    }
+
+    /// Reads the value, with as many requests as its length takes. The GATT timeout bounds
+    /// the whole read.
+    pub fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        future::run_with_timeout(self.1.get().gatt, || self.0.read_value(), || self.0.read_value_async())
+    }
+
+    pub fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        future::start_async(self.1.get().gatt, || self.0.read_value(), || self.0.read_value_async())
+    }
+
+    /// Writes the value, with as many requests as its length takes: values longer than
+    /// `MTU - 3` bytes are long attributes. The GATT timeout bounds the whole write.
+    pub fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        let async_values = values.clone();
+        future::run_with_timeout(self.1.get().gatt, move || self.0.write_value(values), move || self.0.write_value_async(async_values))
+    }
+
+    pub fn write_value_async(&self, values: Vec<u8>) -> BluetoothFuture<()> {
+        let async_values = values.clone();
+        future::start_async(self.1.get().gatt, move || self.0.write_value(values), move || self.0.write_value_async(async_values))
+    }
+
+    /// Reads the value from `offset` on, with as many requests as its length takes.
+    pub fn read_value_at(&self, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        future::run_with_timeout(self.1.get().gatt, || self.0.read_value_at(offset), || self.0.read_value_at_async(offset))
+    }
+
+    pub fn read_value_at_async(&self, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        future::start_async(self.1.get().gatt, || self.0.read_value_at(offset), || self.0.read_value_at_async(offset))
     }
 
-    pub fn read_value(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        get_inner_and_call!(@with_bluez_offset, self, BluetoothGATTDescriptor, read_value)
+    /// Writes `values` over the value from `offset` on, with as many requests as their length
+    /// takes.
+    pub fn write_value_at(&self, offset: u16, values: Vec<u8>) -> Result<(), BluetoothError> {
+        let async_values = values.clone();
+        future::run_with_timeout(self.1.get().gatt, move || self.0.write_value_at(offset, values), move || self.0.write_value_at_async(offset, async_values))
     }
 
-    pub fn write_value(&self, values: Vec<u8>) -> Result<(), Box<dyn Error>> {
-        get_inner_and_call!(@with_bluez_offset, self, BluetoothGATTDescriptor, write_value, values)
+    pub fn write_value_at_async(&self, offset: u16, values: Vec<u8>) -> BluetoothFuture<()> {
+        let async_values = values.clone();
+        future::start_async(self.1.get().gatt, move || self.0.write_value_at(offset, values), move || self.0.write_value_at_async(offset, async_values))
     }
 }
@@ -0,0 +1,1169 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use blurz::bluetooth_adapter::BluetoothAdapter as BluetoothAdapterBluez;
+use blurz::bluetooth_device::BluetoothDevice as BluetoothDeviceBluez;
+use blurz::bluetooth_gatt_characteristic::BluetoothGATTCharacteristic as BluetoothGATTCharacteristicBluez;
+use blurz::bluetooth_gatt_descriptor::BluetoothGATTDescriptor as BluetoothGATTDescriptorBluez;
+use blurz::bluetooth_gatt_service::BluetoothGATTService as BluetoothGATTServiceBluez;
+
+use dbus::{BusType, Connection, ConnectionItem, Message, MessageItem, MessageItemArray, Props, Signature};
+
+use super::address::{AddressType, BdAddr};
+use super::advertisement::{AD_TYPE_COMPLETE_LOCAL_NAME, AD_TYPE_COMPLETE_UUIDS_128, AD_TYPE_COMPLETE_UUIDS_16, AD_TYPE_COMPLETE_UUIDS_32,
+                            AD_TYPE_MANUFACTURER_DATA, AD_TYPE_SERVICE_DATA_128, AD_TYPE_SERVICE_DATA_16, AD_TYPE_SERVICE_DATA_32,
+                            AD_TYPE_TX_POWER_LEVEL};
+use super::backend::{BluetoothAdapterBackend, BluetoothDeviceBackend, BluetoothDiscoverySessionBackend,
+                     BluetoothGATTCharacteristicBackend, BluetoothGATTDescriptorBackend, BluetoothGATTServiceBackend};
+use super::device_id::DeviceId;
+use super::discovery::{DiscoveryFilter, DiscoveryTransport};
+use super::error::{AttError, BluetoothError};
+use super::event::{AdapterEvent, AdapterEventStream, DeviceProperty};
+use super::future::BluetoothFuture;
+use super::gatt::WriteType;
+use super::notification::{NotificationSubscription, NotificationValue};
+use super::pairing::{IoCapability, PairingAgent};
+use super::uuid::BluetoothUuid;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::SystemTime;
+
+// blurz forwards the D-Bus errors of BlueZ, whose `Debug` output contains the error name,
+// e.g. `D-Bus error: "Not connected" (org.bluez.Error.Failed)`.
+pub(crate) fn map_error(error: Box<dyn Error>) -> BluetoothError {
+    let message = format!("{:?}", error);
+    if let Some(index) = message.find("ATT error: 0x") {
+        let code = &message[index + "ATT error: 0x".len()..];
+        let code = code.get(..2).and_then(|code| u8::from_str_radix(code, 16).ok());
+        if let Some(code) = code {
+            return BluetoothError::GattOperationFailed(AttError::from_code(code));
+        }
+    }
+    if message.contains("org.bluez.Error.NotReady") {
+        return BluetoothError::AdapterOff;
+    }
+    if message.contains("org.bluez.Error.NotConnected") || message.contains("\"Not connected\"") {
+        return BluetoothError::NotConnected;
+    }
+    if message.contains("org.bluez.Error.AuthenticationCanceled") {
+        return BluetoothError::Cancelled;
+    }
+    if message.contains("org.bluez.Error.NotAuthorized") ||
+       message.contains("org.bluez.Error.NotPermitted") ||
+       message.contains("org.bluez.Error.Authentication") {
+        return BluetoothError::NotAuthorized;
+    }
+    if message.contains("org.bluez.Error.InProgress") {
+        return BluetoothError::InProgress;
+    }
+    if message.contains("org.bluez.Error.InvalidValueLength") {
+        return BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength);
+    }
+    if message.contains("org.bluez.Error.InvalidOffset") {
+        return BluetoothError::GattOperationFailed(AttError::InvalidOffset);
+    }
+    if message.contains("org.bluez.Error.NotSupported") ||
+       message.contains("org.freedesktop.DBus.Error.UnknownMethod") {
+        return BluetoothError::NotSupported;
+    }
+    if message.contains("org.freedesktop.DBus.Error.NoReply") || message.contains("org.bluez.Error.Timeout") {
+        return BluetoothError::Timeout;
+    }
+    match error.to_string().as_str() {
+        "Bluetooth adapter not found" => BluetoothError::AdapterNotFound,
+        "No device found." => BluetoothError::DeviceNotFound,
+        "Not implemented" | "Deprecated, use Discovery Session" => BluetoothError::NotSupported,
+        _ => BluetoothError::Other(error.to_string()),
+    }
+}
+
+const BLUEZ_SERVICE: &str = "org.bluez";
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const OBJECT_MANAGER_INTERFACE: &str = "org.freedesktop.DBus.ObjectManager";
+const GATT_CHARACTERISTIC_INTERFACE: &str = "org.bluez.GattCharacteristic1";
+const AGENT_MANAGER_INTERFACE: &str = "org.bluez.AgentManager1";
+const AGENT_INTERFACE: &str = "org.bluez.Agent1";
+const AGENT_PATH: &str = "/org/servo/devices/agent";
+const DBUS_TIMEOUT_MS: i32 = 1000;
+// Pairing waits on the agent, so it is given the time the user needs to answer.
+const PAIRING_TIMEOUT_MS: i32 = 60_000;
+
+// blurz only finds the first adapter, so the adapters are listed from the object manager.
+pub(crate) fn get_adapter_paths() -> Result<Vec<String>, Box<dyn Error>> {
+    let connection = Connection::get_private(BusType::System)?;
+    let message = Message::new_method_call(BLUEZ_SERVICE, "/", OBJECT_MANAGER_INTERFACE, "GetManagedObjects")?;
+    let reply = connection.send_with_reply_and_block(message, DBUS_TIMEOUT_MS)?;
+    // GetManagedObjects() -> {object path: {interface: properties}}
+    let items = reply.get_items();
+    let objects = match items.first() {
+        Some(MessageItem::Array(objects)) => objects,
+        _ => return Ok(vec![]),
+    };
+    let mut adapter_paths: Vec<String> = objects.iter().filter_map(|object| match *object {
+        MessageItem::DictEntry(ref path, ref interfaces) => match (&**path, &**interfaces) {
+            (MessageItem::ObjectPath(path), MessageItem::Array(interfaces)) => {
+                let is_adapter = interfaces.iter().any(|interface| match *interface {
+                    MessageItem::DictEntry(ref interface, _) => interface.inner::<&str>() == Ok(ADAPTER_INTERFACE),
+                    _ => false,
+                });
+                if is_adapter { Some(path.to_string()) } else { None }
+            },
+            _ => None,
+        },
+        _ => None,
+    }).collect();
+    adapter_paths.sort();
+    Ok(adapter_paths)
+}
+
+// Reads the BlueZ properties that blurz does not expose.
+fn get_property(interface: &str, object_path: &str, prop: &str) -> Result<MessageItem, Box<dyn Error>> {
+    let connection = Connection::get_private(BusType::System)?;
+    let props = Props::new(&connection, BLUEZ_SERVICE, object_path, interface, DBUS_TIMEOUT_MS);
+    Ok(props.get(prop)?)
+}
+
+// Writes the BlueZ properties that blurz cannot write.
+fn set_property(interface: &str, object_path: &str, prop: &str, value: MessageItem) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::get_private(BusType::System)?;
+    let props = Props::new(&connection, BLUEZ_SERVICE, object_path, interface, DBUS_TIMEOUT_MS);
+    Ok(props.set(prop, value)?)
+}
+
+fn watch_properties(connection: &Connection, object_path: &str) -> Result<(), Box<dyn Error>> {
+    let rule = format!("type='signal',sender='{}',interface='{}',member='PropertiesChanged',path='{}'",
+                       BLUEZ_SERVICE, PROPERTIES_INTERFACE, object_path);
+    Ok(connection.add_match(&rule)?)
+}
+
+// Watches the devices added to and removed from BlueZ, and the property changes of the adapter
+// and of every object below it.
+fn watch_adapter(connection: &Connection, adapter_path: &str) -> Result<(), Box<dyn Error>> {
+    for member in &["InterfacesAdded", "InterfacesRemoved"] {
+        let rule = format!("type='signal',sender='{}',interface='{}',member='{}'",
+                           BLUEZ_SERVICE, OBJECT_MANAGER_INTERFACE, member);
+        connection.add_match(&rule)?;
+    }
+    let rule = format!("type='signal',sender='{}',interface='{}',member='PropertiesChanged',path_namespace='{}'",
+                       BLUEZ_SERVICE, PROPERTIES_INTERFACE, adapter_path);
+    Ok(connection.add_match(&rule)?)
+}
+
+// Opens the connection of a forwarding thread, and tells the caller waiting on `ready`
+// whether the signals could be watched.
+fn signal_connection<F>(ready: Sender<Result<(), BluetoothError>>, watch: F) -> Option<Connection>
+    where F: FnOnce(&Connection) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::get_private(BusType::System).map_err(|error| Box::from(error) as Box<dyn Error>);
+    match connection.and_then(|connection| watch(&connection).map(|_| connection)) {
+        Ok(connection) => {
+            let _ = ready.send(Ok(()));
+            Some(connection)
+        },
+        Err(error) => {
+            let _ = ready.send(Err(map_error(error)));
+            None
+        },
+    }
+}
+
+fn changed_properties(items: &[MessageItem]) -> Vec<(&str, &MessageItem)> {
+    // PropertiesChanged(interface, changed properties, invalidated properties)
+    let changed = match items.get(1) {
+        Some(MessageItem::Array(changed)) => changed,
+        _ => return vec![],
+    };
+    changed.iter().filter_map(|entry| match *entry {
+        MessageItem::DictEntry(ref key, ref value) => {
+            let value = match **value {
+                MessageItem::Variant(ref value) => &**value,
+                ref value => value,
+            };
+            key.inner::<&str>().ok().map(|key| (key, value))
+        },
+        _ => None,
+    }).collect()
+}
+
+// Forwards the `Value` changes of the characteristic until its notifications stop, its device
+// disconnects, or the subscription is dropped.
+fn forward_notifications(characteristic_path: String,
+                         device_path: String,
+                         sender: Sender<NotificationValue>,
+                         alive: Weak<()>,
+                         ready: Sender<Result<(), BluetoothError>>) {
+    let connection = signal_connection(ready, |connection| {
+        watch_properties(connection, &characteristic_path)?;
+        watch_properties(connection, &device_path)
+    });
+    let connection = match connection {
+        Some(connection) => connection,
+        None => return,
+    };
+
+    for item in connection.iter(DBUS_TIMEOUT_MS) {
+        // Sending fails once the subscription is dropped, but a quiet characteristic may not
+        // send anything for a long time.
+        if alive.strong_count() == 0 {
+            return;
+        }
+        let message = match item {
+            ConnectionItem::Signal(message) => message,
+            _ => continue,
+        };
+        let from_characteristic = message.path().is_some_and(|path| &*path == characteristic_path.as_str());
+        let items = message.get_items();
+        for (property, value) in changed_properties(&items) {
+            match (property, value, from_characteristic) {
+                ("Value", MessageItem::Array(bytes), true) => {
+                    let value = bytes.iter().filter_map(|byte| byte.inner::<u8>().ok()).collect();
+                    let notification = NotificationValue { value, timestamp: SystemTime::now() };
+                    if sender.send(notification).is_err() {
+                        return;
+                    }
+                },
+                ("Notifying", MessageItem::Bool(false), true) |
+                ("Connected", MessageItem::Bool(false), false) => return,
+                _ => {},
+            }
+        }
+    }
+}
+
+// Device objects are direct children of their adapter, e.g. `/org/bluez/hci0/dev_XX_XX_XX_XX_XX_XX`.
+fn is_device_of(adapter_path: &str, object_path: &str) -> bool {
+    object_path.strip_prefix(adapter_path)
+               .and_then(|rest| rest.strip_prefix('/'))
+               .is_some_and(|rest| !rest.is_empty() && !rest.contains('/'))
+}
+
+// The device an object belongs to, e.g. `/org/bluez/hci0/dev_XX_XX_XX_XX_XX_XX` for
+// `/org/bluez/hci0/dev_XX_XX_XX_XX_XX_XX/service0010/char0011`.
+fn owning_device(adapter_path: &str, object_path: &str) -> Option<String> {
+    let rest = object_path.strip_prefix(adapter_path)?.strip_prefix('/')?;
+    let device = rest.split('/').next().filter(|device| !device.is_empty())?;
+    Some(format!("{}/{}", adapter_path, device))
+}
+
+fn device_property(property: &str) -> Option<DeviceProperty> {
+    match property {
+        "Name" => Some(DeviceProperty::Name),
+        "RSSI" => Some(DeviceProperty::Rssi),
+        "TxPower" => Some(DeviceProperty::TxPower),
+        "UUIDs" => Some(DeviceProperty::Uuids),
+        "ManufacturerData" => Some(DeviceProperty::ManufacturerData),
+        "ServiceData" => Some(DeviceProperty::ServiceData),
+        "Paired" => Some(DeviceProperty::Paired),
+        "Trusted" => Some(DeviceProperty::Trusted),
+        "Blocked" => Some(DeviceProperty::Blocked),
+        _ => None,
+    }
+}
+
+// Translates a signal received by `watch_adapter` into the adapter events it stands for.
+fn adapter_events(adapter_path: &str, message: &Message) -> Vec<AdapterEvent> {
+    let member = match message.member() {
+        Some(member) => member.to_string(),
+        None => return vec![],
+    };
+    let items = message.get_items();
+    match member.as_str() {
+        // InterfacesAdded(object path, {interface: properties})
+        // InterfacesRemoved(object path, [interface])
+        "InterfacesAdded" | "InterfacesRemoved" => {
+            let device_path = match items.first() {
+                Some(MessageItem::ObjectPath(path)) if is_device_of(adapter_path, path) => path.to_string(),
+                _ => return vec![],
+            };
+            let has_device_interface = match items.get(1) {
+                Some(MessageItem::Array(interfaces)) => interfaces.iter().any(|interface| match *interface {
+                    MessageItem::DictEntry(ref interface, _) => interface.inner::<&str>() == Ok(DEVICE_INTERFACE),
+                    ref interface => interface.inner::<&str>() == Ok(DEVICE_INTERFACE),
+                }),
+                _ => false,
+            };
+            match (has_device_interface, member.as_str()) {
+                (true, "InterfacesAdded") => vec![AdapterEvent::DeviceDiscovered(device_path)],
+                (true, _) => vec![AdapterEvent::DeviceLost(device_path)],
+                (false, _) => vec![],
+            }
+        },
+        "PropertiesChanged" => {
+            let path = match message.path() {
+                Some(path) => path.to_string(),
+                None => return vec![],
+            };
+            let interface = items.first().and_then(|interface| interface.inner::<&str>().ok());
+            let changed = changed_properties(&items);
+            match interface {
+                Some(ADAPTER_INTERFACE) if path == adapter_path => {
+                    changed.into_iter().filter_map(|(property, value)| match (property, value) {
+                        ("Powered", MessageItem::Bool(powered)) => Some(AdapterEvent::PoweredChanged(*powered)),
+                        ("Discovering", MessageItem::Bool(discovering)) =>
+                            Some(AdapterEvent::DiscoveringChanged(*discovering)),
+                        _ => None,
+                    }).collect()
+                },
+                Some(DEVICE_INTERFACE) if is_device_of(adapter_path, &path) => {
+                    changed.into_iter().filter_map(|(property, value)| match (property, value) {
+                        ("Connected", MessageItem::Bool(true)) => Some(AdapterEvent::Connected(path.clone())),
+                        ("Connected", MessageItem::Bool(false)) => Some(AdapterEvent::Disconnected(path.clone())),
+                        (property, _) => device_property(property)
+                            .map(|property| AdapterEvent::DeviceUpdated(path.clone(), property)),
+                    }).collect()
+                },
+                // Every characteristic of the device reports the MTU of its connection.
+                Some(GATT_CHARACTERISTIC_INTERFACE) => {
+                    let device_path = match owning_device(adapter_path, &path) {
+                        Some(device_path) => device_path,
+                        None => return vec![],
+                    };
+                    changed.into_iter().filter_map(|(property, value)| match (property, value) {
+                        ("MTU", MessageItem::UInt16(mtu)) => Some(AdapterEvent::MtuChanged(device_path.clone(), *mtu)),
+                        _ => None,
+                    }).collect()
+                },
+                _ => vec![],
+            }
+        },
+        _ => vec![],
+    }
+}
+
+// Forwards the events of the adapter until the stream is dropped.
+fn forward_adapter_events(adapter_path: String,
+                          sender: Sender<AdapterEvent>,
+                          alive: Weak<()>,
+                          ready: Sender<Result<(), BluetoothError>>) {
+    let connection = match signal_connection(ready, |connection| watch_adapter(connection, &adapter_path)) {
+        Some(connection) => connection,
+        None => return,
+    };
+
+    // The MTU last reported for each device, since all its characteristics report the change.
+    let mut mtus = HashMap::new();
+    for item in connection.iter(DBUS_TIMEOUT_MS) {
+        // As for the notifications, the adapter may stay quiet long after the stream is dropped.
+        if alive.strong_count() == 0 {
+            return;
+        }
+        let message = match item {
+            ConnectionItem::Signal(message) => message,
+            _ => continue,
+        };
+        for event in adapter_events(&adapter_path, &message) {
+            if let AdapterEvent::MtuChanged(ref device_path, mtu) = event {
+                if mtus.insert(device_path.clone(), mtu) == Some(mtu) {
+                    continue;
+                }
+            }
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+// The sender stopping the thread serving the agent registered by `register_pairing_agent`.
+// BlueZ makes the agent the default one, which answers the pairings of every adapter, so there
+// is a single agent for all of them.
+static PAIRING_AGENT: Mutex<Option<Sender<()>>> = Mutex::new(None);
+
+fn capability_name(capability: IoCapability) -> &'static str {
+    match capability {
+        IoCapability::DisplayOnly => "DisplayOnly",
+        IoCapability::DisplayYesNo => "DisplayYesNo",
+        IoCapability::KeyboardOnly => "KeyboardOnly",
+        IoCapability::NoInputNoOutput => "NoInputNoOutput",
+        IoCapability::KeyboardDisplay => "KeyboardDisplay",
+    }
+}
+
+fn call_agent_manager(connection: &Connection, method: &str, items: &[MessageItem]) -> Result<(), Box<dyn Error>> {
+    let mut message = Message::new_method_call(BLUEZ_SERVICE, "/org/bluez", AGENT_MANAGER_INTERFACE, method)?;
+    message.append_items(items);
+    connection.send_with_reply_and_block(message, DBUS_TIMEOUT_MS)?;
+    Ok(())
+}
+
+// BlueZ keeps one agent per D-Bus client, and makes the default one answer the pairings of every
+// adapter.
+fn register_agent(connection: &Connection, agent_path: &str, capability: IoCapability) -> Result<(), Box<dyn Error>> {
+    connection.register_object_path(agent_path)?;
+    let path = MessageItem::ObjectPath(agent_path.to_owned().into());
+    call_agent_manager(connection, "RegisterAgent", &[path.clone(), MessageItem::Str(capability_name(capability).to_owned())])?;
+    call_agent_manager(connection, "RequestDefaultAgent", &[path])
+}
+
+// Answers a call of BlueZ to the agent. Every call but `Release` and `Cancel` expects a reply.
+fn agent_reply(agent: &dyn PairingAgent, call: &Message) -> Option<Message> {
+    let items = call.get_items();
+    let device = match items.first() {
+        Some(MessageItem::ObjectPath(device)) => device.to_string(),
+        _ => String::new(),
+    };
+    let rejected = || Message::new_error(call, "org.bluez.Error.Rejected", "Rejected by the agent");
+    let accepted = |accepted: bool| if accepted { Message::new_method_return(call) } else { rejected() };
+    let member = call.member()?;
+    match (&*member, items.get(1), items.get(2)) {
+        ("Release", _, _) => None,
+        ("Cancel", _, _) => {
+            agent.cancel();
+            None
+        },
+        ("RequestPinCode", _, _) => match agent.request_pin_code(&device) {
+            Some(pin_code) => Message::new_method_return(call).map(|reply| reply.append1(pin_code)),
+            None => rejected(),
+        },
+        ("DisplayPinCode", Some(MessageItem::Str(pin_code)), _) => {
+            agent.display_pin_code(&device, pin_code);
+            Message::new_method_return(call)
+        },
+        ("RequestPasskey", _, _) => match agent.request_passkey(&device) {
+            Some(passkey) => Message::new_method_return(call).map(|reply| reply.append1(passkey)),
+            None => rejected(),
+        },
+        ("DisplayPasskey", Some(MessageItem::UInt32(passkey)), Some(MessageItem::UInt16(entered))) => {
+            agent.display_passkey(&device, *passkey, *entered);
+            Message::new_method_return(call)
+        },
+        ("RequestConfirmation", Some(MessageItem::UInt32(passkey)), _) => accepted(agent.request_confirmation(&device, *passkey)),
+        ("RequestAuthorization", _, _) => accepted(agent.request_authorization(&device)),
+        ("AuthorizeService", Some(MessageItem::Str(uuid)), _) => match uuid.parse() {
+            Ok(uuid) => accepted(agent.authorize_service(&device, uuid)),
+            Err(_) => rejected(),
+        },
+        _ => Message::new_error(call, "org.freedesktop.DBus.Error.UnknownMethod", "Unknown agent method"),
+    }
+}
+
+// Serves the agent until `stop` fires or its sender is dropped, then unregisters it.
+fn serve_pairing_agent(agent_path: String,
+                       agent: Arc<dyn PairingAgent>,
+                       capability: IoCapability,
+                       stop: Receiver<()>,
+                       ready: Sender<Result<(), BluetoothError>>) {
+    let connection = match signal_connection(ready, |connection| register_agent(connection, &agent_path, capability)) {
+        Some(connection) => connection,
+        None => return,
+    };
+
+    for item in connection.iter(DBUS_TIMEOUT_MS) {
+        if stop.try_recv() != Err(TryRecvError::Empty) {
+            break;
+        }
+        let call = match item {
+            ConnectionItem::MethodCall(call) => call,
+            _ => continue,
+        };
+        if call.interface().is_some_and(|interface| &*interface == AGENT_INTERFACE) {
+            if let Some(reply) = agent_reply(&*agent, &call) {
+                let _ = connection.send(reply);
+            }
+        }
+    }
+    let _ = call_agent_manager(&connection, "UnregisterAgent", &[MessageItem::ObjectPath(agent_path.into())]);
+}
+
+// Takes the agent, if any, out of `PAIRING_AGENT`. Dropping the sender stops the thread serving
+// it.
+fn take_pairing_agent() -> Option<Sender<()>> {
+    PAIRING_AGENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()
+}
+
+impl BluetoothAdapterBackend for Arc<BluetoothAdapterBluez> {
+    fn get_id(&self) -> String {
+        BluetoothAdapterBluez::get_id(self)
+    }
+
+    fn get_devices(&self) -> Result<Vec<Arc<dyn BluetoothDeviceBackend>>, BluetoothError> {
+        let device_list = self.get_device_list().map_err(map_error)?;
+        Ok(device_list.into_iter()
+                      .map(|device| Arc::new(Arc::new(BluetoothDeviceBluez::new(device))) as Arc<dyn BluetoothDeviceBackend>)
+                      .collect())
+    }
+
+    // blurz's discovery session is neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn create_discovery_session(&self) -> Result<Arc<dyn BluetoothDiscoverySessionBackend>, BluetoothError> {
+        let session = BluezDiscoverySession::create_session(BluetoothAdapterBluez::get_id(self)).map_err(map_error)?;
+        Ok(Arc::new(session))
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterBluez::get_address(self).map_err(map_error)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterBluez::get_name(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterBluez::get_alias(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterBluez::get_class(self).map_err(map_error)
+    }
+
+    fn is_powered(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterBluez::is_powered(self).map_err(map_error)
+    }
+
+    fn is_discoverable(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterBluez::is_discoverable(self).map_err(map_error)
+    }
+
+    fn is_pairable(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterBluez::is_pairable(self).map_err(map_error)
+    }
+
+    fn get_pairable_timeout(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterBluez::get_pairable_timeout(self).map_err(map_error)
+    }
+
+    fn get_discoverable_timeout(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterBluez::get_discoverable_timeout(self).map_err(map_error)
+    }
+
+    fn is_discovering(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterBluez::is_discovering(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        BluetoothAdapterBluez::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        DeviceId::from_parts(BluetoothAdapterBluez::get_modalias(self).map_err(map_error)?)
+    }
+
+    fn set_alias(&self, alias: String) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::set_alias(self, alias).map_err(map_error)
+    }
+
+    fn set_powered(&self, powered: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::set_powered(self, powered).map_err(map_error)
+    }
+
+    fn set_discoverable(&self, discoverable: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::set_discoverable(self, discoverable).map_err(map_error)
+    }
+
+    fn set_pairable(&self, pairable: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::set_pairable(self, pairable).map_err(map_error)
+    }
+
+    fn set_pairable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::set_pairable_timeout(self, timeout).map_err(map_error)
+    }
+
+    fn set_discoverable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::set_discoverable_timeout(self, timeout).map_err(map_error)
+    }
+
+    fn subscribe_events(&self) -> Result<AdapterEventStream, BluetoothError> {
+        let adapter_path = BluetoothAdapterBluez::get_id(self);
+        let (sender, receiver) = channel();
+        let (ready_sender, ready_receiver) = channel();
+        let stream = AdapterEventStream::new(receiver);
+        let alive = stream.liveness();
+        thread::spawn(move || forward_adapter_events(adapter_path, sender, alive, ready_sender));
+        ready_receiver.recv().unwrap_or(Err(BluetoothError::NotSupported))?;
+        Ok(stream)
+    }
+
+    fn remove_device(&self, device_id: String) -> Result<(), BluetoothError> {
+        BluetoothAdapterBluez::remove_device(self, device_id).map_err(map_error)
+    }
+
+    // The agent answers for every adapter, and replaces the one registered on any of them.
+    fn register_pairing_agent(&self, agent: Arc<dyn PairingAgent>, capability: IoCapability) -> Result<(), BluetoothError> {
+        if let Some(stop) = take_pairing_agent() {
+            let _ = stop.send(());
+        }
+        let (stop_sender, stop_receiver) = channel();
+        let (ready_sender, ready_receiver) = channel();
+        thread::spawn(move || serve_pairing_agent(AGENT_PATH.to_owned(), agent, capability, stop_receiver, ready_sender));
+        ready_receiver.recv().unwrap_or(Err(BluetoothError::NotSupported))?;
+        *PAIRING_AGENT.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(stop_sender);
+        Ok(())
+    }
+
+    fn unregister_pairing_agent(&self) -> Result<(), BluetoothError> {
+        if let Some(stop) = take_pairing_agent() {
+            let _ = stop.send(());
+        }
+        Ok(())
+    }
+}
+
+// blurz gives `Pair` the usual D-Bus timeout, which is too short to wait on the agent.
+fn pair(device_path: &str) -> Result<(), Box<dyn Error>> {
+    let connection = Connection::get_private(BusType::System)?;
+    let message = Message::new_method_call(BLUEZ_SERVICE, device_path, DEVICE_INTERFACE, "Pair")?;
+    connection.send_with_reply_and_block(message, PAIRING_TIMEOUT_MS)?;
+    Ok(())
+}
+
+// BlueZ (5.62 and later) negotiates the ATT MTU by itself when connecting, and exposes it on
+// every characteristic of the device, so the device must be connected with its services
+// resolved.
+fn get_mtu(device: &BluetoothDeviceBluez) -> Result<u16, BluetoothError> {
+    for service in device.get_gatt_services().map_err(map_error)? {
+        let characteristics = BluetoothGATTServiceBluez::new(service).get_gatt_characteristics().map_err(map_error)?;
+        if let Some(characteristic) = characteristics.first() {
+            let mtu = get_property(GATT_CHARACTERISTIC_INTERFACE, characteristic, "MTU").map_err(map_error)?;
+            return mtu.inner::<u16>().map_err(|_| BluetoothError::NotSupported);
+        }
+    }
+    // The attributes are only known once connected and resolved.
+    Err(BluetoothError::NotConnected)
+}
+
+// BlueZ decodes the advertising data into the device properties, and only keeps the AD
+// structures it does not know in `AdvertisingData` (type -> data), so the report is put back
+// together from both. The flags are lost.
+fn get_advertisement_data(device: &Arc<BluetoothDeviceBluez>) -> Result<Vec<u8>, BluetoothError> {
+    let mut raw = vec![];
+    let mut push = |ad_type: u8, data: &[u8]| {
+        if data.len() < u8::MAX as usize {
+            raw.push(data.len() as u8 + 1);
+            raw.push(ad_type);
+            raw.extend_from_slice(data);
+        }
+    };
+    if let Ok(name) = BluetoothDeviceBluez::get_name(device) {
+        push(AD_TYPE_COMPLETE_LOCAL_NAME, name.as_bytes());
+    }
+    if let Ok(tx_power) = BluetoothDeviceBluez::get_tx_power(device) {
+        push(AD_TYPE_TX_POWER_LEVEL, &[tx_power as i8 as u8]);
+    }
+    let uuids = BluetoothDeviceBackend::get_uuids(device).unwrap_or_default();
+    let mut lists = [(AD_TYPE_COMPLETE_UUIDS_16, vec![]), (AD_TYPE_COMPLETE_UUIDS_32, vec![]), (AD_TYPE_COMPLETE_UUIDS_128, vec![])];
+    for uuid in &uuids {
+        match (uuid.to_u16(), uuid.to_u32()) {
+            (Some(uuid), _) => lists[0].1.extend_from_slice(&uuid.to_le_bytes()),
+            (None, Some(uuid)) => lists[1].1.extend_from_slice(&uuid.to_le_bytes()),
+            (None, None) => lists[2].1.extend_from_slice(&uuid.as_u128().to_le_bytes()),
+        }
+    }
+    for (ad_type, list) in lists.iter().filter(|(_, list)| !list.is_empty()) {
+        push(*ad_type, list);
+    }
+    for (uuid, data) in BluetoothDeviceBackend::get_service_data(device).unwrap_or_default() {
+        let (ad_type, mut bytes) = match (uuid.to_u16(), uuid.to_u32()) {
+            (Some(uuid), _) => (AD_TYPE_SERVICE_DATA_16, uuid.to_le_bytes().to_vec()),
+            (None, Some(uuid)) => (AD_TYPE_SERVICE_DATA_32, uuid.to_le_bytes().to_vec()),
+            (None, None) => (AD_TYPE_SERVICE_DATA_128, uuid.as_u128().to_le_bytes().to_vec()),
+        };
+        bytes.extend(data);
+        push(ad_type, &bytes);
+    }
+    for (company_identifier, data) in BluetoothDeviceBluez::get_manufacturer_data(device).unwrap_or_default() {
+        let mut bytes = company_identifier.to_le_bytes().to_vec();
+        bytes.extend(data);
+        push(AD_TYPE_MANUFACTURER_DATA, &bytes);
+    }
+    // Added in BlueZ 5.48.
+    if let Ok(MessageItem::Array(structures)) = get_property(DEVICE_INTERFACE, &device.get_id(), "AdvertisingData") {
+        for structure in structures.iter() {
+            let (ad_type, data) = match *structure {
+                MessageItem::DictEntry(ref ad_type, ref data) => (ad_type, data),
+                _ => continue,
+            };
+            let data = match **data {
+                MessageItem::Variant(ref data) => data,
+                _ => continue,
+            };
+            if let (MessageItem::Byte(ad_type), MessageItem::Array(bytes)) = (&**ad_type, &**data) {
+                let bytes: Vec<u8> = bytes.iter().filter_map(|byte| byte.inner::<u8>().ok()).collect();
+                push(*ad_type, &bytes);
+            }
+        }
+    }
+    Ok(raw)
+}
+
+// blurz cannot pass the `type` option of `WriteValue`, added in BlueZ 5.51. There is no signed
+// type: BlueZ signs a Write Command itself when the characteristic permits it and the link is
+// not encrypted, and sends it unsigned over an encrypted link, which authenticates it already.
+fn write_value_with_type(characteristic_path: &str, values: Vec<u8>, write_type: WriteType) -> Result<(), Box<dyn Error>> {
+    let write_type = match write_type {
+        WriteType::WithResponse => "request",
+        WriteType::WithoutResponse | WriteType::Signed => "command",
+        WriteType::Reliable => "reliable",
+    };
+    let values = values.into_iter().map(MessageItem::Byte).collect();
+    let values = MessageItemArray::new(values, Signature::new("ay")?).map_err(|error| format!("{:?}", error))?;
+    let option = MessageItem::DictEntry(Box::new(MessageItem::Str("type".to_owned())),
+                                        Box::new(MessageItem::Variant(Box::new(MessageItem::Str(write_type.to_owned())))));
+    let options = MessageItemArray::new(vec![option], Signature::new("a{sv}")?).map_err(|error| format!("{:?}", error))?;
+    let connection = Connection::get_private(BusType::System)?;
+    let mut message = Message::new_method_call(BLUEZ_SERVICE, characteristic_path, GATT_CHARACTERISTIC_INTERFACE, "WriteValue")?;
+    message.append_items(&[MessageItem::Array(values), MessageItem::Array(options)]);
+    connection.send_with_reply_and_block(message, DBUS_TIMEOUT_MS)?;
+    Ok(())
+}
+
+// BlueZ keeps a discovery filter per D-Bus client, so the filter has to be set on the connection
+// that starts the discovery, which the blurz session does not expose.
+#[derive(Debug)]
+struct BluezDiscoverySession {
+    adapter_path: String,
+    connection: Connection,
+}
+
+impl BluezDiscoverySession {
+    fn create_session(adapter_path: String) -> Result<BluezDiscoverySession, Box<dyn Error>> {
+        let connection = Connection::get_private(BusType::System)?;
+        Ok(BluezDiscoverySession { adapter_path, connection })
+    }
+
+    fn call_method(&self, method: &str, items: &[MessageItem]) -> Result<(), Box<dyn Error>> {
+        let mut message = Message::new_method_call(BLUEZ_SERVICE, &self.adapter_path, ADAPTER_INTERFACE, method)?;
+        message.append_items(items);
+        self.connection.send_with_reply_and_block(message, DBUS_TIMEOUT_MS)?;
+        Ok(())
+    }
+}
+
+// The `SetDiscoveryFilter` argument. The default filter is sent empty, which clears the filter.
+// The name prefix is left to the software filter, because BlueZ matches its `Pattern` against
+// the address too, and older versions reject it. BlueZ also rejects RSSI and path loss together.
+fn discovery_filter_dict(filter: &DiscoveryFilter) -> Result<MessageItem, Box<dyn Error>> {
+    let entry = |key: &str, value: MessageItem| {
+        MessageItem::DictEntry(Box::new(MessageItem::Str(key.to_owned())), Box::new(MessageItem::Variant(Box::new(value))))
+    };
+    let mut entries = vec![];
+    if *filter != DiscoveryFilter::default() {
+        if !filter.service_uuids.is_empty() {
+            let uuids = filter.service_uuids.iter().map(|uuid| MessageItem::Str(uuid.to_string())).collect();
+            let uuids = MessageItemArray::new(uuids, Signature::new("as")?).map_err(|error| format!("{:?}", error))?;
+            entries.push(entry("UUIDs", MessageItem::Array(uuids)));
+        }
+        if let Some(rssi) = filter.rssi {
+            entries.push(entry("RSSI", MessageItem::Int16(rssi)));
+        }
+        if let (None, Some(pathloss)) = (filter.rssi, filter.pathloss) {
+            entries.push(entry("Pathloss", MessageItem::UInt16(pathloss)));
+        }
+        let transport = match filter.transport {
+            DiscoveryTransport::Auto => "auto",
+            DiscoveryTransport::Le => "le",
+            DiscoveryTransport::BrEdr => "bredr",
+        };
+        entries.push(entry("Transport", MessageItem::Str(transport.to_owned())));
+        entries.push(entry("DuplicateData", MessageItem::Bool(filter.duplicate_data)));
+    }
+    let dict = MessageItemArray::new(entries, Signature::new("a{sv}")?).map_err(|error| format!("{:?}", error))?;
+    Ok(MessageItem::Array(dict))
+}
+
+impl BluetoothDiscoverySessionBackend for BluezDiscoverySession {
+    fn start_discovery(&self) -> Result<(), BluetoothError> {
+        self.call_method("StartDiscovery", &[]).map_err(map_error)
+    }
+
+    fn stop_discovery(&self) -> Result<(), BluetoothError> {
+        self.call_method("StopDiscovery", &[]).map_err(map_error)
+    }
+
+    fn set_discovery_filter(&self, filter: &DiscoveryFilter) -> Result<(), BluetoothError> {
+        let dict = discovery_filter_dict(filter).map_err(map_error)?;
+        self.call_method("SetDiscoveryFilter", &[dict]).map_err(map_error)
+    }
+}
+
+impl BluetoothDeviceBackend for Arc<BluetoothDeviceBluez> {
+    fn get_id(&self) -> String {
+        BluetoothDeviceBluez::get_id(self)
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceBluez::get_address(self).map_err(map_error)
+    }
+
+    fn get_address_type(&self) -> Result<AddressType, BluetoothError> {
+        let address_type = get_property(DEVICE_INTERFACE, &BluetoothDeviceBluez::get_id(self), "AddressType")
+            .map_err(map_error)?;
+        match address_type.inner::<&str>() {
+            Ok("public") => Ok(AddressType::Public),
+            Ok("random") => {
+                let address: BdAddr = BluetoothDeviceBluez::get_address(self).map_err(map_error)?.parse()?;
+                address.random_address_type().ok_or_else(|| BluetoothError::InvalidAddress(address.to_string()))
+            },
+            _ => Err(BluetoothError::Other(format!("Unknown address type: {:?}", address_type))),
+        }
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceBluez::get_name(self).map_err(map_error)
+    }
+
+    fn get_icon(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceBluez::get_icon(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        BluetoothDeviceBluez::get_class(self).map_err(map_error)
+    }
+
+    fn get_appearance(&self) -> Result<u16, BluetoothError> {
+        BluetoothDeviceBluez::get_appearance(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        BluetoothDeviceBluez::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn is_paired(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceBluez::is_paired(self).map_err(map_error)
+    }
+
+    fn is_connected(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceBluez::is_connected(self).map_err(map_error)
+    }
+
+    fn is_trusted(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceBluez::is_trusted(self).map_err(map_error)
+    }
+
+    fn is_blocked(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceBluez::is_blocked(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceBluez::get_alias(self).map_err(map_error)
+    }
+
+    fn is_legacy_pairing(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceBluez::is_legacy_pairing(self).map_err(map_error)
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        DeviceId::from_parts(BluetoothDeviceBluez::get_modalias(self).map_err(map_error)?)
+    }
+
+    fn get_rssi(&self) -> Result<i16, BluetoothError> {
+        BluetoothDeviceBluez::get_rssi(self).map_err(map_error)
+    }
+
+    fn get_tx_power(&self) -> Result<i16, BluetoothError> {
+        BluetoothDeviceBluez::get_tx_power(self).map_err(map_error)
+    }
+
+    fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError> {
+        BluetoothDeviceBluez::get_manufacturer_data(self).map_err(map_error)
+    }
+
+    fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError> {
+        BluetoothDeviceBluez::get_service_data(self).map_err(map_error)?
+            .into_iter()
+            .map(|(uuid, data)| Ok((uuid.parse()?, data)))
+            .collect()
+    }
+
+    fn get_gatt_services(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        let services = BluetoothDeviceBluez::get_gatt_services(self).map_err(map_error)?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(Arc::new(BluetoothGATTServiceBluez::new(service))) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    fn connect(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceBluez::connect(self).map_err(map_error)
+    }
+
+    fn disconnect(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceBluez::disconnect(self).map_err(map_error)
+    }
+
+    fn connect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        BluetoothDeviceBluez::connect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn disconnect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        BluetoothDeviceBluez::disconnect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn pair(&self) -> Result<(), BluetoothError> {
+        pair(&BluetoothDeviceBluez::get_id(self)).map_err(map_error)
+    }
+
+    fn cancel_pairing(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceBluez::cancel_pairing(self).map_err(map_error)
+    }
+
+    // BlueZ only forgets the keys of a device by removing it.
+    fn unpair(&self) -> Result<(), BluetoothError> {
+        let adapter_path = BluetoothDeviceBluez::get_adapter(self).map_err(map_error)?;
+        BluetoothAdapterBluez::create_adapter(adapter_path).and_then(|adapter| adapter.remove_device(BluetoothDeviceBluez::get_id(self)))
+                                                           .map_err(map_error)
+    }
+
+    fn set_trusted(&self, trusted: bool) -> Result<(), BluetoothError> {
+        set_property(DEVICE_INTERFACE, &BluetoothDeviceBluez::get_id(self), "Trusted", MessageItem::Bool(trusted)).map_err(map_error)
+    }
+
+    fn set_blocked(&self, blocked: bool) -> Result<(), BluetoothError> {
+        set_property(DEVICE_INTERFACE, &BluetoothDeviceBluez::get_id(self), "Blocked", MessageItem::Bool(blocked)).map_err(map_error)
+    }
+
+    fn get_mtu(&self) -> Result<u16, BluetoothError> {
+        get_mtu(self)
+    }
+
+    fn get_advertisement_data(&self) -> Result<Vec<u8>, BluetoothError> {
+        get_advertisement_data(self)
+    }
+
+    fn connect_async(&self) -> BluetoothFuture<()> {
+        let device = self.clone();
+        BluetoothFuture::spawn(move || BluetoothDeviceBackend::connect(&device))
+    }
+
+    fn disconnect_async(&self) -> BluetoothFuture<()> {
+        let device = self.clone();
+        BluetoothFuture::spawn(move || BluetoothDeviceBackend::disconnect(&device))
+    }
+
+    fn pair_async(&self) -> BluetoothFuture<()> {
+        let device = self.clone();
+        BluetoothFuture::spawn(move || BluetoothDeviceBackend::pair(&device))
+    }
+
+    fn get_gatt_services_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTServiceBackend>>> {
+        let device = self.clone();
+        BluetoothFuture::spawn(move || BluetoothDeviceBluez::get_gatt_services(&device).map_err(map_error))
+            .map(|services| services.into_iter()
+                                    .map(|path| Arc::new(Arc::new(BluetoothGATTServiceBluez::new(path))) as Arc<dyn BluetoothGATTServiceBackend>)
+                                    .collect())
+    }
+}
+
+impl BluetoothGATTServiceBackend for Arc<BluetoothGATTServiceBluez> {
+    fn get_id(&self) -> String {
+        BluetoothGATTServiceBluez::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTServiceBluez::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn is_primary(&self) -> Result<bool, BluetoothError> {
+        BluetoothGATTServiceBluez::is_primary(self).map_err(map_error)
+    }
+
+    fn get_includes(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        let services = BluetoothGATTServiceBluez::get_includes(self).map_err(map_error)?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(Arc::new(BluetoothGATTServiceBluez::new(service))) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    fn get_gatt_characteristics(&self) -> Result<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>, BluetoothError> {
+        let characteristics = BluetoothGATTServiceBluez::get_gatt_characteristics(self).map_err(map_error)?;
+        Ok(characteristics.into_iter()
+                          .map(|characteristic|
+                              Arc::new(Arc::new(BluetoothGATTCharacteristicBluez::new(characteristic)))
+                                  as Arc<dyn BluetoothGATTCharacteristicBackend>)
+                          .collect())
+    }
+
+    fn get_includes_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTServiceBackend>>> {
+        let service = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTServiceBluez::get_includes(&service).map_err(map_error))
+            .map(|services| services.into_iter()
+                                    .map(|path| Arc::new(Arc::new(BluetoothGATTServiceBluez::new(path))) as Arc<dyn BluetoothGATTServiceBackend>)
+                                    .collect())
+    }
+
+    fn get_gatt_characteristics_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>> {
+        let service = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTServiceBluez::get_gatt_characteristics(&service).map_err(map_error))
+            .map(|characteristics| characteristics.into_iter()
+                                                  .map(|path| Arc::new(Arc::new(BluetoothGATTCharacteristicBluez::new(path))) as Arc<dyn BluetoothGATTCharacteristicBackend>)
+                                                  .collect())
+    }
+}
+
+impl BluetoothGATTCharacteristicBackend for Arc<BluetoothGATTCharacteristicBluez> {
+    fn get_id(&self) -> String {
+        BluetoothGATTCharacteristicBluez::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTCharacteristicBluez::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTCharacteristicBluez::get_value(self).map_err(map_error)
+    }
+
+    fn is_notifying(&self) -> Result<bool, BluetoothError> {
+        BluetoothGATTCharacteristicBluez::is_notifying(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        BluetoothGATTCharacteristicBluez::get_flags(self).map_err(map_error)
+    }
+
+    fn get_gatt_descriptors(&self) -> Result<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>, BluetoothError> {
+        let descriptors = BluetoothGATTCharacteristicBluez::get_gatt_descriptors(self).map_err(map_error)?;
+        Ok(descriptors.into_iter()
+                      .map(|descriptor|
+                          Arc::new(Arc::new(BluetoothGATTDescriptorBluez::new(descriptor)))
+                              as Arc<dyn BluetoothGATTDescriptorBackend>)
+                      .collect())
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTCharacteristicBluez::read_value(self, None).map_err(map_error)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicBluez::write_value(self, values, None).map_err(map_error)
+    }
+
+    // BlueZ reads and writes long values with the Read Blob and the Prepare Write requests.
+    fn read_value_at(&self, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTCharacteristicBluez::read_value(self, Some(offset)).map_err(map_error)
+    }
+
+    fn write_value_at(&self, offset: u16, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicBluez::write_value(self, values, Some(offset)).map_err(map_error)
+    }
+
+    fn write_value_with_type(&self, values: Vec<u8>, write_type: WriteType) -> Result<(), BluetoothError> {
+        write_value_with_type(&BluetoothGATTCharacteristicBluez::get_id(self), values, write_type).map_err(map_error)
+    }
+
+    fn start_notify(&self) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicBluez::start_notify(self).map_err(map_error)
+    }
+
+    fn stop_notify(&self) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicBluez::stop_notify(self).map_err(map_error)
+    }
+
+    fn subscribe(&self) -> Result<NotificationSubscription, BluetoothError> {
+        // The characteristic object path is `<device>/serviceXXXX/charXXXX`.
+        let characteristic_path = BluetoothGATTCharacteristicBluez::get_id(self);
+        let device_path = match characteristic_path.rsplitn(3, '/').nth(2) {
+            Some(device_path) => device_path.to_owned(),
+            None => return Err(BluetoothError::CharacteristicNotFound),
+        };
+        let (sender, receiver) = channel();
+        let (ready_sender, ready_receiver) = channel();
+        let subscription = NotificationSubscription::new(receiver);
+        let alive = subscription.liveness();
+        thread::spawn(move || forward_notifications(characteristic_path, device_path, sender, alive, ready_sender));
+        ready_receiver.recv().unwrap_or(Err(BluetoothError::NotSupported))?;
+        Ok(subscription)
+    }
+
+    fn get_gatt_descriptors_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBluez::get_gatt_descriptors(&characteristic).map_err(map_error))
+            .map(|descriptors| descriptors.into_iter()
+                                          .map(|path| Arc::new(Arc::new(BluetoothGATTDescriptorBluez::new(path))) as Arc<dyn BluetoothGATTDescriptorBackend>)
+                                          .collect())
+    }
+
+    fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::read_value(&characteristic))
+    }
+
+    fn write_value_async(&self, values: Vec<u8>) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::write_value(&characteristic, values))
+    }
+
+    fn read_value_at_async(&self, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::read_value_at(&characteristic, offset))
+    }
+
+    fn write_value_at_async(&self, offset: u16, values: Vec<u8>) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::write_value_at(&characteristic, offset, values))
+    }
+
+    fn write_value_with_type_async(&self, values: Vec<u8>, write_type: WriteType) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::write_value_with_type(&characteristic, values, write_type))
+    }
+
+    fn start_notify_async(&self) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::start_notify(&characteristic))
+    }
+
+    fn stop_notify_async(&self) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTCharacteristicBackend::stop_notify(&characteristic))
+    }
+}
+
+impl BluetoothGATTDescriptorBackend for Arc<BluetoothGATTDescriptorBluez> {
+    fn get_id(&self) -> String {
+        BluetoothGATTDescriptorBluez::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTDescriptorBluez::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorBluez::get_value(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        BluetoothGATTDescriptorBluez::get_flags(self).map_err(map_error)
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorBluez::read_value(self, None).map_err(map_error)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTDescriptorBluez::write_value(self, values, None).map_err(map_error)
+    }
+
+    // BlueZ reads and writes long values with the Read Blob and the Prepare Write requests.
+    fn read_value_at(&self, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorBluez::read_value(self, Some(offset)).map_err(map_error)
+    }
+
+    fn write_value_at(&self, offset: u16, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTDescriptorBluez::write_value(self, values, Some(offset)).map_err(map_error)
+    }
+
+    fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        let descriptor = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTDescriptorBackend::read_value(&descriptor))
+    }
+
+    fn write_value_async(&self, values: Vec<u8>) -> BluetoothFuture<()> {
+        let descriptor = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTDescriptorBackend::write_value(&descriptor, values))
+    }
+
+    fn read_value_at_async(&self, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        let descriptor = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTDescriptorBackend::read_value_at(&descriptor, offset))
+    }
+
+    fn write_value_at_async(&self, offset: u16, values: Vec<u8>) -> BluetoothFuture<()> {
+        let descriptor = self.clone();
+        BluetoothFuture::spawn(move || BluetoothGATTDescriptorBackend::write_value_at(&descriptor, offset, values))
+    }
+}
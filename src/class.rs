@@ -0,0 +1,429 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::fmt;
+
+const MAJOR_DEVICE_CLASS_SHIFT: u32 = 8;
+const MINOR_DEVICE_CLASS_SHIFT: u32 = 2;
+
+/// A bit of the major service classes of a Class of Device (Bluetooth Assigned Numbers, 2.8.1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MajorServiceClass {
+    LimitedDiscoverableMode,
+    LeAudio,
+    Positioning,
+    Networking,
+    Rendering,
+    Capturing,
+    ObjectTransfer,
+    Audio,
+    Telephony,
+    Information,
+}
+
+const SERVICE_CLASSES: [MajorServiceClass; 10] = [
+    MajorServiceClass::LimitedDiscoverableMode,
+    MajorServiceClass::LeAudio,
+    MajorServiceClass::Positioning,
+    MajorServiceClass::Networking,
+    MajorServiceClass::Rendering,
+    MajorServiceClass::Capturing,
+    MajorServiceClass::ObjectTransfer,
+    MajorServiceClass::Audio,
+    MajorServiceClass::Telephony,
+    MajorServiceClass::Information,
+];
+
+impl MajorServiceClass {
+    /// The bit of the service class in the Class of Device.
+    pub fn bit(&self) -> u32 {
+        match *self {
+            MajorServiceClass::LimitedDiscoverableMode => 13,
+            MajorServiceClass::LeAudio => 14,
+            MajorServiceClass::Positioning => 16,
+            MajorServiceClass::Networking => 17,
+            MajorServiceClass::Rendering => 18,
+            MajorServiceClass::Capturing => 19,
+            MajorServiceClass::ObjectTransfer => 20,
+            MajorServiceClass::Audio => 21,
+            MajorServiceClass::Telephony => 22,
+            MajorServiceClass::Information => 23,
+        }
+    }
+}
+
+impl fmt::Display for MajorServiceClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            MajorServiceClass::LimitedDiscoverableMode => "Limited Discoverable Mode",
+            MajorServiceClass::LeAudio => "LE audio",
+            MajorServiceClass::Positioning => "Positioning",
+            MajorServiceClass::Networking => "Networking",
+            MajorServiceClass::Rendering => "Rendering",
+            MajorServiceClass::Capturing => "Capturing",
+            MajorServiceClass::ObjectTransfer => "Object Transfer",
+            MajorServiceClass::Audio => "Audio",
+            MajorServiceClass::Telephony => "Telephony",
+            MajorServiceClass::Information => "Information",
+        })
+    }
+}
+
+/// The major device class of a Class of Device (Bluetooth Assigned Numbers, 2.8.2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MajorDeviceClass {
+    Miscellaneous,
+    Computer,
+    Phone,
+    NetworkAccessPoint,
+    AudioVideo,
+    Peripheral,
+    Imaging,
+    Wearable,
+    Toy,
+    Health,
+    Uncategorized,
+    /// A reserved value, from 10 to 30.
+    Reserved(u8),
+}
+
+impl MajorDeviceClass {
+    pub fn value(&self) -> u8 {
+        match *self {
+            MajorDeviceClass::Miscellaneous => 0x00,
+            MajorDeviceClass::Computer => 0x01,
+            MajorDeviceClass::Phone => 0x02,
+            MajorDeviceClass::NetworkAccessPoint => 0x03,
+            MajorDeviceClass::AudioVideo => 0x04,
+            MajorDeviceClass::Peripheral => 0x05,
+            MajorDeviceClass::Imaging => 0x06,
+            MajorDeviceClass::Wearable => 0x07,
+            MajorDeviceClass::Toy => 0x08,
+            MajorDeviceClass::Health => 0x09,
+            MajorDeviceClass::Uncategorized => 0x1F,
+            MajorDeviceClass::Reserved(value) => value & 0x1F,
+        }
+    }
+}
+
+impl From<u8> for MajorDeviceClass {
+    fn from(value: u8) -> MajorDeviceClass {
+        match value & 0x1F {
+            0x00 => MajorDeviceClass::Miscellaneous,
+            0x01 => MajorDeviceClass::Computer,
+            0x02 => MajorDeviceClass::Phone,
+            0x03 => MajorDeviceClass::NetworkAccessPoint,
+            0x04 => MajorDeviceClass::AudioVideo,
+            0x05 => MajorDeviceClass::Peripheral,
+            0x06 => MajorDeviceClass::Imaging,
+            0x07 => MajorDeviceClass::Wearable,
+            0x08 => MajorDeviceClass::Toy,
+            0x09 => MajorDeviceClass::Health,
+            0x1F => MajorDeviceClass::Uncategorized,
+            value => MajorDeviceClass::Reserved(value),
+        }
+    }
+}
+
+impl fmt::Display for MajorDeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            MajorDeviceClass::Miscellaneous => "Miscellaneous",
+            MajorDeviceClass::Computer => "Computer",
+            MajorDeviceClass::Phone => "Phone",
+            MajorDeviceClass::NetworkAccessPoint => "LAN/Network Access Point",
+            MajorDeviceClass::AudioVideo => "Audio/Video",
+            MajorDeviceClass::Peripheral => "Peripheral",
+            MajorDeviceClass::Imaging => "Imaging",
+            MajorDeviceClass::Wearable => "Wearable",
+            MajorDeviceClass::Toy => "Toy",
+            MajorDeviceClass::Health => "Health",
+            MajorDeviceClass::Uncategorized => "Uncategorized",
+            MajorDeviceClass::Reserved(_) => "Reserved",
+        })
+    }
+}
+
+/// A Class of Device, as found in inquiry results and EIR data (Bluetooth Assigned Numbers, 2.8):
+/// the major service classes in bits 13 to 23, the major device class in bits 8 to 12, and the
+/// minor device class in bits 2 to 7.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ClassOfDevice(u32);
+
+impl ClassOfDevice {
+    /// Encodes a Class of Device. The minor device class is truncated to its 6 bits.
+    pub fn new(service_classes: &[MajorServiceClass], major_device_class: MajorDeviceClass, minor_device_class: u8) -> ClassOfDevice {
+        let service_classes = service_classes.iter().fold(0, |bits, service_class| bits | 1 << service_class.bit());
+        ClassOfDevice(service_classes |
+                      (major_device_class.value() as u32) << MAJOR_DEVICE_CLASS_SHIFT |
+                      ((minor_device_class & 0x3F) as u32) << MINOR_DEVICE_CLASS_SHIFT)
+    }
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    pub fn service_classes(&self) -> Vec<MajorServiceClass> {
+        SERVICE_CLASSES.iter().cloned().filter(|service_class| self.has_service_class(*service_class)).collect()
+    }
+
+    pub fn has_service_class(&self, service_class: MajorServiceClass) -> bool {
+        self.0 & 1 << service_class.bit() != 0
+    }
+
+    pub fn major_device_class(&self) -> MajorDeviceClass {
+        MajorDeviceClass::from((self.0 >> MAJOR_DEVICE_CLASS_SHIFT) as u8)
+    }
+
+    /// The 6 bits of the minor device class, whose meaning depends on the major device class.
+    pub fn minor_device_class(&self) -> u8 {
+        ((self.0 >> MINOR_DEVICE_CLASS_SHIFT) & 0x3F) as u8
+    }
+
+    /// The name of the minor device class. Some major device classes split it in fields, whose
+    /// names are joined with ", ".
+    pub fn minor_device_class_name(&self) -> Option<String> {
+        let minor = self.minor_device_class();
+        let name = match self.major_device_class() {
+            MajorDeviceClass::Computer => match minor {
+                0x00 => "Uncategorized",
+                0x01 => "Desktop workstation",
+                0x02 => "Server-class computer",
+                0x03 => "Laptop",
+                0x04 => "Handheld PC/PDA (clamshell)",
+                0x05 => "Palm-size PC/PDA",
+                0x06 => "Wearable computer (watch size)",
+                0x07 => "Tablet",
+                _ => return None,
+            },
+            MajorDeviceClass::Phone => match minor {
+                0x00 => "Uncategorized",
+                0x01 => "Cellular",
+                0x02 => "Cordless",
+                0x03 => "Smartphone",
+                0x04 => "Wired modem or voice gateway",
+                0x05 => "Common ISDN access",
+                _ => return None,
+            },
+            // The upper 3 bits tell how busy the access point is.
+            MajorDeviceClass::NetworkAccessPoint => match minor >> 3 {
+                0x00 => "Fully available",
+                0x01 => "1% to 17% utilized",
+                0x02 => "17% to 33% utilized",
+                0x03 => "33% to 50% utilized",
+                0x04 => "50% to 67% utilized",
+                0x05 => "67% to 83% utilized",
+                0x06 => "83% to 99% utilized",
+                _ => "No service available",
+            },
+            MajorDeviceClass::AudioVideo => match minor {
+                0x00 => "Uncategorized",
+                0x01 => "Wearable Headset Device",
+                0x02 => "Hands-free Device",
+                0x04 => "Microphone",
+                0x05 => "Loudspeaker",
+                0x06 => "Headphones",
+                0x07 => "Portable Audio",
+                0x08 => "Car audio",
+                0x09 => "Set-top box",
+                0x0A => "HiFi Audio Device",
+                0x0B => "VCR",
+                0x0C => "Video Camera",
+                0x0D => "Camcorder",
+                0x0E => "Video Monitor",
+                0x0F => "Video Display and Loudspeaker",
+                0x10 => "Video Conferencing",
+                0x12 => "Gaming/Toy",
+                _ => return None,
+            },
+            // The upper 2 bits tell whether it is a keyboard or a pointing device, and the lower
+            // 4 bits what else it is.
+            MajorDeviceClass::Peripheral => {
+                let input = match minor >> 4 {
+                    0x00 => None,
+                    0x01 => Some("Keyboard"),
+                    0x02 => Some("Pointing device"),
+                    _ => Some("Combo keyboard/pointing device"),
+                };
+                let kind = match minor & 0x0F {
+                    0x00 => None,
+                    0x01 => Some("Joystick"),
+                    0x02 => Some("Gamepad"),
+                    0x03 => Some("Remote control"),
+                    0x04 => Some("Sensing device"),
+                    0x05 => Some("Digitizer tablet"),
+                    0x06 => Some("Card Reader"),
+                    0x07 => Some("Digital Pen"),
+                    0x08 => Some("Handheld scanner"),
+                    0x09 => Some("Handheld gestural input device"),
+                    _ => return None,
+                };
+                let names: Vec<_> = input.into_iter().chain(kind).collect();
+                return Some(if names.is_empty() { "Uncategorized".to_owned() } else { names.join(", ") });
+            },
+            // Each of the upper 4 bits stands for a kind of imaging device.
+            MajorDeviceClass::Imaging => {
+                let kinds = ["Display", "Camera", "Scanner", "Printer"];
+                let names: Vec<_> = kinds.iter()
+                                         .enumerate()
+                                         .filter(|&(bit, _)| minor & 1 << (bit + 2) != 0)
+                                         .map(|(_, name)| *name)
+                                         .collect();
+                return Some(if names.is_empty() { "Uncategorized".to_owned() } else { names.join(", ") });
+            },
+            MajorDeviceClass::Wearable => match minor {
+                0x01 => "Wristwatch",
+                0x02 => "Pager",
+                0x03 => "Jacket",
+                0x04 => "Helmet",
+                0x05 => "Glasses",
+                0x06 => "Pin",
+                _ => return None,
+            },
+            MajorDeviceClass::Toy => match minor {
+                0x01 => "Robot",
+                0x02 => "Vehicle",
+                0x03 => "Doll / Action figure",
+                0x04 => "Controller",
+                0x05 => "Game",
+                _ => return None,
+            },
+            MajorDeviceClass::Health => match minor {
+                0x00 => "Undefined",
+                0x01 => "Blood Pressure Monitor",
+                0x02 => "Thermometer",
+                0x03 => "Weighing Scale",
+                0x04 => "Glucose Meter",
+                0x05 => "Pulse Oximeter",
+                0x06 => "Heart/Pulse Rate Monitor",
+                0x07 => "Health Data Display",
+                0x08 => "Step Counter",
+                0x09 => "Body Composition Analyzer",
+                0x0A => "Peak Flow Monitor",
+                0x0B => "Medication Monitor",
+                0x0C => "Knee Prosthesis",
+                0x0D => "Ankle Prosthesis",
+                0x0E => "Generic Health Manager",
+                0x0F => "Personal Mobility Device",
+                _ => return None,
+            },
+            MajorDeviceClass::Miscellaneous | MajorDeviceClass::Uncategorized | MajorDeviceClass::Reserved(_) => return None,
+        };
+        Some(name.to_owned())
+    }
+}
+
+impl From<u32> for ClassOfDevice {
+    fn from(class: u32) -> ClassOfDevice {
+        ClassOfDevice(class)
+    }
+}
+
+impl From<ClassOfDevice> for u32 {
+    fn from(class: ClassOfDevice) -> u32 {
+        class.0
+    }
+}
+
+/// The major device class, the minor device class if it has a name, and the service classes,
+/// e.g. "Audio/Video: Wearable Headset Device (Rendering, Audio)".
+impl fmt::Display for ClassOfDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.major_device_class())?;
+        if let Some(minor) = self.minor_device_class_name() {
+            write!(f, ": {}", minor)?;
+        }
+        let service_classes = self.service_classes();
+        if !service_classes.is_empty() {
+            let names: Vec<_> = service_classes.iter().map(MajorServiceClass::to_string).collect();
+            write!(f, " ({})", names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClassOfDevice, MajorDeviceClass, MajorServiceClass, SERVICE_CLASSES};
+
+    fn class(major_device_class: MajorDeviceClass, minor_device_class: u8) -> ClassOfDevice {
+        ClassOfDevice::new(&[], major_device_class, minor_device_class)
+    }
+
+    #[test]
+    fn fields_split_the_value() {
+        let headset = ClassOfDevice::from(0x240404);
+        assert_eq!(headset.service_classes(), vec![MajorServiceClass::Rendering, MajorServiceClass::Audio]);
+        assert!(headset.has_service_class(MajorServiceClass::Audio));
+        assert!(!headset.has_service_class(MajorServiceClass::Telephony));
+        assert_eq!(headset.major_device_class(), MajorDeviceClass::AudioVideo);
+        assert_eq!(headset.minor_device_class(), 0x01);
+        assert_eq!(ClassOfDevice::new(&[MajorServiceClass::Audio, MajorServiceClass::Rendering],
+                                      MajorDeviceClass::AudioVideo, 0x01), headset);
+        assert_eq!(u32::from(headset), 0x240404);
+    }
+
+    #[test]
+    fn new_truncates_the_minor_device_class() {
+        assert_eq!(class(MajorDeviceClass::Computer, 0xFF).value(), 0x01FC);
+        assert_eq!(class(MajorDeviceClass::Computer, 0xFF).minor_device_class(), 0x3F);
+        assert_eq!(class(MajorDeviceClass::Reserved(0x2A), 0x00).value(), 0x0A00);
+    }
+
+    #[test]
+    fn service_classes_round_trip() {
+        let class = ClassOfDevice::new(&SERVICE_CLASSES, MajorDeviceClass::Miscellaneous, 0);
+        assert_eq!(class.value(), 0xFF6000);
+        assert_eq!(class.service_classes(), SERVICE_CLASSES.to_vec());
+        assert!(ClassOfDevice::from(0x008000).service_classes().is_empty());
+    }
+
+    #[test]
+    fn major_device_classes_round_trip() {
+        for value in 0..0x20 {
+            assert_eq!(MajorDeviceClass::from(value).value(), value);
+        }
+        assert_eq!(MajorDeviceClass::from(0x0A), MajorDeviceClass::Reserved(0x0A));
+        assert_eq!(MajorDeviceClass::from(0x1E), MajorDeviceClass::Reserved(0x1E));
+        assert_eq!(MajorDeviceClass::from(0x21), MajorDeviceClass::Computer);
+        assert_eq!(MajorDeviceClass::Reserved(0x0A).to_string(), "Reserved");
+    }
+
+    #[test]
+    fn minor_device_class_names_depend_on_the_major_device_class() {
+        let name = |major, minor| class(major, minor).minor_device_class_name();
+        assert_eq!(name(MajorDeviceClass::Phone, 0x03), Some("Smartphone".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Phone, 0x06), None);
+        assert_eq!(name(MajorDeviceClass::AudioVideo, 0x03), None);
+        assert_eq!(name(MajorDeviceClass::Wearable, 0x00), None);
+        assert_eq!(name(MajorDeviceClass::Health, 0x00), Some("Undefined".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Miscellaneous, 0x01), None);
+        assert_eq!(name(MajorDeviceClass::Uncategorized, 0x01), None);
+        assert_eq!(name(MajorDeviceClass::Reserved(0x0A), 0x01), None);
+    }
+
+    #[test]
+    fn minor_device_class_fields_are_named() {
+        let name = |major, minor| class(major, minor).minor_device_class_name();
+        assert_eq!(name(MajorDeviceClass::NetworkAccessPoint, 0x00), Some("Fully available".to_owned()));
+        assert_eq!(name(MajorDeviceClass::NetworkAccessPoint, 0x1F), Some("33% to 50% utilized".to_owned()));
+        assert_eq!(name(MajorDeviceClass::NetworkAccessPoint, 0x38), Some("No service available".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Peripheral, 0x00), Some("Uncategorized".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Peripheral, 0x10), Some("Keyboard".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Peripheral, 0x32), Some("Combo keyboard/pointing device, Gamepad".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Peripheral, 0x2A), None);
+        assert_eq!(name(MajorDeviceClass::Imaging, 0x00), Some("Uncategorized".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Imaging, 0x28), Some("Camera, Printer".to_owned()));
+        assert_eq!(name(MajorDeviceClass::Imaging, 0x03), Some("Uncategorized".to_owned()));
+    }
+
+    #[test]
+    fn display_joins_the_classes() {
+        assert_eq!(ClassOfDevice::from(0x240404).to_string(), "Audio/Video: Wearable Headset Device (Rendering, Audio)");
+        assert_eq!(class(MajorDeviceClass::Wearable, 0x00).to_string(), "Wearable");
+        assert_eq!(ClassOfDevice::new(&[MajorServiceClass::LimitedDiscoverableMode], MajorDeviceClass::Toy, 0x01).to_string(),
+                   "Toy: Robot (Limited Discoverable Mode)");
+        assert_eq!(ClassOfDevice::default().to_string(), "Miscellaneous");
+    }
+}
@@ -0,0 +1,51 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::time::Duration;
+
+/// A trade-off between the responsiveness and the power consumption of an LE connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum ConnectionPriority {
+    LowLatency,
+    #[default]
+    Balanced,
+    LowPower,
+}
+
+impl ConnectionPriority {
+    /// The connection parameters the priority asks for.
+    pub fn parameters(&self) -> ConnectionParameters {
+        let (interval, latency) = match *self {
+            ConnectionPriority::LowLatency => (Duration::from_micros(15_000), 0),
+            ConnectionPriority::Balanced => (Duration::from_micros(50_000), 0),
+            ConnectionPriority::LowPower => (Duration::from_micros(125_000), 2),
+        };
+        ConnectionParameters {
+            interval,
+            latency,
+            supervision_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+/// The parameters of an LE connection (Bluetooth Core Specification, Vol 6, Part B, 4.5.1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConnectionParameters {
+    /// The time between two connection events, a multiple of 1.25 ms.
+    pub interval: Duration,
+    /// The number of connection events the peripheral may skip.
+    pub latency: u16,
+    /// How long the link may go without a packet before it is considered lost.
+    pub supervision_timeout: Duration,
+}
+
+/// The physical layer of an LE connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Phy {
+    #[default]
+    Le1M,
+    Le2M,
+    /// The long range PHY.
+    LeCoded,
+}
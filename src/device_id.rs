@@ -0,0 +1,265 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::error::BluetoothError;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+// A few of the company identifiers of the Bluetooth SIG (Bluetooth Assigned Numbers, 7.1).
+const SIG_COMPANIES: &[(u16, &str)] = &[
+    (0x0000, "Ericsson Technology Licensing"),
+    (0x0001, "Nokia Mobile Phones"),
+    (0x0002, "Intel Corp."),
+    (0x0006, "Microsoft"),
+    (0x000A, "Qualcomm Technologies International, Ltd. (QTIL)"),
+    (0x000D, "Texas Instruments Inc."),
+    (0x000F, "Broadcom Corporation"),
+    (0x001D, "Qualcomm"),
+    (0x0025, "NXP Semiconductors (formerly Philips Semiconductors)"),
+    (0x0030, "ST Microelectronics"),
+    (0x0046, "MediaTek, Inc."),
+    (0x004C, "Apple, Inc."),
+    (0x0059, "Nordic Semiconductor ASA"),
+    (0x006B, "Polar Electro OY"),
+    (0x0075, "Samsung Electronics Co. Ltd."),
+    (0x0087, "Garmin International, Inc."),
+    (0x009E, "Bose Corporation"),
+    (0x00E0, "Google"),
+    (0x0118, "Radius Networks, Inc."),
+    (0x012D, "Sony Corporation"),
+    (0x0131, "Cypress Semiconductor"),
+    (0x0171, "Amazon.com Services, LLC"),
+    (0x02E5, "Espressif Incorporated"),
+    (0x0499, "Ruuvi Innovations Ltd."),
+];
+
+// A few of the vendor IDs of the USB Implementers Forum.
+const USB_VENDORS: &[(u16, &str)] = &[
+    (0x03F0, "HP, Inc."),
+    (0x0451, "Texas Instruments, Inc."),
+    (0x045E, "Microsoft Corp."),
+    (0x046D, "Logitech, Inc."),
+    (0x0489, "Foxconn / Hon Hai"),
+    (0x04E8, "Samsung Electronics Co., Ltd"),
+    (0x054C, "Sony Corp."),
+    (0x057E, "Nintendo Co., Ltd"),
+    (0x05AC, "Apple, Inc."),
+    (0x0A12, "Cambridge Silicon Radio, Ltd"),
+    (0x0A5C, "Broadcom Corp."),
+    (0x0B05, "ASUSTek Computer, Inc."),
+    (0x0BDA, "Realtek Semiconductor Corp."),
+    (0x0CF3, "Qualcomm Atheros Communications"),
+    (0x0E8D, "MediaTek Inc."),
+    (0x13D3, "IMC Networks"),
+    (0x17EF, "Lenovo"),
+    (0x18D1, "Google Inc."),
+    (0x1915, "Nordic Semiconductor ASA"),
+    (0x1D6B, "Linux Foundation"),
+    (0x2717, "Xiaomi Inc."),
+    (0x413C, "Dell Computer Corp."),
+    (0x8087, "Intel Corp."),
+];
+
+/// The body which assigned the vendor ID of a `DeviceId`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VendorIdSource {
+    /// A company identifier of the Bluetooth SIG.
+    Bluetooth,
+    /// A vendor ID of the USB Implementers Forum.
+    Usb,
+}
+
+impl VendorIdSource {
+    /// The value of the VendorIDSource attribute of the Device ID profile.
+    pub fn value(&self) -> u16 {
+        match *self {
+            VendorIdSource::Bluetooth => 0x0001,
+            VendorIdSource::Usb => 0x0002,
+        }
+    }
+
+    pub fn from_value(value: u16) -> Option<VendorIdSource> {
+        match value {
+            0x0001 => Some(VendorIdSource::Bluetooth),
+            0x0002 => Some(VendorIdSource::Usb),
+            _ => None,
+        }
+    }
+
+    /// The bus name of the modalias strings.
+    pub fn modalias_prefix(&self) -> &'static str {
+        match *self {
+            VendorIdSource::Bluetooth => "bluetooth",
+            VendorIdSource::Usb => "usb",
+        }
+    }
+}
+
+impl FromStr for VendorIdSource {
+    type Err = BluetoothError;
+
+    fn from_str(source: &str) -> Result<VendorIdSource, BluetoothError> {
+        match source {
+            "bluetooth" => Ok(VendorIdSource::Bluetooth),
+            "usb" => Ok(VendorIdSource::Usb),
+            _ => Err(BluetoothError::InvalidModalias(source.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for VendorIdSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            VendorIdSource::Bluetooth => "Bluetooth SIG",
+            VendorIdSource::Usb => "USB Implementers Forum",
+        })
+    }
+}
+
+/// The Device ID of an adapter or device, as BlueZ and the kernel write it in modalias strings:
+/// `bluetooth:v004Cp0001d0002` or `usb:v1D6Bp0246d0537`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceId {
+    pub source: VendorIdSource,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// The release number of the product, the `d` of the modalias.
+    pub version: u16,
+}
+
+impl DeviceId {
+    pub fn new(source: VendorIdSource, vendor_id: u16, product_id: u16, version: u16) -> DeviceId {
+        DeviceId {
+            source,
+            vendor_id,
+            product_id,
+            version,
+        }
+    }
+
+    pub fn parse(modalias: &str) -> Result<DeviceId, BluetoothError> {
+        let invalid = || BluetoothError::InvalidModalias(modalias.to_owned());
+        let (source, ids) = modalias.split_once(':').ok_or_else(invalid)?;
+        let source = source.parse().map_err(|_| invalid())?;
+        let id = |prefix: char, field: &str| -> Result<u16, BluetoothError> {
+            match field.strip_prefix(prefix) {
+                Some(id) if id.len() == 4 && id.chars().all(|c| c.is_ascii_hexdigit()) => {
+                    u16::from_str_radix(id, 16).map_err(|_| invalid())
+                },
+                _ => Err(invalid()),
+            }
+        };
+        if ids.len() != 15 || !ids.is_char_boundary(5) || !ids.is_char_boundary(10) {
+            return Err(invalid());
+        }
+        Ok(DeviceId {
+            source,
+            vendor_id: id('v', &ids[..5])?,
+            product_id: id('p', &ids[5..10])?,
+            version: id('d', &ids[10..])?,
+        })
+    }
+
+    /// The name of the vendor, for the vendors this crate knows of.
+    pub fn company_name(&self) -> Option<&'static str> {
+        let companies = match self.source {
+            VendorIdSource::Bluetooth => SIG_COMPANIES,
+            VendorIdSource::Usb => USB_VENDORS,
+        };
+        companies.iter().find(|&&(vendor_id, _)| vendor_id == self.vendor_id).map(|&(_, name)| name)
+    }
+
+    /// Builds the Device ID from a modalias split the way blurz, blurmac and blurdroid split
+    /// it. The IDs are 16-bit, so larger ones are rejected rather than truncated.
+    pub fn from_parts((source, vendor_id, product_id, version): (String, u32, u32, u32)) -> Result<DeviceId, BluetoothError> {
+        let invalid = || BluetoothError::InvalidModalias(format!("{}:v{:X}p{:X}d{:X}", source, vendor_id, product_id, version));
+        Ok(DeviceId {
+            source: source.parse()?,
+            vendor_id: u16::try_from(vendor_id).map_err(|_| invalid())?,
+            product_id: u16::try_from(product_id).map_err(|_| invalid())?,
+            version: u16::try_from(version).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl FromStr for DeviceId {
+    type Err = BluetoothError;
+
+    fn from_str(modalias: &str) -> Result<DeviceId, BluetoothError> {
+        DeviceId::parse(modalias)
+    }
+}
+
+/// Formats the modalias.
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:v{:04X}p{:04X}d{:04X}", self.source.modalias_prefix(), self.vendor_id, self.product_id, self.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BluetoothError, DeviceId, VendorIdSource};
+
+    fn is_invalid(modalias: &str) -> bool {
+        match DeviceId::parse(modalias) {
+            Err(BluetoothError::InvalidModalias(invalid)) => invalid == modalias,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn modaliases_round_trip() {
+        let id = DeviceId::parse("bluetooth:v004Cp2002d0A01").unwrap();
+        assert_eq!(id, DeviceId::new(VendorIdSource::Bluetooth, 0x004C, 0x2002, 0x0A01));
+        assert_eq!(id.to_string(), "bluetooth:v004Cp2002d0A01");
+        let id: DeviceId = "usb:v1d6bp0246dffff".parse().unwrap();
+        assert_eq!(id, DeviceId::new(VendorIdSource::Usb, 0x1D6B, 0x0246, 0xFFFF));
+        assert_eq!(id.to_string(), "usb:v1D6Bp0246dFFFF");
+    }
+
+    #[test]
+    fn malformed_modaliases_are_rejected() {
+        for modalias in &["", "bluetooth", "bluetooth:", "bluetooth:v004Cp2002d0A0", "bluetooth:v004Cp2002d0A012",
+                          "pci:v004Cp2002d0A01", "Bluetooth:v004Cp2002d0A01", "bluetooth:p004Cv2002d0A01",
+                          "bluetooth:v04Cp02002d0A01", "bluetooth:v004Gp2002d0A01", "bluetooth:v+04Cp2002d0A01",
+                          "bluetooth:v004Cp2002d0A01:", "bluetooth:v004Cp2002dé0A", "bluetooth:v00éCp2002d0A0"] {
+            assert!(is_invalid(modalias), "{}", modalias);
+        }
+    }
+
+    #[test]
+    fn parts_must_fit_in_16_bits() {
+        let parts = |vendor_id, product_id, version| ("usb".to_owned(), vendor_id, product_id, version);
+        assert_eq!(DeviceId::from_parts(parts(0x1D6B, 0x0246, 0xFFFF)).unwrap(),
+                   DeviceId::new(VendorIdSource::Usb, 0x1D6B, 0x0246, 0xFFFF));
+        for parts in [parts(0x1_0000, 0, 0), parts(0, 0x1_0000, 0), parts(0, 0, u32::MAX)] {
+            match DeviceId::from_parts(parts.clone()) {
+                Err(BluetoothError::InvalidModalias(_)) => {},
+                result => panic!("{:?} gave {:?}", parts, result),
+            }
+        }
+        assert!(DeviceId::from_parts(("pci".to_owned(), 0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn company_names_depend_on_the_source() {
+        assert_eq!(DeviceId::new(VendorIdSource::Bluetooth, 0x004C, 0, 0).company_name(), Some("Apple, Inc."));
+        assert_eq!(DeviceId::new(VendorIdSource::Usb, 0x05AC, 0, 0).company_name(), Some("Apple, Inc."));
+        assert_eq!(DeviceId::new(VendorIdSource::Usb, 0x004C, 0, 0).company_name(), None);
+        assert_eq!(DeviceId::new(VendorIdSource::Bluetooth, 0xFFFF, 0, 0).company_name(), None);
+    }
+
+    #[test]
+    fn vendor_id_sources_round_trip() {
+        for &source in &[VendorIdSource::Bluetooth, VendorIdSource::Usb] {
+            assert_eq!(VendorIdSource::from_value(source.value()), Some(source));
+            assert_eq!(source.modalias_prefix().parse::<VendorIdSource>().unwrap(), source);
+        }
+        assert_eq!(VendorIdSource::from_value(0x0000), None);
+        assert_eq!(VendorIdSource::from_value(0x0003), None);
+    }
+}
@@ -0,0 +1,258 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::backend::{BluetoothAdapterBackend, BluetoothDiscoverySessionBackend};
+use super::bluetooth::BluetoothDevice;
+use super::error::BluetoothError;
+use super::uuid::BluetoothUuid;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The radio a discovery scans with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum DiscoveryTransport {
+    /// Both LE and BR/EDR, as the adapter supports them.
+    #[default]
+    Auto,
+    Le,
+    BrEdr,
+}
+
+/// Restricts the devices reported by a discovery session.
+///
+/// Backends apply what they can natively, and `BluetoothDiscoverySession::get_devices`
+/// applies the filter to its results in software, see `matches`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiscoveryFilter {
+    /// Only devices advertising at least one of these services, or any device if empty.
+    pub service_uuids: Vec<BluetoothUuid>,
+    /// Only devices received with at least this RSSI, in dBm.
+    pub rssi: Option<i16>,
+    /// Only devices whose path loss (TX power minus RSSI) is at most this value, in dB.
+    pub pathloss: Option<u16>,
+    pub transport: DiscoveryTransport,
+    /// Reports every advertisement instead of only the ones changing the device's data.
+    pub duplicate_data: bool,
+    /// Only devices whose name starts with this prefix.
+    pub name_prefix: Option<String>,
+}
+
+impl DiscoveryFilter {
+    pub fn new() -> DiscoveryFilter {
+        DiscoveryFilter::default()
+    }
+
+    /// Whether the device passes the filter. The transport and the duplicate data policy
+    /// only apply to the scan itself, so they are not checked here.
+    pub fn matches(&self, device: &BluetoothDevice) -> bool {
+        if !self.service_uuids.is_empty() {
+            let mut uuids = device.get_uuids().unwrap_or_default();
+            uuids.extend(device.get_service_data().unwrap_or_default().into_keys());
+            if !uuids.iter().any(|uuid| self.service_uuids.contains(uuid)) {
+                return false;
+            }
+        }
+        if let Some(threshold) = self.rssi {
+            if !device.get_rssi().is_ok_and(|rssi| rssi >= threshold) {
+                return false;
+            }
+        }
+        if let Some(threshold) = self.pathloss {
+            let pathloss = match (device.get_tx_power(), device.get_rssi()) {
+                (Ok(tx_power), Ok(rssi)) => i32::from(tx_power) - i32::from(rssi),
+                _ => return false,
+            };
+            if pathloss > i32::from(threshold) {
+                return false;
+            }
+        }
+        if let Some(ref prefix) = self.name_prefix {
+            if !device.get_name().is_ok_and(|name| name.starts_with(prefix.as_str())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The filter of the scan shared by the active sessions, which passes everything that passes any
+// of their filters. The name prefix is only applied in software.
+fn merge_filters(filters: &[&DiscoveryFilter]) -> DiscoveryFilter {
+    let first = match filters.first() {
+        Some(first) => first,
+        None => return DiscoveryFilter::default(),
+    };
+    let mut service_uuids = vec![];
+    if filters.iter().all(|filter| !filter.service_uuids.is_empty()) {
+        for uuid in filters.iter().flat_map(|filter| filter.service_uuids.iter()) {
+            if !service_uuids.contains(uuid) {
+                service_uuids.push(*uuid);
+            }
+        }
+    }
+    let rssi = filters.iter().map(|filter| filter.rssi).collect::<Option<Vec<_>>>().and_then(|rssi| rssi.into_iter().min());
+    let pathloss = filters.iter()
+                          .map(|filter| filter.pathloss)
+                          .collect::<Option<Vec<_>>>()
+                          .and_then(|pathloss| pathloss.into_iter().max());
+    let transport = if filters.iter().all(|filter| filter.transport == first.transport) {
+        first.transport
+    } else {
+        DiscoveryTransport::Auto
+    };
+    DiscoveryFilter {
+        service_uuids,
+        rssi,
+        pathloss,
+        transport,
+        duplicate_data: filters.iter().any(|filter| filter.duplicate_data),
+        name_prefix: None,
+    }
+}
+
+#[derive(Debug, Default)]
+struct DiscoveryState {
+    // Created with the first started session, and shared by the later ones.
+    backend: Option<Arc<dyn BluetoothDiscoverySessionBackend>>,
+    active: Vec<(usize, DiscoveryFilter)>,
+    next_id: usize,
+}
+
+/// Counts the started discovery sessions of an adapter, and keeps the adapter scanning while
+/// there is at least one.
+#[derive(Debug, Default)]
+pub(crate) struct DiscoverySessions {
+    state: Mutex<DiscoveryState>,
+}
+
+impl DiscoverySessions {
+    pub(crate) fn new_id(&self) -> usize {
+        let mut state = self.lock();
+        state.next_id += 1;
+        state.next_id
+    }
+
+    pub(crate) fn start(&self,
+                        adapter: &dyn BluetoothAdapterBackend,
+                        id: usize,
+                        filter: &DiscoveryFilter)
+                        -> Result<(), BluetoothError> {
+        let mut state = self.lock();
+        if state.active.iter().any(|(active_id, _)| *active_id == id) {
+            return Ok(());
+        }
+        let backend = match state.backend {
+            Some(ref backend) => backend.clone(),
+            None => adapter.create_discovery_session()?,
+        };
+        state.backend = Some(backend.clone());
+        let mut filters: Vec<&DiscoveryFilter> = state.active.iter().map(|(_, filter)| filter).collect();
+        filters.push(filter);
+        apply_filter(&*backend, &merge_filters(&filters))?;
+        if state.active.is_empty() {
+            backend.start_discovery()?;
+        }
+        state.active.push((id, filter.clone()));
+        Ok(())
+    }
+
+    /// Stops the scan if the session was the last started one. The session stays started if
+    /// the backend fails to stop, so that it can be retried.
+    pub(crate) fn stop(&self, id: usize) -> Result<(), BluetoothError> {
+        let mut state = self.lock();
+        let index = match state.active.iter().position(|(active_id, _)| *active_id == id) {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let backend = match state.backend {
+            Some(ref backend) => backend.clone(),
+            None => return Ok(()),
+        };
+        if state.active.len() == 1 {
+            backend.stop_discovery()?;
+            state.active.clear();
+            return Ok(());
+        }
+        state.active.remove(index);
+        let filters: Vec<&DiscoveryFilter> = state.active.iter().map(|(_, filter)| filter).collect();
+        apply_filter(&*backend, &merge_filters(&filters))
+    }
+
+    pub(crate) fn is_active(&self, id: usize) -> bool {
+        self.lock().active.iter().any(|(active_id, _)| *active_id == id)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DiscoveryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn apply_filter(backend: &dyn BluetoothDiscoverySessionBackend, filter: &DiscoveryFilter) -> Result<(), BluetoothError> {
+    match backend.set_discovery_filter(filter) {
+        Ok(()) | Err(BluetoothError::NotSupported) => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_filters, BluetoothUuid, DiscoveryFilter, DiscoveryTransport};
+
+    const HEART_RATE: BluetoothUuid = BluetoothUuid::from_u16(0x180D);
+    const BATTERY: BluetoothUuid = BluetoothUuid::from_u16(0x180F);
+
+    fn with_uuids(uuids: &[BluetoothUuid]) -> DiscoveryFilter {
+        DiscoveryFilter { service_uuids: uuids.to_vec(), ..DiscoveryFilter::default() }
+    }
+
+    #[test]
+    fn merging_nothing_gives_the_default_filter() {
+        assert_eq!(merge_filters(&[]), DiscoveryFilter::default());
+    }
+
+    #[test]
+    fn service_uuids_are_united_without_duplicates() {
+        let first = with_uuids(&[HEART_RATE]);
+        let second = with_uuids(&[BATTERY, HEART_RATE]);
+        assert_eq!(merge_filters(&[&first, &second]).service_uuids, vec![HEART_RATE, BATTERY]);
+    }
+
+    #[test]
+    fn a_filter_without_service_uuids_lets_every_service_through() {
+        let first = with_uuids(&[HEART_RATE]);
+        let second = with_uuids(&[]);
+        assert!(merge_filters(&[&first, &second]).service_uuids.is_empty());
+    }
+
+    #[test]
+    fn thresholds_keep_the_loosest_bound() {
+        let first = DiscoveryFilter { rssi: Some(-50), pathloss: Some(30), ..DiscoveryFilter::default() };
+        let second = DiscoveryFilter { rssi: Some(-70), pathloss: Some(60), ..DiscoveryFilter::default() };
+        let merged = merge_filters(&[&first, &second]);
+        assert_eq!(merged.rssi, Some(-70));
+        assert_eq!(merged.pathloss, Some(60));
+
+        let unbounded = DiscoveryFilter::default();
+        let merged = merge_filters(&[&first, &unbounded]);
+        assert_eq!(merged.rssi, None);
+        assert_eq!(merged.pathloss, None);
+    }
+
+    #[test]
+    fn the_transport_is_kept_only_if_every_filter_agrees() {
+        let le = DiscoveryFilter { transport: DiscoveryTransport::Le, ..DiscoveryFilter::default() };
+        let br_edr = DiscoveryFilter { transport: DiscoveryTransport::BrEdr, ..DiscoveryFilter::default() };
+        assert_eq!(merge_filters(&[&le, &le]).transport, DiscoveryTransport::Le);
+        assert_eq!(merge_filters(&[&le, &br_edr]).transport, DiscoveryTransport::Auto);
+    }
+
+    #[test]
+    fn duplicate_data_is_reported_if_any_filter_asks_and_name_prefixes_are_dropped() {
+        let first = DiscoveryFilter { duplicate_data: true, ..DiscoveryFilter::default() };
+        let second = DiscoveryFilter { name_prefix: Some("Heart".to_owned()), ..DiscoveryFilter::default() };
+        let merged = merge_filters(&[&first, &second]);
+        assert!(merged.duplicate_data);
+        assert_eq!(merged.name_prefix, None);
+    }
+}
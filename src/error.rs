@@ -0,0 +1,155 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::error::Error;
+use std::fmt;
+
+/// ATT protocol error codes (Bluetooth Core Specification, Vol 3, Part F, 3.4.1.1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttError {
+    InvalidHandle,
+    ReadNotPermitted,
+    WriteNotPermitted,
+    InvalidPdu,
+    InsufficientAuthentication,
+    RequestNotSupported,
+    InvalidOffset,
+    InsufficientAuthorization,
+    PrepareQueueFull,
+    AttributeNotFound,
+    AttributeNotLong,
+    InsufficientEncryptionKeySize,
+    InvalidAttributeValueLength,
+    UnlikelyError,
+    InsufficientEncryption,
+    UnsupportedGroupType,
+    InsufficientResources,
+    /// Reserved, application or profile specific error code.
+    Other(u8),
+}
+
+impl AttError {
+    pub fn from_code(code: u8) -> AttError {
+        match code {
+            0x01 => AttError::InvalidHandle,
+            0x02 => AttError::ReadNotPermitted,
+            0x03 => AttError::WriteNotPermitted,
+            0x04 => AttError::InvalidPdu,
+            0x05 => AttError::InsufficientAuthentication,
+            0x06 => AttError::RequestNotSupported,
+            0x07 => AttError::InvalidOffset,
+            0x08 => AttError::InsufficientAuthorization,
+            0x09 => AttError::PrepareQueueFull,
+            0x0A => AttError::AttributeNotFound,
+            0x0B => AttError::AttributeNotLong,
+            0x0C => AttError::InsufficientEncryptionKeySize,
+            0x0D => AttError::InvalidAttributeValueLength,
+            0x0E => AttError::UnlikelyError,
+            0x0F => AttError::InsufficientEncryption,
+            0x10 => AttError::UnsupportedGroupType,
+            0x11 => AttError::InsufficientResources,
+            code => AttError::Other(code),
+        }
+    }
+
+    pub fn code(&self) -> u8 {
+        match *self {
+            AttError::InvalidHandle => 0x01,
+            AttError::ReadNotPermitted => 0x02,
+            AttError::WriteNotPermitted => 0x03,
+            AttError::InvalidPdu => 0x04,
+            AttError::InsufficientAuthentication => 0x05,
+            AttError::RequestNotSupported => 0x06,
+            AttError::InvalidOffset => 0x07,
+            AttError::InsufficientAuthorization => 0x08,
+            AttError::PrepareQueueFull => 0x09,
+            AttError::AttributeNotFound => 0x0A,
+            AttError::AttributeNotLong => 0x0B,
+            AttError::InsufficientEncryptionKeySize => 0x0C,
+            AttError::InvalidAttributeValueLength => 0x0D,
+            AttError::UnlikelyError => 0x0E,
+            AttError::InsufficientEncryption => 0x0F,
+            AttError::UnsupportedGroupType => 0x10,
+            AttError::InsufficientResources => 0x11,
+            AttError::Other(code) => code,
+        }
+    }
+}
+
+/// The error type of every fallible operation in this crate.
+///
+/// Backends map their own failures into the matching variant, and use `Other` with the
+/// backend's message for anything that has no dedicated variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BluetoothError {
+    /// The platform, the backend or the given object does not support the operation.
+    NotSupported,
+    AdapterNotFound,
+    AdapterOff,
+    DeviceNotFound,
+    ServiceNotFound,
+    CharacteristicNotFound,
+    DescriptorNotFound,
+    NotConnected,
+    ConnectionFailed,
+    NotAuthorized,
+    InProgress,
+    /// The remote device answered with an ATT error response.
+    GattOperationFailed(AttError),
+    Timeout,
+    /// The operation was cancelled through its `CancellationToken`.
+    Cancelled,
+    /// The backend dropped the operation without completing it.
+    Abandoned,
+    /// The string could not be parsed as a Bluetooth UUID.
+    InvalidUuid(String),
+    /// The string could not be parsed as a Bluetooth device address.
+    InvalidAddress(String),
+    /// The GATT blocklist forbids the operation on the attribute.
+    Blocklisted,
+    /// The line could not be parsed as a GATT blocklist entry.
+    InvalidBlocklist(String),
+    /// The `requestDevice` options are invalid, for the given reason.
+    InvalidOptions(String),
+    /// The bytes are not a sequence of AD structures.
+    InvalidAdvertisementData(Vec<u8>),
+    /// The beacon cannot be encoded, for the given reason.
+    InvalidBeacon(String),
+    InvalidModalias(String),
+    Other(String),
+}
+
+impl fmt::Display for BluetoothError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BluetoothError::NotSupported => write!(f, "Error! Not supported!"),
+            BluetoothError::AdapterNotFound => write!(f, "Error! No bluetooth adapter found!"),
+            BluetoothError::AdapterOff => write!(f, "Error! The bluetooth adapter is turned off!"),
+            BluetoothError::DeviceNotFound => write!(f, "Error! No device found!"),
+            BluetoothError::ServiceNotFound => write!(f, "Error! No service found!"),
+            BluetoothError::CharacteristicNotFound => write!(f, "Error! No characteristic found!"),
+            BluetoothError::DescriptorNotFound => write!(f, "Error! No descriptor found!"),
+            BluetoothError::NotConnected => write!(f, "Error! The device is not connected!"),
+            BluetoothError::ConnectionFailed => write!(f, "Error! Could not connect to the device!"),
+            BluetoothError::NotAuthorized => write!(f, "Error! Not authorized!"),
+            BluetoothError::InProgress => write!(f, "Error! Operation already in progress!"),
+            BluetoothError::GattOperationFailed(ref att_error) =>
+                write!(f, "Error! GATT operation failed with ATT error 0x{:02X} ({:?})!", att_error.code(), att_error),
+            BluetoothError::Timeout => write!(f, "Error! Operation timed out!"),
+            BluetoothError::Cancelled => write!(f, "Error! Operation cancelled!"),
+            BluetoothError::Abandoned => write!(f, "Error! The operation was abandoned!"),
+            BluetoothError::InvalidUuid(ref uuid) => write!(f, "Error! Invalid UUID: {}!", uuid),
+            BluetoothError::InvalidAddress(ref address) => write!(f, "Error! Invalid address: {}!", address),
+            BluetoothError::Blocklisted => write!(f, "Error! The attribute is blocklisted!"),
+            BluetoothError::InvalidBlocklist(ref line) => write!(f, "Error! Invalid blocklist entry: {}!", line),
+            BluetoothError::InvalidOptions(ref reason) => write!(f, "Error! Invalid options: {}!", reason),
+            BluetoothError::InvalidAdvertisementData(ref data) => write!(f, "Error! Invalid advertising data: {:02X?}!", data),
+            BluetoothError::InvalidBeacon(ref reason) => write!(f, "Error! Invalid beacon: {}!", reason),
+            BluetoothError::InvalidModalias(ref modalias) => write!(f, "Error! Invalid modalias: {}!", modalias),
+            BluetoothError::Other(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for BluetoothError {}
@@ -0,0 +1,44 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::stream::{Hub, Stream};
+
+/// The device property reported as changed by `AdapterEvent::DeviceUpdated`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceProperty {
+    Name,
+    Rssi,
+    TxPower,
+    Uuids,
+    ManufacturerData,
+    ServiceData,
+    Paired,
+    Trusted,
+    Blocked,
+}
+
+/// A change on an adapter or on one of its devices. Devices are identified by their id,
+/// as returned by `BluetoothDevice::get_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdapterEvent {
+    DeviceDiscovered(String),
+    DeviceUpdated(String, DeviceProperty),
+    /// The adapter forgot about the device, e.g. because it was removed or went out of range.
+    DeviceLost(String),
+    Connected(String),
+    Disconnected(String),
+    /// The ATT MTU of the connection to the device changed.
+    MtuChanged(String, u16),
+    PoweredChanged(bool),
+    DiscoveringChanged(bool),
+}
+
+/// The receiving end of an adapter's events.
+///
+/// The stream ends, i.e. `recv` returns `None` and the iteration stops, when the backend
+/// stops watching the adapter.
+pub type AdapterEventStream = Stream<AdapterEvent>;
+
+/// Delivers the events of one adapter to all of its streams, with `send`.
+pub type AdapterEventHub = Hub<AdapterEvent>;
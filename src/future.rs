@@ -0,0 +1,587 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::error::BluetoothError;
+
+use std::cell::Cell;
+use std::fmt;
+use std::future::{self, Future};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+/// The default timeouts of the blocking operations, for an adapter and the objects obtained
+/// from it. `None` lets an operation block until the backend gives up.
+///
+/// The timeouts interrupt the backends which run the operations in the background (Linux and
+/// the mock backend), and bound the waits of the macOS backend for CoreBluetooth. The Android
+/// backend cannot be interrupted, and performs the operations in full.
+///
+/// The timeouts are set per adapter, or per handle with `with_operation_timeouts`. A single
+/// call is given its own timeout, or a `CancellationToken`, by waiting on its `*_async` variant,
+/// e.g. `device.connect_async().cancellable(&token).wait(Some(timeout))`; on macOS and Android,
+/// the operation has then already run, within the timeouts of the handle, when the future is
+/// returned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OperationTimeouts {
+    /// Bounds `connect`, `disconnect` and `pair`.
+    pub connect: Option<Duration>,
+    /// Bounds the attribute discovery, the reads and the writes, and the start and the stop
+    /// of the notifications.
+    pub gatt: Option<Duration>,
+}
+
+/// The timeouts shared by the handles on an adapter and by the objects obtained from them, or
+/// by the objects obtained from a handle given its own timeouts.
+#[derive(Debug, Default)]
+pub(crate) struct TimeoutSettings(Mutex<OperationTimeouts>);
+
+impl TimeoutSettings {
+    pub(crate) fn new(timeouts: OperationTimeouts) -> TimeoutSettings {
+        TimeoutSettings(Mutex::new(timeouts))
+    }
+
+    pub(crate) fn get(&self) -> OperationTimeouts {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub(crate) fn set(&self, timeouts: OperationTimeouts) {
+        *self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = timeouts;
+    }
+}
+
+thread_local! {
+    static BLOCKING_TIMEOUT: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+// Runs a blocking operation, or its asynchronous variant if it has to be interrupted. Backends
+// which cannot run the operation in the background return `BluetoothFuture::unavailable`, in
+// which case it runs blocking after all, and the backend enforces the timeout itself.
+pub(crate) fn run_with_timeout<T, B, A>(timeout: Option<Duration>, blocking: B, asynchronous: A) -> Result<T, BluetoothError>
+    where T: 'static, B: FnOnce() -> Result<T, BluetoothError>, A: FnOnce() -> BluetoothFuture<T> {
+    if let Some(timeout) = timeout {
+        let future = asynchronous();
+        if !future.unavailable {
+            return future.wait(Some(timeout));
+        }
+    }
+    run_blocking(timeout, blocking)
+}
+
+// Starts the asynchronous variant of an operation. Backends which cannot run the operation in
+// the background perform it here instead, bounded by `timeout`, and the future is complete.
+pub(crate) fn start_async<T, B, A>(timeout: Option<Duration>, blocking: B, asynchronous: A) -> BluetoothFuture<T>
+    where T: Send + 'static, B: FnOnce() -> Result<T, BluetoothError>, A: FnOnce() -> BluetoothFuture<T> {
+    let future = asynchronous();
+    if future.unavailable {
+        return BluetoothFuture::ready(run_blocking(timeout, blocking));
+    }
+    future
+}
+
+fn run_blocking<T, B>(timeout: Option<Duration>, blocking: B) -> Result<T, BluetoothError>
+    where B: FnOnce() -> Result<T, BluetoothError> {
+    let previous = BLOCKING_TIMEOUT.with(|current| current.replace(timeout));
+    let result = blocking();
+    BLOCKING_TIMEOUT.with(|current| current.set(previous));
+    result
+}
+
+/// The timeout of the blocking operation running on the current thread, which the backends
+/// that cannot run operations in the background have to enforce themselves.
+pub fn blocking_timeout() -> Option<Duration> {
+    BLOCKING_TIMEOUT.with(|current| current.get())
+}
+
+/// The result of an asynchronous operation.
+///
+/// Dropping the future cancels the operation: an operation that has not started yet is not
+/// performed, and the result of a running one is discarded.
+///
+/// Only Linux and the mock backend run the operations in the background. On Android, macOS
+/// and the platforms without a backend, whose objects cannot be sent to another thread, the
+/// `*_async` methods perform the operation when they are called, bounded by the timeouts of
+/// the handle, and return a completed future; listing the attributes asynchronously fails with
+/// `NotSupported` there, since the listed objects could not be sent along with the future.
+#[must_use]
+pub struct BluetoothFuture<T> {
+    future: Pin<Box<dyn Future<Output = Result<T, BluetoothError>> + Send>>,
+    unavailable: bool,
+}
+
+impl<T: 'static> BluetoothFuture<T> {
+    pub fn new<F>(future: F) -> BluetoothFuture<T>
+        where F: Future<Output = Result<T, BluetoothError>> + Send + 'static {
+        BluetoothFuture { future: Box::pin(future), unavailable: false }
+    }
+
+    /// The asynchronous variant of an operation on a backend which cannot run it in the
+    /// background. The blocking methods then perform the operation on the calling thread. The
+    /// future fails with `NotSupported`.
+    pub fn unavailable() -> BluetoothFuture<T> {
+        BluetoothFuture { unavailable: true, ..BluetoothFuture::failed(BluetoothError::NotSupported) }
+    }
+
+    /// A future that is already complete, for backends that can only block.
+    pub fn ready(result: Result<T, BluetoothError>) -> BluetoothFuture<T> where T: Send {
+        BluetoothFuture::new(future::ready(result))
+    }
+
+    /// A future that fails with `error`, whatever the type of its value.
+    pub fn failed(error: BluetoothError) -> BluetoothFuture<T> {
+        BluetoothFuture::new(Failed { error: Some(error), value: PhantomData })
+    }
+
+    /// Returns a pending future, which completes when the backend calls `complete` on the
+    /// returned `Completion`.
+    pub fn pending() -> (BluetoothFuture<T>, Completion<T>) where T: Send {
+        let state = Arc::new(Mutex::new(OperationState { result: None, waker: None, cancelled: false }));
+        let operation = Operation { state: state.clone() };
+        (BluetoothFuture::new(operation), Completion { state: Some(state) })
+    }
+
+    /// Runs a blocking operation on its own thread. Once the operation has started it runs to
+    /// completion, even if the future is dropped or times out in the meantime.
+    pub fn spawn<F>(operation: F) -> BluetoothFuture<T>
+        where F: FnOnce() -> Result<T, BluetoothError> + Send + 'static, T: Send {
+        let (future, completion) = BluetoothFuture::pending();
+        thread::spawn(move || {
+            if !completion.is_cancelled() {
+                completion.complete(operation());
+            }
+        });
+        future
+    }
+
+    /// Converts the value of a successful operation, when it completes.
+    pub fn map<U, F>(self, f: F) -> BluetoothFuture<U>
+        where F: FnOnce(T) -> U + Send + 'static, U: 'static {
+        self.and_then(move |value| Ok(f(value)))
+    }
+
+    /// Converts the value of a successful operation, when it completes, with a conversion
+    /// which may fail.
+    pub fn and_then<U, F>(self, f: F) -> BluetoothFuture<U>
+        where F: FnOnce(T) -> Result<U, BluetoothError> + Send + 'static, U: 'static {
+        BluetoothFuture::new(Map { future: self, f: Some(f) })
+    }
+
+    /// Fails the operation with `Cancelled` as soon as the token is cancelled.
+    pub fn cancellable(self, token: &CancellationToken) -> BluetoothFuture<T> {
+        let id = token.register();
+        BluetoothFuture::new(Cancellable { future: self, token: token.clone(), id })
+    }
+
+    /// Blocks the current thread until the operation completes, or fails with `Timeout` and
+    /// cancels the operation if it takes longer than the timeout.
+    pub fn wait(mut self, timeout: Option<Duration>) -> Result<T, BluetoothError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(result) = Pin::new(&mut self).poll(&mut context) {
+                return result;
+            }
+            match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(BluetoothError::Timeout);
+                    }
+                    thread::park_timeout(deadline - now);
+                },
+                None => thread::park(),
+            }
+        }
+    }
+}
+
+impl<T> Future for BluetoothFuture<T> {
+    type Output = Result<T, BluetoothError>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<T, BluetoothError>> {
+        self.future.as_mut().poll(context)
+    }
+}
+
+impl<T> fmt::Debug for BluetoothFuture<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BluetoothFuture")
+    }
+}
+
+struct OperationState<T> {
+    result: Option<Result<T, BluetoothError>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+}
+
+fn lock<T>(state: &Mutex<OperationState<T>>) -> MutexGuard<'_, OperationState<T>> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+struct Operation<T> {
+    state: Arc<Mutex<OperationState<T>>>,
+}
+
+impl<T> Future for Operation<T> {
+    type Output = Result<T, BluetoothError>;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<T, BluetoothError>> {
+        let mut state = lock(&self.state);
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<T> Drop for Operation<T> {
+    fn drop(&mut self) {
+        lock(&self.state).cancelled = true;
+    }
+}
+
+/// The backend side of a pending `BluetoothFuture`.
+///
+/// Dropping it without calling `complete` fails the future, so that it does not wait forever.
+pub struct Completion<T> {
+    state: Option<Arc<Mutex<OperationState<T>>>>,
+}
+
+impl<T> Completion<T> {
+    /// Whether the future was dropped, in which case the operation can be abandoned.
+    pub fn is_cancelled(&self) -> bool {
+        match self.state {
+            Some(ref state) => lock(state).cancelled,
+            None => true,
+        }
+    }
+
+    pub fn complete(mut self, result: Result<T, BluetoothError>) {
+        if let Some(state) = self.state.take() {
+            let waker = {
+                let mut state = lock(&state);
+                state.result = Some(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        if self.state.is_some() {
+            let state = self.state.take();
+            Completion { state }.complete(Err(BluetoothError::Abandoned));
+        }
+    }
+}
+
+impl<T> fmt::Debug for Completion<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Completion")
+    }
+}
+
+struct Map<T, F> {
+    future: BluetoothFuture<T>,
+    f: Option<F>,
+}
+
+// Neither field is structurally pinned: the inner future is boxed.
+impl<T, F> Unpin for Map<T, F> {}
+
+impl<T, U, F> Future for Map<T, F> where F: FnOnce(T) -> Result<U, BluetoothError> {
+    type Output = Result<U, BluetoothError>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<U, BluetoothError>> {
+        let result = match Pin::new(&mut self.future).poll(context) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        let f = self.f.take().expect("Map polled after completion");
+        Poll::Ready(result.and_then(f))
+    }
+}
+
+struct Failed<T> {
+    error: Option<BluetoothError>,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T> Future for Failed<T> {
+    type Output = Result<T, BluetoothError>;
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<Result<T, BluetoothError>> {
+        Poll::Ready(Err(self.error.take().expect("Failed polled after completion")))
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: bool,
+    // The wakers of the pending operations made cancellable with the token, by operation.
+    wakers: Vec<(usize, Waker)>,
+    next_id: usize,
+}
+
+/// Cancels the operations made `cancellable` with it, e.g. from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<Mutex<CancellationState>>);
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        let wakers = {
+            let mut state = self.lock();
+            state.cancelled = true;
+            std::mem::take(&mut state.wakers)
+        };
+        for (_, waker) in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.lock().cancelled
+    }
+
+    // Returns the id of a new cancellable operation.
+    fn register(&self) -> usize {
+        let mut state = self.lock();
+        state.next_id += 1;
+        state.next_id
+    }
+
+    fn forget(&self, id: usize) {
+        self.lock().wakers.retain(|(waker_id, _)| *waker_id != id);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CancellationState> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+struct Cancellable<T> {
+    future: BluetoothFuture<T>,
+    token: CancellationToken,
+    id: usize,
+}
+
+impl<T> Future for Cancellable<T> {
+    type Output = Result<T, BluetoothError>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Result<T, BluetoothError>> {
+        {
+            let id = self.id;
+            let mut state = self.token.lock();
+            if state.cancelled {
+                return Poll::Ready(Err(BluetoothError::Cancelled));
+            }
+            match state.wakers.iter_mut().find(|(waker_id, _)| *waker_id == id) {
+                Some((_, waker)) => waker.clone_from(context.waker()),
+                None => state.wakers.push((id, context.waker().clone())),
+            }
+        }
+        let result = Pin::new(&mut self.future).poll(context);
+        if result.is_ready() {
+            self.token.forget(self.id);
+        }
+        result
+    }
+}
+
+// A token outlives the operations it cancels, so they take their wakers back.
+impl<T> Drop for Cancellable<T> {
+    fn drop(&mut self) {
+        self.token.forget(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blocking_timeout, run_with_timeout, start_async, BluetoothError, BluetoothFuture, CancellationToken};
+    use std::rc::Rc;
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn futures_are_send_even_with_values_that_are_not() {
+        let future = BluetoothFuture::ready(Ok(1)).map(Rc::new);
+        assert_send(&future);
+        assert_eq!(*future.wait(None).unwrap(), 1);
+        assert_send(&BluetoothFuture::<Rc<u8>>::failed(BluetoothError::NotSupported));
+    }
+
+    #[test]
+    fn ready_and_failed_futures_are_complete() {
+        assert_eq!(BluetoothFuture::ready(Ok(1)).wait(Some(Duration::from_secs(0))), Ok(1));
+        assert_eq!(BluetoothFuture::<u8>::failed(BluetoothError::NotConnected).wait(None),
+                   Err(BluetoothError::NotConnected));
+    }
+
+    #[test]
+    fn pending_futures_complete_from_another_thread() {
+        let (future, completion) = BluetoothFuture::pending();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            completion.complete(Ok(vec![1, 2]));
+        });
+        assert_eq!(future.wait(Some(WAIT)), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn dropping_the_completion_abandons_the_operation() {
+        let (future, completion) = BluetoothFuture::<()>::pending();
+        drop(completion);
+        assert_eq!(future.wait(Some(WAIT)), Err(BluetoothError::Abandoned));
+    }
+
+    #[test]
+    fn dropping_the_future_cancels_the_operation() {
+        let (future, completion) = BluetoothFuture::<()>::pending();
+        assert!(!completion.is_cancelled());
+        drop(future);
+        assert!(completion.is_cancelled());
+        completion.complete(Ok(()));
+    }
+
+    #[test]
+    fn waiting_too_long_times_out() {
+        let (future, completion) = BluetoothFuture::<()>::pending();
+        assert_eq!(future.wait(Some(Duration::from_millis(20))), Err(BluetoothError::Timeout));
+        assert!(completion.is_cancelled());
+    }
+
+    #[test]
+    fn spawned_operations_run_on_another_thread() {
+        let caller = thread::current().id();
+        let future = BluetoothFuture::spawn(move || Ok(thread::current().id() != caller));
+        assert_eq!(future.wait(Some(WAIT)), Ok(true));
+    }
+
+    #[test]
+    fn map_converts_only_successful_values() {
+        assert_eq!(BluetoothFuture::ready(Ok(2)).map(|value| value * 2).wait(None), Ok(4));
+        let failed = BluetoothFuture::<u8>::failed(BluetoothError::Timeout).map(|value| value * 2);
+        assert_eq!(failed.wait(None), Err(BluetoothError::Timeout));
+    }
+
+    #[test]
+    fn and_then_may_fail_the_operation() {
+        assert_eq!(BluetoothFuture::ready(Ok(2)).and_then(|value| Ok(value * 2)).wait(None), Ok(4));
+        let rejected = BluetoothFuture::ready(Ok(2)).and_then(|_| Err::<u8, _>(BluetoothError::Blocklisted));
+        assert_eq!(rejected.wait(None), Err(BluetoothError::Blocklisted));
+        let failed = BluetoothFuture::<u8>::failed(BluetoothError::Timeout).and_then(|_| Err::<u8, _>(BluetoothError::Blocklisted));
+        assert_eq!(failed.wait(None), Err(BluetoothError::Timeout));
+    }
+
+    #[test]
+    fn run_with_timeout_blocks_only_without_a_timeout() {
+        let (sender, receiver) = channel();
+        let blocking_sender = sender.clone();
+        let result = run_with_timeout(None,
+                                      move || {
+                                          blocking_sender.send("blocking").unwrap();
+                                          Ok(1)
+                                      },
+                                      move || {
+                                          sender.send("asynchronous").unwrap();
+                                          BluetoothFuture::ready(Ok(2))
+                                      });
+        assert_eq!(result, Ok(1));
+        assert_eq!(receiver.try_recv(), Ok("blocking"));
+        assert!(receiver.try_recv().is_err());
+
+        let result = run_with_timeout(Some(WAIT), || Ok(1), || BluetoothFuture::ready(Ok(2)));
+        assert_eq!(result, Ok(2));
+    }
+
+    #[test]
+    fn run_with_timeout_blocks_if_the_backend_cannot_run_in_the_background() {
+        let result = run_with_timeout(Some(WAIT), || Ok(blocking_timeout()), BluetoothFuture::unavailable);
+        assert_eq!(result, Ok(Some(WAIT)));
+        let result = run_with_timeout(None, || Ok(blocking_timeout()), BluetoothFuture::unavailable);
+        assert_eq!(result, Ok(None));
+        assert_eq!(blocking_timeout(), None);
+    }
+
+    #[test]
+    fn run_with_timeout_does_not_rerun_an_unsupported_operation() {
+        let (sender, receiver) = channel();
+        let result: Result<(), _> = run_with_timeout(Some(WAIT),
+                                                     move || {
+                                                         sender.send("blocking").unwrap();
+                                                         Ok(())
+                                                     },
+                                                     || BluetoothFuture::failed(BluetoothError::NotSupported));
+        assert_eq!(result, Err(BluetoothError::NotSupported));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn start_async_performs_the_operation_if_the_backend_cannot_run_in_the_background() {
+        let future = start_async(Some(WAIT), || Ok(blocking_timeout()), BluetoothFuture::unavailable);
+        assert_eq!(future.wait(None), Ok(Some(WAIT)));
+        let future = start_async(Some(WAIT), || Ok(1), || BluetoothFuture::ready(Ok(2)));
+        assert_eq!(future.wait(None), Ok(2));
+        assert_eq!(BluetoothFuture::<()>::unavailable().wait(None), Err(BluetoothError::NotSupported));
+    }
+
+    #[test]
+    fn cancelling_a_token_cancels_its_pending_operations() {
+        let token = CancellationToken::new();
+        let (future, completion) = BluetoothFuture::<()>::pending();
+        let canceller = token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            canceller.cancel();
+        });
+        assert_eq!(future.cancellable(&token).wait(Some(WAIT)), Err(BluetoothError::Cancelled));
+        assert!(completion.is_cancelled());
+    }
+
+    #[test]
+    fn operations_take_their_wakers_back_from_the_token() {
+        let token = CancellationToken::new();
+        let (future, completion) = BluetoothFuture::<u8>::pending();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            completion.complete(Ok(1));
+        });
+        assert_eq!(future.cancellable(&token).wait(Some(WAIT)), Ok(1));
+        assert!(token.lock().wakers.is_empty());
+
+        let (future, _completion) = BluetoothFuture::<u8>::pending();
+        assert_eq!(future.cancellable(&token).wait(Some(Duration::from_millis(20))),
+                   Err(BluetoothError::Timeout));
+        assert!(token.lock().wakers.is_empty());
+    }
+}
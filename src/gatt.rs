@@ -0,0 +1,237 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::error::{AttError, BluetoothError};
+use std::convert::TryFrom;
+
+/// The ATT procedure used to write a characteristic value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WriteType {
+    /// A Write Request, acknowledged by the device.
+    WithResponse,
+    /// A Write Command, which the device does not acknowledge.
+    WithoutResponse,
+    /// A Signed Write Command, authenticated without encrypting the link. BlueZ signs the
+    /// Write Command only while the link is not encrypted, since encryption authenticates it
+    /// already; CoreBluetooth cannot be asked for a signed write.
+    Signed,
+    /// A Queued Write (prepare and execute), which the device applies only if it received
+    /// the value intact.
+    Reliable,
+}
+
+impl WriteType {
+    /// The characteristic flag, as returned by `get_flags`, which permits the write type.
+    pub fn flag(&self) -> &'static str {
+        match *self {
+            WriteType::WithResponse => "write",
+            WriteType::WithoutResponse => "write-without-response",
+            WriteType::Signed => "authenticated-signed-writes",
+            WriteType::Reliable => "reliable-write",
+        }
+    }
+}
+
+/// The longest value a Write Request carries within the ATT `mtu`. Longer values are long
+/// attributes, written with Prepare Write requests.
+pub fn max_write_length(mtu: u16) -> usize {
+    usize::from(mtu).saturating_sub(3)
+}
+
+/// The most of a value a Read or a Read Blob Response carries within the ATT `mtu`.
+pub fn max_read_length(mtu: u16) -> usize {
+    usize::from(mtu).saturating_sub(1)
+}
+
+/// The most of a value a Prepare Write Request carries within the ATT `mtu`.
+pub fn max_prepare_write_length(mtu: u16) -> usize {
+    usize::from(mtu).saturating_sub(5)
+}
+
+fn offset_after(offset: u16, length: usize) -> Result<u16, BluetoothError> {
+    usize::from(offset).checked_add(length)
+                       .and_then(|end| u16::try_from(end).ok())
+                       .ok_or(BluetoothError::GattOperationFailed(AttError::InvalidOffset))
+}
+
+/// Completes a read which returned `first` from `offset` on: a full response means the value
+/// goes on, so it is read with Read Blob requests, by `read_at`, until a response falls short.
+pub fn read_long<F>(mtu: u16, offset: u16, first: Vec<u8>, mut read_at: F) -> Result<Vec<u8>, BluetoothError>
+    where F: FnMut(u16) -> Result<Vec<u8>, BluetoothError> {
+    let mut offset = offset;
+    let mut value = first;
+    let mut last_length = value.len();
+    while last_length >= max_read_length(mtu).max(1) {
+        offset = offset_after(offset, last_length)?;
+        let part = read_at(offset)?;
+        last_length = part.len();
+        value.extend(part);
+    }
+    Ok(value)
+}
+
+/// Writes the value `values`: the start with a Write Request, by `write`, and the rest, if the
+/// value is long, with Prepare Write requests, by `write_at`.
+pub fn write_long<W, F>(mtu: u16, values: Vec<u8>, write: W, write_at: F) -> Result<(), BluetoothError>
+    where W: FnOnce(Vec<u8>) -> Result<(), BluetoothError>, F: FnMut(u16, Vec<u8>) -> Result<(), BluetoothError> {
+    let length = max_write_length(mtu);
+    if values.len() <= length {
+        return write(values);
+    }
+    let mut start = values;
+    let rest = start.split_off(length);
+    write(start)?;
+    write_long_at(mtu, offset_after(0, length)?, rest, write_at)
+}
+
+/// Writes `values` from `offset` on with as many Prepare Write requests, by `write_at`, as
+/// their length takes.
+pub fn write_long_at<F>(mtu: u16, offset: u16, values: Vec<u8>, mut write_at: F) -> Result<(), BluetoothError>
+    where F: FnMut(u16, Vec<u8>) -> Result<(), BluetoothError> {
+    let length = max_prepare_write_length(mtu).max(1);
+    if values.len() <= length {
+        return write_at(offset, values);
+    }
+    let mut offset = offset;
+    for part in values.chunks(length) {
+        write_at(offset, part.to_vec())?;
+        offset = offset_after(offset, part.len())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_long, write_long, write_long_at, AttError, BluetoothError};
+    use std::cell::RefCell;
+
+    // The default ATT MTU: reads carry 22 bytes and Prepare Write requests 18.
+    const MTU: u16 = 23;
+
+    fn value(length: usize) -> Vec<u8> {
+        (0..length).map(|byte| byte as u8).collect()
+    }
+
+    fn read_from(value: &[u8], offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        let offset = usize::from(offset);
+        if offset > value.len() {
+            return Err(BluetoothError::GattOperationFailed(AttError::InvalidOffset));
+        }
+        Ok(value[offset..].iter().take(22).cloned().collect())
+    }
+
+    #[test]
+    fn short_reads_take_a_single_request() {
+        let mut offsets = vec![];
+        let result = read_long(MTU, 0, value(21), |offset| {
+            offsets.push(offset);
+            Ok(vec![])
+        });
+        assert_eq!(result, Ok(value(21)));
+        assert!(offsets.is_empty());
+    }
+
+    #[test]
+    fn long_reads_go_on_until_a_response_falls_short() {
+        let full = value(50);
+        let mut offsets = vec![];
+        let result = read_long(MTU, 0, read_from(&full, 0).unwrap(), |offset| {
+            offsets.push(offset);
+            read_from(&full, offset)
+        });
+        assert_eq!(result, Ok(full));
+        assert_eq!(offsets, vec![22, 44]);
+    }
+
+    #[test]
+    fn long_reads_end_with_an_empty_response() {
+        let full = value(54);
+        let mut offsets = vec![];
+        let result = read_long(MTU, 10, read_from(&full, 10).unwrap(), |offset| {
+            offsets.push(offset);
+            read_from(&full, offset)
+        });
+        assert_eq!(result, Ok(full[10..].to_vec()));
+        assert_eq!(offsets, vec![32, 54]);
+    }
+
+    #[test]
+    fn long_reads_fail_with_their_requests() {
+        let result = read_long(MTU, 0, value(22), |_| Err(BluetoothError::NotConnected));
+        assert_eq!(result, Err(BluetoothError::NotConnected));
+    }
+
+    #[test]
+    fn long_reads_stop_at_the_largest_offset() {
+        let result = read_long(MTU, u16::MAX - 10, value(22), |_| Ok(value(22)));
+        assert_eq!(result, Err(BluetoothError::GattOperationFailed(AttError::InvalidOffset)));
+    }
+
+    #[test]
+    fn short_writes_take_a_single_request() {
+        let mut requests = vec![];
+        write_long_at(MTU, 4, value(18), |offset, values| {
+            requests.push((offset, values));
+            Ok(())
+        }).unwrap();
+        assert_eq!(requests, vec![(4, value(18))]);
+
+        requests.clear();
+        write_long_at(MTU, 0, vec![], |offset, values| {
+            requests.push((offset, values));
+            Ok(())
+        }).unwrap();
+        assert_eq!(requests, vec![(0, vec![])]);
+    }
+
+    #[test]
+    fn long_writes_are_split_in_prepare_write_requests() {
+        let values = value(40);
+        let mut requests = vec![];
+        write_long_at(MTU, 2, values.clone(), |offset, values| {
+            requests.push((offset, values));
+            Ok(())
+        }).unwrap();
+        assert_eq!(requests, vec![(2, values[..18].to_vec()), (20, values[18..36].to_vec()), (38, values[36..].to_vec())]);
+    }
+
+    #[test]
+    fn long_writes_stop_at_the_first_failure() {
+        let mut requests = 0;
+        let result = write_long_at(MTU, 0, value(40), |_, _| {
+            requests += 1;
+            Err(BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength))
+        });
+        assert_eq!(result, Err(BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength)));
+        assert_eq!(requests, 1);
+    }
+
+    #[test]
+    fn short_values_take_a_single_write_request() {
+        let requests = RefCell::new(vec![]);
+        write_long(MTU, value(20), |values| {
+            requests.borrow_mut().push((None, values));
+            Ok(())
+        }, |offset, values| {
+            requests.borrow_mut().push((Some(offset), values));
+            Ok(())
+        }).unwrap();
+        assert_eq!(requests.into_inner(), vec![(None, value(20))]);
+    }
+
+    #[test]
+    fn long_values_go_on_with_prepare_write_requests() {
+        let values = value(45);
+        let requests = RefCell::new(vec![]);
+        write_long(MTU, values.clone(), |values| {
+            requests.borrow_mut().push((None, values));
+            Ok(())
+        }, |offset, values| {
+            requests.borrow_mut().push((Some(offset), values));
+            Ok(())
+        }).unwrap();
+        assert_eq!(requests.into_inner(),
+                   vec![(None, values[..20].to_vec()), (Some(20), values[20..38].to_vec()), (Some(38), values[38..].to_vec())]);
+    }
+}
@@ -1,19 +1,47 @@
-/* This Source Code Form is subject to the terms of the Mozilla Public
- *  * License, v. 2.0. If a copy of the MPL was not distributed with this
- *   * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
-
-#[cfg(all(target_os = "linux", feature = "bluetooth"))]
-extern crate blurz;
-#[cfg(all(target_os = "android", feature = "bluetooth"))]
-extern crate blurdroid;
-#[cfg(all(target_os = "macos", feature = "bluetooth"))]
-extern crate blurmac;
-#[cfg(feature = "bluetooth-test")]
-extern crate blurmock;
-pub mod adapter;
-pub mod bluetooth;
-#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
-              all(target_os = "android", feature = "bluetooth"),
-              all(target_os = "macos", feature = "bluetooth"))))]
-mod empty;
-mod macros;
\ No newline at end of file
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ *  * License, v. 2.0. If a copy of the MPL was not distributed with this
+ *   * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#[cfg(all(target_os = "linux", feature = "bluetooth"))]
+extern crate blurz;
+#[cfg(all(target_os = "linux", feature = "bluetooth"))]
+extern crate dbus;
+#[cfg(all(target_os = "android", feature = "bluetooth"))]
+extern crate blurdroid;
+#[cfg(all(target_os = "macos", feature = "bluetooth"))]
+extern crate blurmac;
+#[cfg(feature = "bluetooth-test")]
+extern crate blurmock;
+pub mod adapter;
+pub mod address;
+pub mod advertisement;
+pub mod appearance;
+pub mod backend;
+pub mod beacon;
+pub mod blocklist;
+pub mod bluetooth;
+pub mod class;
+pub mod connection;
+pub mod device_id;
+pub mod discovery;
+pub mod error;
+pub mod event;
+pub mod future;
+pub mod gatt;
+pub mod notification;
+pub mod pairing;
+pub mod request;
+pub mod stream;
+pub mod uuid;
+#[cfg(all(target_os = "linux", feature = "bluetooth"))]
+mod bluez;
+#[cfg(all(target_os = "android", feature = "bluetooth"))]
+mod android;
+#[cfg(all(target_os = "macos", feature = "bluetooth"))]
+mod mac;
+#[cfg(feature = "bluetooth-test")]
+mod mock;
+#[cfg(not(any(all(target_os = "linux", feature = "bluetooth"),
+              all(target_os = "android", feature = "bluetooth"),
+              all(target_os = "macos", feature = "bluetooth"))))]
+mod empty;
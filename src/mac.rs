@@ -0,0 +1,377 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use blurmac::BluetoothAdapter as BluetoothAdapterMac;
+use blurmac::BluetoothDevice as BluetoothDeviceMac;
+use blurmac::BluetoothDiscoverySession as BluetoothDiscoverySessionMac;
+use blurmac::BluetoothGATTCharacteristic as BluetoothGATTCharacteristicMac;
+use blurmac::BluetoothGATTDescriptor as BluetoothGATTDescriptorMac;
+use blurmac::BluetoothGATTService as BluetoothGATTServiceMac;
+
+use super::backend::{BluetoothAdapterBackend, BluetoothDeviceBackend, BluetoothDiscoverySessionBackend,
+                     BluetoothGATTCharacteristicBackend, BluetoothGATTDescriptorBackend, BluetoothGATTServiceBackend};
+use super::device_id::DeviceId;
+use super::error::BluetoothError;
+use super::future;
+use super::gatt::WriteType;
+use super::uuid::BluetoothUuid;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+pub(crate) fn map_error(error: Box<dyn Error>) -> BluetoothError {
+    match error.to_string().as_str() {
+        blurmac::NOT_SUPPORTED_ERROR => BluetoothError::NotSupported,
+        blurmac::NO_PERIPHERAL_FOUND => BluetoothError::DeviceNotFound,
+        blurmac::NO_SERVICE_FOUND => BluetoothError::ServiceNotFound,
+        blurmac::NO_CHARACTERISTIC_FOUND => BluetoothError::CharacteristicNotFound,
+        blurmac::TIMEOUT_ERROR => BluetoothError::Timeout,
+        message => BluetoothError::Other(message.to_owned()),
+    }
+}
+
+// Runs a blurmac operation which waits for CoreBluetooth, for at most the timeout of the
+// blocking operation, or blurmac's default.
+fn wait_with_timeout<T, F>(operation: F) -> Result<T, BluetoothError>
+    where F: FnOnce() -> Result<T, Box<dyn Error>> {
+    blurmac::with_timeout(future::blocking_timeout(), operation).map_err(map_error)
+}
+
+impl BluetoothAdapterBackend for Arc<BluetoothAdapterMac> {
+    fn get_id(&self) -> String {
+        BluetoothAdapterMac::get_id(self)
+    }
+
+    // blurmac objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_devices(&self) -> Result<Vec<Arc<dyn BluetoothDeviceBackend>>, BluetoothError> {
+        let device_list = self.get_device_list().map_err(map_error)?;
+        Ok(device_list.into_iter()
+                      .map(|device| Arc::new(Arc::new(BluetoothDeviceMac::new(self.clone(), device))) as Arc<dyn BluetoothDeviceBackend>)
+                      .collect())
+    }
+
+    fn create_discovery_session(&self) -> Result<Arc<dyn BluetoothDiscoverySessionBackend>, BluetoothError> {
+        let session = BluetoothDiscoverySessionMac::create_session(self.clone()).map_err(map_error)?;
+        Ok(Arc::new(Arc::new(session)))
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterMac::get_address(self).map_err(map_error)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterMac::get_name(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        BluetoothAdapterMac::get_alias(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterMac::get_class(self).map_err(map_error)
+    }
+
+    fn is_powered(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterMac::is_powered(self).map_err(map_error)
+    }
+
+    fn is_discoverable(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterMac::is_discoverable(self).map_err(map_error)
+    }
+
+    fn is_pairable(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterMac::is_pairable(self).map_err(map_error)
+    }
+
+    fn get_pairable_timeout(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterMac::get_pairable_timeout(self).map_err(map_error)
+    }
+
+    fn get_discoverable_timeout(&self) -> Result<u32, BluetoothError> {
+        BluetoothAdapterMac::get_discoverable_timeout(self).map_err(map_error)
+    }
+
+    fn is_discovering(&self) -> Result<bool, BluetoothError> {
+        BluetoothAdapterMac::is_discovering(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        BluetoothAdapterMac::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        DeviceId::from_parts(BluetoothAdapterMac::get_modalias(self).map_err(map_error)?)
+    }
+
+    fn set_alias(&self, alias: String) -> Result<(), BluetoothError> {
+        BluetoothAdapterMac::set_alias(self, alias).map_err(map_error)
+    }
+
+    fn set_powered(&self, powered: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterMac::set_powered(self, powered).map_err(map_error)
+    }
+
+    fn set_discoverable(&self, discoverable: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterMac::set_discoverable(self, discoverable).map_err(map_error)
+    }
+
+    fn set_pairable(&self, pairable: bool) -> Result<(), BluetoothError> {
+        BluetoothAdapterMac::set_pairable(self, pairable).map_err(map_error)
+    }
+
+    fn set_pairable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        BluetoothAdapterMac::set_pairable_timeout(self, timeout).map_err(map_error)
+    }
+
+    fn set_discoverable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        BluetoothAdapterMac::set_discoverable_timeout(self, timeout).map_err(map_error)
+    }
+}
+
+impl BluetoothDiscoverySessionBackend for Arc<BluetoothDiscoverySessionMac> {
+    fn start_discovery(&self) -> Result<(), BluetoothError> {
+        BluetoothDiscoverySessionMac::start_discovery(self).map_err(map_error)
+    }
+
+    fn stop_discovery(&self) -> Result<(), BluetoothError> {
+        BluetoothDiscoverySessionMac::stop_discovery(self).map_err(map_error)
+    }
+}
+
+impl BluetoothDeviceBackend for Arc<BluetoothDeviceMac> {
+    fn get_id(&self) -> String {
+        BluetoothDeviceMac::get_id(self)
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceMac::get_address(self).map_err(map_error)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceMac::get_name(self).map_err(map_error)
+    }
+
+    fn get_icon(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceMac::get_icon(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        BluetoothDeviceMac::get_class(self).map_err(map_error)
+    }
+
+    fn get_appearance(&self) -> Result<u16, BluetoothError> {
+        BluetoothDeviceMac::get_appearance(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        BluetoothDeviceMac::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn is_paired(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceMac::is_paired(self).map_err(map_error)
+    }
+
+    fn is_connected(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceMac::is_connected(self).map_err(map_error)
+    }
+
+    fn is_trusted(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceMac::is_trusted(self).map_err(map_error)
+    }
+
+    fn is_blocked(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceMac::is_blocked(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        BluetoothDeviceMac::get_alias(self).map_err(map_error)
+    }
+
+    fn is_legacy_pairing(&self) -> Result<bool, BluetoothError> {
+        BluetoothDeviceMac::is_legacy_pairing(self).map_err(map_error)
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        DeviceId::from_parts(BluetoothDeviceMac::get_modalias(self).map_err(map_error)?)
+    }
+
+    fn get_rssi(&self) -> Result<i16, BluetoothError> {
+        BluetoothDeviceMac::get_rssi(self).map_err(map_error)
+    }
+
+    fn get_tx_power(&self) -> Result<i16, BluetoothError> {
+        BluetoothDeviceMac::get_tx_power(self).map_err(map_error)
+    }
+
+    fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError> {
+        BluetoothDeviceMac::get_manufacturer_data(self).map_err(map_error)
+    }
+
+    fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError> {
+        BluetoothDeviceMac::get_service_data(self).map_err(map_error)?
+            .into_iter()
+            .map(|(uuid, data)| Ok((uuid.parse()?, data)))
+            .collect()
+    }
+
+    // blurmac objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_gatt_services(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        let services = wait_with_timeout(|| BluetoothDeviceMac::get_gatt_services(self))?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(Arc::new(BluetoothGATTServiceMac::new(self.clone(), service))) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    fn connect(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceMac::connect(self).map_err(map_error)
+    }
+
+    fn disconnect(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceMac::disconnect(self).map_err(map_error)
+    }
+
+    fn connect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        BluetoothDeviceMac::connect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn disconnect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        BluetoothDeviceMac::disconnect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn pair(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceMac::pair(self).map_err(map_error)
+    }
+
+    fn cancel_pairing(&self) -> Result<(), BluetoothError> {
+        BluetoothDeviceMac::cancel_pairing(self).map_err(map_error)
+    }
+
+    fn get_mtu(&self) -> Result<u16, BluetoothError> {
+        BluetoothDeviceMac::get_mtu(self).map_err(map_error)
+    }
+
+    fn get_advertisement_data(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothDeviceMac::get_advertisement_data(self).map_err(map_error)
+    }
+}
+
+impl BluetoothGATTServiceBackend for Arc<BluetoothGATTServiceMac> {
+    fn get_id(&self) -> String {
+        BluetoothGATTServiceMac::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTServiceMac::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn is_primary(&self) -> Result<bool, BluetoothError> {
+        BluetoothGATTServiceMac::is_primary(self).map_err(map_error)
+    }
+
+    // blurmac objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_includes(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        let device = self.get_device();
+        let services = wait_with_timeout(|| BluetoothGATTServiceMac::get_includes(self))?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(Arc::new(BluetoothGATTServiceMac::new(device.clone(), service))) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    // blurmac objects are neither Send nor Sync.
+    #[allow(clippy::arc_with_non_send_sync)]
+    fn get_gatt_characteristics(&self) -> Result<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>, BluetoothError> {
+        let characteristics = wait_with_timeout(|| BluetoothGATTServiceMac::get_gatt_characteristics(self))?;
+        Ok(characteristics.into_iter()
+                          .map(|characteristic|
+                              Arc::new(Arc::new(BluetoothGATTCharacteristicMac::new(self.clone(), characteristic)))
+                                  as Arc<dyn BluetoothGATTCharacteristicBackend>)
+                          .collect())
+    }
+}
+
+impl BluetoothGATTCharacteristicBackend for Arc<BluetoothGATTCharacteristicMac> {
+    fn get_id(&self) -> String {
+        BluetoothGATTCharacteristicMac::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTCharacteristicMac::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTCharacteristicMac::get_value(self).map_err(map_error)
+    }
+
+    fn is_notifying(&self) -> Result<bool, BluetoothError> {
+        BluetoothGATTCharacteristicMac::is_notifying(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        BluetoothGATTCharacteristicMac::get_flags(self).map_err(map_error)
+    }
+
+    fn get_gatt_descriptors(&self) -> Result<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>, BluetoothError> {
+        let descriptors = BluetoothGATTCharacteristicMac::get_gatt_descriptors(self).map_err(map_error)?;
+        Ok(descriptors.into_iter()
+                      .map(|descriptor|
+                          Arc::new(Arc::new(BluetoothGATTDescriptorMac::new(descriptor)))
+                              as Arc<dyn BluetoothGATTDescriptorBackend>)
+                      .collect())
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        wait_with_timeout(|| BluetoothGATTCharacteristicMac::read_value(self))
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        wait_with_timeout(|| BluetoothGATTCharacteristicMac::write_value(self, values))
+    }
+
+    // NOTE: CoreBluetooth exposes neither reliable nor signed writes.
+    fn write_value_with_type(&self, values: Vec<u8>, write_type: WriteType) -> Result<(), BluetoothError> {
+        match write_type {
+            WriteType::WithResponse => wait_with_timeout(|| BluetoothGATTCharacteristicMac::write_value(self, values)),
+            WriteType::WithoutResponse => BluetoothGATTCharacteristicMac::write_value_without_response(self, values).map_err(map_error),
+            WriteType::Signed | WriteType::Reliable => Err(BluetoothError::NotSupported),
+        }
+    }
+
+    fn start_notify(&self) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicMac::start_notify(self).map_err(map_error)
+    }
+
+    fn stop_notify(&self) -> Result<(), BluetoothError> {
+        BluetoothGATTCharacteristicMac::stop_notify(self).map_err(map_error)
+    }
+}
+
+impl BluetoothGATTDescriptorBackend for Arc<BluetoothGATTDescriptorMac> {
+    fn get_id(&self) -> String {
+        BluetoothGATTDescriptorMac::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        BluetoothGATTDescriptorMac::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorMac::get_value(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        BluetoothGATTDescriptorMac::get_flags(self).map_err(map_error)
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        BluetoothGATTDescriptorMac::read_value(self).map_err(map_error)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        BluetoothGATTDescriptorMac::write_value(self, values).map_err(map_error)
+    }
+}
@@ -0,0 +1,1179 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use blurmock::fake_adapter::FakeBluetoothAdapter;
+use blurmock::fake_characteristic::FakeBluetoothGATTCharacteristic;
+use blurmock::fake_descriptor::FakeBluetoothGATTDescriptor;
+use blurmock::fake_device::FakeBluetoothDevice;
+use blurmock::fake_discovery_session::FakeBluetoothDiscoverySession;
+use blurmock::fake_service::FakeBluetoothGATTService;
+
+use super::backend::{BluetoothAdapterBackend, BluetoothDeviceBackend, BluetoothDiscoverySessionBackend,
+                     BluetoothGATTCharacteristicBackend, BluetoothGATTDescriptorBackend, BluetoothGATTServiceBackend};
+use super::address::AddressType;
+use super::connection::{ConnectionParameters, ConnectionPriority, Phy};
+use super::device_id::DeviceId;
+use super::discovery::DiscoveryFilter;
+use super::error::{AttError, BluetoothError};
+use super::event::{AdapterEvent, AdapterEventHub, AdapterEventStream, DeviceProperty};
+use super::future::BluetoothFuture;
+use super::gatt::{self, WriteType};
+use super::notification::{NotificationHub, NotificationSubscription};
+use super::pairing::{IoCapability, PairingAgent, PairingMethod};
+use super::uuid::BluetoothUuid;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::Duration;
+
+pub(crate) fn map_error(error: Box<dyn Error>) -> BluetoothError {
+    match error.to_string().as_str() {
+        "No device found." | "No device exists with the given id." => BluetoothError::DeviceNotFound,
+        "No service exists with the given id." => BluetoothError::ServiceNotFound,
+        "No characteristic exists with the given id." => BluetoothError::CharacteristicNotFound,
+        "No descriptor exists with the given id." => BluetoothError::DescriptorNotFound,
+        "Device not connected." | "The device is not connected." => BluetoothError::NotConnected,
+        "Could not connect to the device." => BluetoothError::ConnectionFailed,
+        message => BluetoothError::Other(message.to_owned()),
+    }
+}
+
+thread_local! {
+    // The mock adapters created on this thread. They are kept per thread, so that tests running
+    // concurrently do not see each other's adapters.
+    static MOCK_ADAPTERS: RefCell<Vec<Weak<FakeBluetoothAdapter>>> = const { RefCell::new(Vec::new()) };
+}
+
+pub(crate) fn register_adapter(adapter: &Arc<FakeBluetoothAdapter>) {
+    MOCK_ADAPTERS.with(|adapters| adapters.borrow_mut().push(Arc::downgrade(adapter)));
+}
+
+// Returns the live mock adapters of this thread, in their creation order.
+pub(crate) fn get_adapters() -> Vec<Arc<FakeBluetoothAdapter>> {
+    MOCK_ADAPTERS.with(|adapters| {
+        let mut adapters = adapters.borrow_mut();
+        adapters.retain(|adapter| adapter.strong_count() > 0);
+        adapters.iter().filter_map(Weak::upgrade).collect()
+    })
+}
+
+// State that has no field in the blurmock objects is kept in side tables, next to a weak
+// reference to the fake object it belongs to.
+type ExtrasTable<T, E> = Mutex<Vec<(Weak<T>, E)>>;
+
+#[derive(Default)]
+struct AdapterExtras {
+    events: Arc<AdapterEventHub>,
+    pairing_agent: Option<(Arc<dyn PairingAgent>, IoCapability)>,
+    // The filter the scan applies, until it stops.
+    discovery_filter: Option<DiscoveryFilter>,
+    // blurmock parses the modalias it stores, and panics on an unset one.
+    modalias: Option<DeviceId>,
+}
+
+static ADAPTER_EXTRAS: ExtrasTable<FakeBluetoothAdapter, AdapterExtras> = Mutex::new(Vec::new());
+
+#[derive(Default)]
+struct DeviceExtras {
+    address_type: Option<AddressType>,
+    hung: bool,
+    // The negotiated ATT MTU, or `None` for the default one.
+    mtu: Option<u16>,
+    // The requested connection priority and PHY, or `None` for the defaults.
+    priority: Option<ConnectionPriority>,
+    phy: Option<Phy>,
+    pairing_method: PairingMethod,
+    pairing_error: Option<BluetoothError>,
+    // Whether a pairing waits on the agent, and whether it was cancelled meanwhile.
+    pairing: bool,
+    pairing_cancelled: bool,
+    // The AD structures of the last advertisement.
+    advertisement_data: Vec<u8>,
+    modalias: Option<DeviceId>,
+}
+
+static DEVICE_EXTRAS: ExtrasTable<FakeBluetoothDevice, DeviceExtras> = Mutex::new(Vec::new());
+// Signalled, with `DEVICE_EXTRAS` as its mutex, when a device stops hanging.
+static HUNG_RELEASED: Condvar = Condvar::new();
+
+#[derive(Default)]
+struct CharacteristicExtras {
+    notifications: Arc<NotificationHub>,
+    last_write_type: Option<WriteType>,
+    max_value_length: Option<u16>,
+    // The ATT requests sent to the characteristic, read and write alike.
+    request_count: u32,
+}
+
+static CHARACTERISTIC_EXTRAS: ExtrasTable<FakeBluetoothGATTCharacteristic, CharacteristicExtras> =
+    Mutex::new(Vec::new());
+
+#[derive(Default)]
+struct DescriptorExtras {
+    max_value_length: Option<u16>,
+}
+
+static DESCRIPTOR_EXTRAS: ExtrasTable<FakeBluetoothGATTDescriptor, DescriptorExtras> = Mutex::new(Vec::new());
+
+// How often an operation waiting for a hung device checks whether it was abandoned.
+const ABANDON_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// The longest value ATT allows for an attribute.
+const MAX_VALUE_LENGTH: u16 = 512;
+
+// The ATT MTU of a connection before any negotiation, and the largest one a device may ask for.
+const DEFAULT_MTU: u16 = 23;
+const MAX_MTU: u16 = 517;
+
+fn lock_extras<T, E>(table: &ExtrasTable<T, E>) -> MutexGuard<'_, Vec<(Weak<T>, E)>> {
+    let mut table = table.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    table.retain(|(weak, _)| weak.strong_count() > 0);
+    table
+}
+
+fn with_extras<T, E, R, F>(table: &ExtrasTable<T, E>, object: &Arc<T>, f: F) -> R
+    where E: Default, F: FnOnce(&mut E) -> R {
+    let mut table = lock_extras(table);
+    let index = match table.iter().position(|(weak, _)| weak.as_ptr() == Arc::as_ptr(object)) {
+        Some(index) => index,
+        None => {
+            table.push((Arc::downgrade(object), E::default()));
+            table.len() - 1
+        },
+    };
+    f(&mut table[index].1)
+}
+
+fn events(adapter: &Arc<FakeBluetoothAdapter>) -> Arc<AdapterEventHub> {
+    with_extras(&ADAPTER_EXTRAS, adapter, |extras| extras.events.clone())
+}
+
+pub(crate) fn get_discovery_filter(adapter: &Arc<FakeBluetoothAdapter>) -> Option<DiscoveryFilter> {
+    with_extras(&ADAPTER_EXTRAS, adapter, |extras| extras.discovery_filter.clone())
+}
+
+pub(crate) fn emit_event(adapter: &Arc<FakeBluetoothAdapter>, event: AdapterEvent) {
+    events(adapter).send(event);
+}
+
+// Reports a change of the device to the event streams of its adapter.
+fn emit_device_event<F>(device: &Arc<FakeBluetoothDevice>, event: F)
+    where F: FnOnce(String) -> AdapterEvent {
+    if let Ok(adapter) = device.get_adapter() {
+        emit_event(&adapter, event(FakeBluetoothDevice::get_id(device)));
+    }
+}
+
+pub(crate) fn device_updated(device: &Arc<FakeBluetoothDevice>, property: DeviceProperty) {
+    emit_device_event(device, |id| AdapterEvent::DeviceUpdated(id, property));
+}
+
+pub(crate) fn set_connected(device: &Arc<FakeBluetoothDevice>, connected: bool) -> Result<(), BluetoothError> {
+    let was_connected = FakeBluetoothDevice::is_connected(device).map_err(map_error)?;
+    FakeBluetoothDevice::set_connected(device, connected).map_err(map_error)?;
+    if !connected {
+        stop_device_notifications(device);
+        reset_connection(device);
+    }
+    if connected != was_connected {
+        emit_device_event(device, if connected { AdapterEvent::Connected } else { AdapterEvent::Disconnected });
+    }
+    Ok(())
+}
+
+type FlagGetter = fn(&FakeBluetoothDevice) -> Result<bool, Box<dyn Error>>;
+type FlagSetter = fn(&FakeBluetoothDevice, bool) -> Result<(), Box<dyn Error>>;
+
+// Sets a flag of the device, and reports whether it changed.
+fn update_flag(device: &Arc<FakeBluetoothDevice>,
+               property: DeviceProperty,
+               (get, set): (FlagGetter, FlagSetter),
+               value: bool)
+               -> Result<bool, BluetoothError> {
+    let previous = get(device).map_err(map_error)?;
+    set(device, value).map_err(map_error)?;
+    if value != previous {
+        device_updated(device, property);
+    }
+    Ok(value != previous)
+}
+
+pub(crate) fn set_paired(device: &Arc<FakeBluetoothDevice>, paired: bool) -> Result<(), BluetoothError> {
+    update_flag(device, DeviceProperty::Paired, (FakeBluetoothDevice::is_paired, FakeBluetoothDevice::set_paired), paired)?;
+    Ok(())
+}
+
+pub(crate) fn set_adapter_modalias(adapter: &Arc<FakeBluetoothAdapter>, modalias: DeviceId) {
+    with_extras(&ADAPTER_EXTRAS, adapter, |extras| extras.modalias = Some(modalias));
+}
+
+pub(crate) fn set_modalias(device: &Arc<FakeBluetoothDevice>, modalias: DeviceId) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.modalias = Some(modalias));
+}
+
+pub(crate) fn set_address_type(device: &Arc<FakeBluetoothDevice>, address_type: AddressType) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.address_type = Some(address_type));
+}
+
+pub(crate) fn set_hung(device: &Arc<FakeBluetoothDevice>, hung: bool) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.hung = hung);
+    HUNG_RELEASED.notify_all();
+}
+
+fn get_mtu(device: &Arc<FakeBluetoothDevice>) -> u16 {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.mtu).unwrap_or(DEFAULT_MTU)
+}
+
+// Simulates an MTU negotiated by the remote device.
+pub(crate) fn set_mtu(device: &Arc<FakeBluetoothDevice>, mtu: u16) {
+    let previous = with_extras(&DEVICE_EXTRAS, device, |extras| extras.mtu.replace(mtu)).unwrap_or(DEFAULT_MTU);
+    if mtu != previous {
+        emit_device_event(device, |id| AdapterEvent::MtuChanged(id, mtu));
+    }
+}
+
+// A new connection starts over from the default MTU, parameters and PHY.
+fn reset_connection(device: &Arc<FakeBluetoothDevice>) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| {
+        extras.mtu = None;
+        extras.priority = None;
+        extras.phy = None;
+    });
+}
+
+pub(crate) fn set_pairing_method(device: &Arc<FakeBluetoothDevice>, method: PairingMethod) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.pairing_method = method);
+}
+
+pub(crate) fn set_advertisement_data(device: &Arc<FakeBluetoothDevice>, raw: Vec<u8>) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.advertisement_data = raw);
+}
+
+pub(crate) fn set_pairing_error(device: &Arc<FakeBluetoothDevice>, error: Option<BluetoothError>) {
+    with_extras(&DEVICE_EXTRAS, device, |extras| extras.pairing_error = error);
+}
+
+fn pairing_agent(device: &Arc<FakeBluetoothDevice>) -> Option<(Arc<dyn PairingAgent>, IoCapability)> {
+    let adapter = device.get_adapter().ok()?;
+    with_extras(&ADAPTER_EXTRAS, &adapter, |extras| extras.pairing_agent.clone())
+}
+
+// Runs the pairing method of the device with the agent, and tells whether the pairing is
+// authenticated. The methods the IO capability does not allow fall back to Just Works, as they
+// do in the Security Manager, and there is no agent to answer a PIN code request without one.
+fn authenticate(device: &str, method: &PairingMethod, agent: Option<&(Arc<dyn PairingAgent>, IoCapability)>) -> bool {
+    match (method, agent) {
+        (PairingMethod::PinCode(pin_code), Some((agent, _))) => agent.request_pin_code(device).as_ref() == Some(pin_code),
+        (PairingMethod::PinCode(_), None) => false,
+        (PairingMethod::PasskeyEntry(passkey), Some((agent, capability))) if capability.has_keyboard() =>
+            agent.request_passkey(device) == Some(*passkey),
+        (PairingMethod::PasskeyDisplay(passkey), Some((agent, capability))) if capability.has_display() => {
+            // The device types the passkey in right away.
+            agent.display_passkey(device, *passkey, 6);
+            true
+        },
+        (PairingMethod::NumericComparison(passkey), Some((agent, capability))) if capability.can_confirm() =>
+            agent.request_confirmation(device, *passkey),
+        _ => true,
+    }
+}
+
+fn check_connected(device: &Arc<FakeBluetoothDevice>) -> Result<(), BluetoothError> {
+    if !FakeBluetoothDevice::is_connected(device).map_err(map_error)? {
+        return Err(BluetoothError::NotConnected);
+    }
+    Ok(())
+}
+
+// Blocks while the device hangs the operations on it and on its attributes.
+fn wait_while_hung(device: Option<Arc<FakeBluetoothDevice>>) {
+    wait_until_released(device, || false);
+}
+
+// Waits until the device stops hanging, and returns whether the operation is still wanted, or
+// until `abandoned` holds.
+fn wait_until_released<F>(device: Option<Arc<FakeBluetoothDevice>>, abandoned: F) -> bool
+    where F: Fn() -> bool {
+    let device = match device {
+        Some(device) => device,
+        None => return true,
+    };
+    let mut table = lock_extras(&DEVICE_EXTRAS);
+    while table.iter().any(|(weak, extras)| extras.hung && weak.as_ptr() == Arc::as_ptr(&device)) {
+        if abandoned() {
+            return false;
+        }
+        table = HUNG_RELEASED.wait_timeout(table, ABANDON_POLL_INTERVAL)
+                             .map(|(table, _)| table)
+                             .unwrap_or_else(|poisoned| poisoned.into_inner().0);
+    }
+    !abandoned()
+}
+
+// Runs the operation on its own thread once the device stops hanging. If the future is dropped
+// first, e.g. because it timed out, the operation is abandoned and the thread ends.
+fn spawn_when_released<T, F>(device: Option<Arc<FakeBluetoothDevice>>, operation: F) -> BluetoothFuture<T>
+    where T: Send + 'static, F: FnOnce() -> Result<T, BluetoothError> + Send + 'static {
+    let (future, completion) = BluetoothFuture::pending();
+    thread::spawn(move || {
+        if wait_until_released(device, || completion.is_cancelled()) {
+            completion.complete(operation());
+        }
+    });
+    future
+}
+
+fn characteristic_device(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) -> Option<Arc<FakeBluetoothDevice>> {
+    characteristic.get_service().ok().and_then(|service| service.get_device().ok())
+}
+
+fn descriptor_device(descriptor: &Arc<FakeBluetoothGATTDescriptor>) -> Option<Arc<FakeBluetoothDevice>> {
+    descriptor.get_characteristic().ok().as_ref().and_then(characteristic_device)
+}
+
+fn notifications(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) -> Arc<NotificationHub> {
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.notifications.clone())
+}
+
+// Simulates a notification from the remote device.
+pub(crate) fn notify_value(characteristic: &Arc<FakeBluetoothGATTCharacteristic>,
+                           value: Vec<u8>)
+                           -> Result<(), BluetoothError> {
+    FakeBluetoothGATTCharacteristic::set_value(characteristic, Some(value.clone())).map_err(map_error)?;
+    if FakeBluetoothGATTCharacteristic::is_notifying(characteristic).map_err(map_error)? {
+        notifications(characteristic).notify(value);
+    }
+    Ok(())
+}
+
+// The write type of the last write, or `None` if it left the choice to the backend.
+pub(crate) fn get_last_write_type(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) -> Option<WriteType> {
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.last_write_type)
+}
+
+pub(crate) fn get_request_count(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) -> u32 {
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.request_count)
+}
+
+fn count_request(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) {
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.request_count += 1);
+}
+
+pub(crate) fn set_characteristic_max_value_length(characteristic: &Arc<FakeBluetoothGATTCharacteristic>,
+                                                  max_value_length: Option<u16>) {
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.max_value_length = max_value_length);
+}
+
+fn characteristic_max_value_length(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) -> u16 {
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.max_value_length.unwrap_or(MAX_VALUE_LENGTH))
+}
+
+pub(crate) fn set_descriptor_max_value_length(descriptor: &Arc<FakeBluetoothGATTDescriptor>, max_value_length: Option<u16>) {
+    with_extras(&DESCRIPTOR_EXTRAS, descriptor, |extras| extras.max_value_length = max_value_length);
+}
+
+fn descriptor_max_value_length(descriptor: &Arc<FakeBluetoothGATTDescriptor>) -> u16 {
+    with_extras(&DESCRIPTOR_EXTRAS, descriptor, |extras| extras.max_value_length.unwrap_or(MAX_VALUE_LENGTH))
+}
+
+fn attribute_mtu(device: Option<Arc<FakeBluetoothDevice>>) -> u16 {
+    device.map_or(DEFAULT_MTU, |device| get_mtu(&device))
+}
+
+// The part of the value from `offset` on which a single Read or Read Blob response carries.
+fn value_from(mut value: Vec<u8>, offset: u16, mtu: u16) -> Result<Vec<u8>, BluetoothError> {
+    let offset = usize::from(offset);
+    if offset > value.len() {
+        return Err(BluetoothError::GattOperationFailed(AttError::InvalidOffset));
+    }
+    let mut value = value.split_off(offset);
+    value.truncate(gatt::max_read_length(mtu));
+    Ok(value)
+}
+
+// The value once `values` are written over it from `offset` on, as Prepare Write requests do.
+fn value_written_at(mut value: Vec<u8>, offset: u16, values: &[u8], max_value_length: u16) -> Result<Vec<u8>, BluetoothError> {
+    let offset = usize::from(offset);
+    if offset > value.len() {
+        return Err(BluetoothError::GattOperationFailed(AttError::InvalidOffset));
+    }
+    let end = offset + values.len();
+    if end > usize::from(max_value_length) {
+        return Err(BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength));
+    }
+    if value.len() < end {
+        value.resize(end, 0);
+    }
+    value[offset..end].copy_from_slice(values);
+    Ok(value)
+}
+
+// The value once a Write request, or a Prepare Write request at `offset`, carried `values` over
+// `current`. Longer values than a single request carries are left to the caller to split.
+fn value_after_write<F>(offset: Option<u16>, values: &[u8], mtu: u16, max_value_length: u16, current: F)
+                        -> Result<Vec<u8>, BluetoothError>
+    where F: FnOnce() -> Vec<u8> {
+    let (value, offset, max_request_length) = match offset {
+        Some(offset) => (current(), offset, gatt::max_prepare_write_length(mtu)),
+        None => (vec![], 0, gatt::max_write_length(mtu)),
+    };
+    if values.len() > max_request_length {
+        return Err(BluetoothError::GattOperationFailed(AttError::InvalidPdu));
+    }
+    value_written_at(value, offset, values, max_value_length)
+}
+
+// A single Read or Read Blob request.
+fn read_request(characteristic: &Arc<FakeBluetoothGATTCharacteristic>, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+    let device = characteristic_device(characteristic);
+    wait_while_hung(device.clone());
+    count_request(characteristic);
+    value_from(FakeBluetoothGATTCharacteristic::read_value(characteristic).map_err(map_error)?, offset, attribute_mtu(device))
+}
+
+// Reads the value from `offset` on with as many requests as its length takes, as the platform
+// stacks do.
+fn read_value(characteristic: &Arc<FakeBluetoothGATTCharacteristic>, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+    let mtu = attribute_mtu(characteristic_device(characteristic));
+    let first = read_request(characteristic, offset)?;
+    gatt::read_long(mtu, offset, first, |offset| read_request(characteristic, offset))
+}
+
+// A single Write request, or a Prepare Write request at `offset`.
+fn write_request(characteristic: &Arc<FakeBluetoothGATTCharacteristic>,
+                 offset: Option<u16>,
+                 values: Vec<u8>,
+                 write_type: Option<WriteType>)
+                 -> Result<(), BluetoothError> {
+    let device = characteristic_device(characteristic);
+    wait_while_hung(device.clone());
+    count_request(characteristic);
+    let value = value_after_write(offset, &values, attribute_mtu(device), characteristic_max_value_length(characteristic), || {
+        FakeBluetoothGATTCharacteristic::get_value(characteristic).unwrap_or_default()
+    })?;
+    FakeBluetoothGATTCharacteristic::write_value(characteristic, value).map_err(map_error)?;
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.last_write_type = write_type);
+    Ok(())
+}
+
+// Writes the value, or `values` over it from `offset` on, with as many requests as their
+// length takes, as the platform stacks do.
+fn write_value(characteristic: &Arc<FakeBluetoothGATTCharacteristic>, offset: Option<u16>, values: Vec<u8>) -> Result<(), BluetoothError> {
+    let mtu = attribute_mtu(characteristic_device(characteristic));
+    let write_at = |offset, values| write_request(characteristic, Some(offset), values, None);
+    match offset {
+        Some(offset) => gatt::write_long_at(mtu, offset, values, write_at),
+        None => gatt::write_long(mtu, values, |values| write_request(characteristic, None, values, None), write_at),
+    }
+}
+
+// A Reliable Write: the Prepare Write requests queue the whole value, and the Execute Write
+// request applies it at once, so a failed request leaves the value as it was.
+fn write_reliably(characteristic: &Arc<FakeBluetoothGATTCharacteristic>, values: Vec<u8>) -> Result<(), BluetoothError> {
+    let device = characteristic_device(characteristic);
+    wait_while_hung(device.clone());
+    let max_value_length = characteristic_max_value_length(characteristic);
+    let mut queue = vec![];
+    gatt::write_long_at(attribute_mtu(device), 0, values, |offset, values| {
+        count_request(characteristic);
+        queue = value_written_at(mem::take(&mut queue), offset, &values, max_value_length)?;
+        Ok(())
+    })?;
+    count_request(characteristic);
+    FakeBluetoothGATTCharacteristic::write_value(characteristic, queue).map_err(map_error)?;
+    with_extras(&CHARACTERISTIC_EXTRAS, characteristic, |extras| extras.last_write_type = Some(WriteType::Reliable));
+    Ok(())
+}
+
+fn read_descriptor_request(descriptor: &Arc<FakeBluetoothGATTDescriptor>, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+    let device = descriptor_device(descriptor);
+    wait_while_hung(device.clone());
+    value_from(FakeBluetoothGATTDescriptor::read_value(descriptor).map_err(map_error)?, offset, attribute_mtu(device))
+}
+
+fn read_descriptor_value(descriptor: &Arc<FakeBluetoothGATTDescriptor>, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+    let mtu = attribute_mtu(descriptor_device(descriptor));
+    let first = read_descriptor_request(descriptor, offset)?;
+    gatt::read_long(mtu, offset, first, |offset| read_descriptor_request(descriptor, offset))
+}
+
+fn write_descriptor_request(descriptor: &Arc<FakeBluetoothGATTDescriptor>,
+                            offset: Option<u16>,
+                            values: Vec<u8>)
+                            -> Result<(), BluetoothError> {
+    let device = descriptor_device(descriptor);
+    wait_while_hung(device.clone());
+    let value = value_after_write(offset, &values, attribute_mtu(device), descriptor_max_value_length(descriptor), || {
+        FakeBluetoothGATTDescriptor::get_value(descriptor).unwrap_or_default()
+    })?;
+    FakeBluetoothGATTDescriptor::write_value(descriptor, value).map_err(map_error)
+}
+
+fn write_descriptor_value(descriptor: &Arc<FakeBluetoothGATTDescriptor>, offset: Option<u16>, values: Vec<u8>) -> Result<(), BluetoothError> {
+    let mtu = attribute_mtu(descriptor_device(descriptor));
+    let write_at = |offset, values| write_descriptor_request(descriptor, Some(offset), values);
+    match offset {
+        Some(offset) => gatt::write_long_at(mtu, offset, values, write_at),
+        None => gatt::write_long(mtu, values, |values| write_descriptor_request(descriptor, None, values), write_at),
+    }
+}
+
+pub(crate) fn stop_notifications(characteristic: &Arc<FakeBluetoothGATTCharacteristic>) {
+    notifications(characteristic).close();
+}
+
+// Ends the subscriptions to every characteristic of the device, as a disconnection does.
+fn stop_device_notifications(device: &Arc<FakeBluetoothDevice>) {
+    for (characteristic, extras) in lock_extras(&CHARACTERISTIC_EXTRAS).iter() {
+        let characteristic_device = characteristic.upgrade().and_then(|characteristic| characteristic_device(&characteristic));
+        if characteristic_device.is_some_and(|characteristic_device| Arc::ptr_eq(&characteristic_device, device)) {
+            extras.notifications.close();
+        }
+    }
+}
+
+impl BluetoothAdapterBackend for Arc<FakeBluetoothAdapter> {
+    fn get_id(&self) -> String {
+        FakeBluetoothAdapter::get_id(self)
+    }
+
+    fn get_devices(&self) -> Result<Vec<Arc<dyn BluetoothDeviceBackend>>, BluetoothError> {
+        let device_list = self.get_device_list().map_err(map_error)?;
+        Ok(device_list.into_iter()
+                      .map(|device| Arc::new(FakeBluetoothDevice::new_empty(self.clone(), device)) as Arc<dyn BluetoothDeviceBackend>)
+                      .collect())
+    }
+
+    fn create_discovery_session(&self) -> Result<Arc<dyn BluetoothDiscoverySessionBackend>, BluetoothError> {
+        Ok(Arc::new(Arc::new(FakeBluetoothDiscoverySession::create_session(self.clone()).map_err(map_error)?)))
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothAdapter::get_address(self).map_err(map_error)
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothAdapter::get_name(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothAdapter::get_alias(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        FakeBluetoothAdapter::get_class(self).map_err(map_error)
+    }
+
+    fn is_powered(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothAdapter::is_powered(self).map_err(map_error)
+    }
+
+    fn is_discoverable(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothAdapter::is_discoverable(self).map_err(map_error)
+    }
+
+    fn is_pairable(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothAdapter::is_pairable(self).map_err(map_error)
+    }
+
+    fn get_pairable_timeout(&self) -> Result<u32, BluetoothError> {
+        FakeBluetoothAdapter::get_pairable_timeout(self).map_err(map_error)
+    }
+
+    fn get_discoverable_timeout(&self) -> Result<u32, BluetoothError> {
+        FakeBluetoothAdapter::get_discoverable_timeout(self).map_err(map_error)
+    }
+
+    fn is_discovering(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothAdapter::is_discovering(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        FakeBluetoothAdapter::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        with_extras(&ADAPTER_EXTRAS, self, |extras| extras.modalias).ok_or(BluetoothError::NotSupported)
+    }
+
+    fn set_alias(&self, alias: String) -> Result<(), BluetoothError> {
+        FakeBluetoothAdapter::set_alias(self, alias).map_err(map_error)
+    }
+
+    fn set_powered(&self, powered: bool) -> Result<(), BluetoothError> {
+        let was_powered = FakeBluetoothAdapter::is_powered(self).map_err(map_error)?;
+        FakeBluetoothAdapter::set_powered(self, powered).map_err(map_error)?;
+        if powered != was_powered {
+            emit_event(self, AdapterEvent::PoweredChanged(powered));
+        }
+        Ok(())
+    }
+
+    fn set_discoverable(&self, discoverable: bool) -> Result<(), BluetoothError> {
+        FakeBluetoothAdapter::set_discoverable(self, discoverable).map_err(map_error)
+    }
+
+    fn set_pairable(&self, pairable: bool) -> Result<(), BluetoothError> {
+        FakeBluetoothAdapter::set_pairable(self, pairable).map_err(map_error)
+    }
+
+    fn set_pairable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        FakeBluetoothAdapter::set_pairable_timeout(self, timeout).map_err(map_error)
+    }
+
+    fn set_discoverable_timeout(&self, timeout: u32) -> Result<(), BluetoothError> {
+        FakeBluetoothAdapter::set_discoverable_timeout(self, timeout).map_err(map_error)
+    }
+
+    fn subscribe_events(&self) -> Result<AdapterEventStream, BluetoothError> {
+        Ok(events(self).subscribe())
+    }
+
+    fn remove_device(&self, device_id: String) -> Result<(), BluetoothError> {
+        let device = self.get_device(device_id.clone()).map_err(map_error)?;
+        set_connected(&device, false)?;
+        FakeBluetoothAdapter::remove_device(self, device_id.clone()).map_err(map_error)?;
+        emit_event(self, AdapterEvent::DeviceLost(device_id));
+        Ok(())
+    }
+
+    fn register_pairing_agent(&self, agent: Arc<dyn PairingAgent>, capability: IoCapability) -> Result<(), BluetoothError> {
+        with_extras(&ADAPTER_EXTRAS, self, |extras| extras.pairing_agent = Some((agent, capability)));
+        Ok(())
+    }
+
+    fn unregister_pairing_agent(&self) -> Result<(), BluetoothError> {
+        with_extras(&ADAPTER_EXTRAS, self, |extras| extras.pairing_agent = None);
+        Ok(())
+    }
+
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothAdapter>> {
+        Some(self)
+    }
+}
+
+impl BluetoothDiscoverySessionBackend for Arc<FakeBluetoothDiscoverySession> {
+    fn start_discovery(&self) -> Result<(), BluetoothError> {
+        FakeBluetoothDiscoverySession::start_discovery(self).map_err(map_error)
+    }
+
+    fn stop_discovery(&self) -> Result<(), BluetoothError> {
+        FakeBluetoothDiscoverySession::stop_discovery(self).map_err(map_error)?;
+        with_extras(&ADAPTER_EXTRAS, &self.get_adapter(), |extras| extras.discovery_filter = None);
+        Ok(())
+    }
+
+    fn set_discovery_filter(&self, filter: &DiscoveryFilter) -> Result<(), BluetoothError> {
+        with_extras(&ADAPTER_EXTRAS, &self.get_adapter(), |extras| extras.discovery_filter = Some(filter.clone()));
+        Ok(())
+    }
+
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothDiscoverySession>> {
+        Some(self)
+    }
+}
+
+impl BluetoothDeviceBackend for Arc<FakeBluetoothDevice> {
+    fn get_id(&self) -> String {
+        FakeBluetoothDevice::get_id(self)
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothDevice::get_address(self).map_err(map_error)
+    }
+
+    fn get_address_type(&self) -> Result<AddressType, BluetoothError> {
+        Ok(with_extras(&DEVICE_EXTRAS, self, |extras| extras.address_type).unwrap_or(AddressType::Public))
+    }
+
+    fn get_name(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothDevice::get_name(self).map_err(map_error)
+    }
+
+    fn get_icon(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothDevice::get_icon(self).map_err(map_error)
+    }
+
+    fn get_class(&self) -> Result<u32, BluetoothError> {
+        FakeBluetoothDevice::get_class(self).map_err(map_error)
+    }
+
+    fn get_appearance(&self) -> Result<u16, BluetoothError> {
+        FakeBluetoothDevice::get_appearance(self).map_err(map_error)
+    }
+
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> {
+        FakeBluetoothDevice::get_uuids(self).map_err(map_error)?.iter().map(|uuid| uuid.parse()).collect()
+    }
+
+    fn is_paired(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothDevice::is_paired(self).map_err(map_error)
+    }
+
+    fn is_connected(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothDevice::is_connected(self).map_err(map_error)
+    }
+
+    fn is_trusted(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothDevice::is_trusted(self).map_err(map_error)
+    }
+
+    fn is_blocked(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothDevice::is_blocked(self).map_err(map_error)
+    }
+
+    fn get_alias(&self) -> Result<String, BluetoothError> {
+        FakeBluetoothDevice::get_alias(self).map_err(map_error)
+    }
+
+    fn is_legacy_pairing(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothDevice::is_legacy_pairing(self).map_err(map_error)
+    }
+
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> {
+        with_extras(&DEVICE_EXTRAS, self, |extras| extras.modalias).ok_or(BluetoothError::NotSupported)
+    }
+
+    fn get_rssi(&self) -> Result<i16, BluetoothError> {
+        FakeBluetoothDevice::get_rssi(self).map_err(map_error)
+    }
+
+    fn get_tx_power(&self) -> Result<i16, BluetoothError> {
+        FakeBluetoothDevice::get_tx_power(self).map_err(map_error)
+    }
+
+    fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError> {
+        FakeBluetoothDevice::get_manufacturer_data(self).map_err(map_error)
+    }
+
+    fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError> {
+        FakeBluetoothDevice::get_service_data(self).map_err(map_error)?
+            .into_iter()
+            .map(|(uuid, data)| Ok((uuid.parse()?, data)))
+            .collect()
+    }
+
+    fn get_gatt_services(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        let services = FakeBluetoothDevice::get_gatt_services(self).map_err(map_error)?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(FakeBluetoothGATTService::new_empty(self.clone(), service)) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    fn connect(&self) -> Result<(), BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        if FakeBluetoothDevice::is_blocked(self).map_err(map_error)? {
+            return Err(BluetoothError::NotAuthorized);
+        }
+        let was_connected = FakeBluetoothDevice::is_connected(self).map_err(map_error)?;
+        FakeBluetoothDevice::connect(self).map_err(map_error)?;
+        if !was_connected {
+            emit_device_event(self, AdapterEvent::Connected);
+        }
+        Ok(())
+    }
+
+    fn disconnect(&self) -> Result<(), BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        let was_connected = FakeBluetoothDevice::is_connected(self).map_err(map_error)?;
+        FakeBluetoothDevice::disconnect(self).map_err(map_error)?;
+        stop_device_notifications(self);
+        reset_connection(self);
+        if was_connected {
+            emit_device_event(self, AdapterEvent::Disconnected);
+        }
+        Ok(())
+    }
+
+    fn connect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        FakeBluetoothDevice::connect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn disconnect_profile(&self, uuid: BluetoothUuid) -> Result<(), BluetoothError> {
+        FakeBluetoothDevice::disconnect_profile(self, uuid.to_string()).map_err(map_error)
+    }
+
+    fn pair(&self) -> Result<(), BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        let (method, error) = with_extras(&DEVICE_EXTRAS, self, |extras| {
+            (extras.pairing_method.clone(), extras.pairing_error.clone())
+        });
+        if let Some(error) = error {
+            return Err(error);
+        }
+        if FakeBluetoothDevice::is_blocked(self).map_err(map_error)? {
+            return Err(BluetoothError::NotAuthorized);
+        }
+        with_extras(&DEVICE_EXTRAS, self, |extras| extras.pairing = true);
+        let authenticated = authenticate(&FakeBluetoothDevice::get_id(self), &method, pairing_agent(self).as_ref());
+        let cancelled = with_extras(&DEVICE_EXTRAS, self, |extras| {
+            extras.pairing = false;
+            mem::take(&mut extras.pairing_cancelled)
+        });
+        if cancelled {
+            return Err(BluetoothError::Cancelled);
+        }
+        if !authenticated {
+            return Err(BluetoothError::NotAuthorized);
+        }
+        set_paired(self, true)
+    }
+
+    // Cancels the pairing waiting on the agent, if any, or removes the pairing otherwise.
+    fn cancel_pairing(&self) -> Result<(), BluetoothError> {
+        let pairing = with_extras(&DEVICE_EXTRAS, self, |extras| {
+            extras.pairing_cancelled = extras.pairing;
+            extras.pairing
+        });
+        if !pairing {
+            return set_paired(self, false);
+        }
+        if let Some((agent, _)) = pairing_agent(self) {
+            agent.cancel();
+        }
+        Ok(())
+    }
+
+    fn unpair(&self) -> Result<(), BluetoothError> {
+        set_paired(self, false)
+    }
+
+    fn set_trusted(&self, trusted: bool) -> Result<(), BluetoothError> {
+        update_flag(self, DeviceProperty::Trusted, (FakeBluetoothDevice::is_trusted, FakeBluetoothDevice::set_trusted), trusted)?;
+        Ok(())
+    }
+
+    fn set_blocked(&self, blocked: bool) -> Result<(), BluetoothError> {
+        update_flag(self, DeviceProperty::Blocked, (FakeBluetoothDevice::is_blocked, FakeBluetoothDevice::set_blocked), blocked)?;
+        if blocked {
+            set_connected(self, false)?;
+        }
+        Ok(())
+    }
+
+    fn get_mtu(&self) -> Result<u16, BluetoothError> {
+        check_connected(self)?;
+        Ok(get_mtu(self))
+    }
+
+    fn get_advertisement_data(&self) -> Result<Vec<u8>, BluetoothError> {
+        Ok(with_extras(&DEVICE_EXTRAS, self, |extras| extras.advertisement_data.clone()))
+    }
+
+    fn request_mtu(&self, mtu: u16) -> Result<u16, BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        check_connected(self)?;
+        // The device accepts anything ATT allows.
+        let mtu = mtu.clamp(DEFAULT_MTU, MAX_MTU);
+        set_mtu(self, mtu);
+        Ok(mtu)
+    }
+
+    // The device accepts every request as is.
+    fn request_connection_priority(&self, priority: ConnectionPriority) -> Result<(), BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        check_connected(self)?;
+        with_extras(&DEVICE_EXTRAS, self, |extras| extras.priority = Some(priority));
+        Ok(())
+    }
+
+    fn get_connection_parameters(&self) -> Result<ConnectionParameters, BluetoothError> {
+        check_connected(self)?;
+        Ok(with_extras(&DEVICE_EXTRAS, self, |extras| extras.priority).unwrap_or_default().parameters())
+    }
+
+    fn set_preferred_phy(&self, phy: Phy) -> Result<(), BluetoothError> {
+        wait_while_hung(Some(self.clone()));
+        check_connected(self)?;
+        with_extras(&DEVICE_EXTRAS, self, |extras| extras.phy = Some(phy));
+        Ok(())
+    }
+
+    fn get_phy(&self) -> Result<Phy, BluetoothError> {
+        check_connected(self)?;
+        Ok(with_extras(&DEVICE_EXTRAS, self, |extras| extras.phy).unwrap_or_default())
+    }
+
+    fn connect_async(&self) -> BluetoothFuture<()> {
+        let device = self.clone();
+        spawn_when_released(Some(device.clone()), move || BluetoothDeviceBackend::connect(&device))
+    }
+
+    fn disconnect_async(&self) -> BluetoothFuture<()> {
+        let device = self.clone();
+        spawn_when_released(Some(device.clone()), move || BluetoothDeviceBackend::disconnect(&device))
+    }
+
+    fn pair_async(&self) -> BluetoothFuture<()> {
+        let device = self.clone();
+        spawn_when_released(Some(device.clone()), move || BluetoothDeviceBackend::pair(&device))
+    }
+
+    fn get_gatt_services_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTServiceBackend>>> {
+        let device = self.clone();
+        let parent = self.clone();
+        spawn_when_released(Some(device.clone()), move || {
+            FakeBluetoothDevice::get_gatt_services(&device).map_err(map_error)
+        })
+            .map(move |services| services.into_iter()
+                                         .map(|id| Arc::new(FakeBluetoothGATTService::new_empty(parent.clone(), id)) as Arc<dyn BluetoothGATTServiceBackend>)
+                                         .collect())
+    }
+
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothDevice>> {
+        Some(self)
+    }
+}
+
+impl BluetoothGATTServiceBackend for Arc<FakeBluetoothGATTService> {
+    fn get_id(&self) -> String {
+        FakeBluetoothGATTService::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        FakeBluetoothGATTService::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn is_primary(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothGATTService::is_primary(self).map_err(map_error)
+    }
+
+    fn get_includes(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> {
+        let device = self.get_device().map_err(map_error)?;
+        wait_while_hung(Some(device.clone()));
+        let services = FakeBluetoothGATTService::get_includes(self).map_err(map_error)?;
+        Ok(services.into_iter()
+                   .map(|service| Arc::new(FakeBluetoothGATTService::new_empty(device.clone(), service)) as Arc<dyn BluetoothGATTServiceBackend>)
+                   .collect())
+    }
+
+    fn get_gatt_characteristics(&self) -> Result<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>, BluetoothError> {
+        wait_while_hung(self.get_device().ok());
+        let characteristics = FakeBluetoothGATTService::get_gatt_characteristics(self).map_err(map_error)?;
+        Ok(characteristics.into_iter()
+                          .map(|characteristic|
+                              Arc::new(FakeBluetoothGATTCharacteristic::new_empty(self.clone(), characteristic))
+                                  as Arc<dyn BluetoothGATTCharacteristicBackend>)
+                          .collect())
+    }
+
+    fn get_includes_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTServiceBackend>>> {
+        let device = match self.get_device() {
+            Ok(device) => device,
+            Err(error) => return BluetoothFuture::failed(map_error(error)),
+        };
+        let (service, hung_device) = (self.clone(), device.clone());
+        spawn_when_released(Some(hung_device), move || {
+            FakeBluetoothGATTService::get_includes(&service).map_err(map_error)
+        })
+            .map(move |services| services.into_iter()
+                                         .map(|service| Arc::new(FakeBluetoothGATTService::new_empty(device.clone(), service)) as Arc<dyn BluetoothGATTServiceBackend>)
+                                         .collect())
+    }
+
+    fn get_gatt_characteristics_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTCharacteristicBackend>>> {
+        let service = self.clone();
+        let parent = self.clone();
+        spawn_when_released(service.get_device().ok(), move || {
+            FakeBluetoothGATTService::get_gatt_characteristics(&service).map_err(map_error)
+        })
+            .map(move |characteristics| characteristics.into_iter()
+                                                       .map(|id| Arc::new(FakeBluetoothGATTCharacteristic::new_empty(parent.clone(), id)) as Arc<dyn BluetoothGATTCharacteristicBackend>)
+                                                       .collect())
+    }
+
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothGATTService>> {
+        Some(self)
+    }
+}
+
+impl BluetoothGATTCharacteristicBackend for Arc<FakeBluetoothGATTCharacteristic> {
+    fn get_id(&self) -> String {
+        FakeBluetoothGATTCharacteristic::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        FakeBluetoothGATTCharacteristic::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        FakeBluetoothGATTCharacteristic::get_value(self).map_err(map_error)
+    }
+
+    fn is_notifying(&self) -> Result<bool, BluetoothError> {
+        FakeBluetoothGATTCharacteristic::is_notifying(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        FakeBluetoothGATTCharacteristic::get_flags(self).map_err(map_error)
+    }
+
+    fn get_gatt_descriptors(&self) -> Result<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>, BluetoothError> {
+        wait_while_hung(characteristic_device(self));
+        let descriptors = FakeBluetoothGATTCharacteristic::get_gatt_descriptors(self).map_err(map_error)?;
+        Ok(descriptors.into_iter()
+                      .map(|descriptor|
+                          Arc::new(FakeBluetoothGATTDescriptor::new_empty(self.clone(), descriptor))
+                              as Arc<dyn BluetoothGATTDescriptorBackend>)
+                      .collect())
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        read_value(self, 0)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        write_value(self, None, values)
+    }
+
+    fn write_value_with_type(&self, values: Vec<u8>, write_type: WriteType) -> Result<(), BluetoothError> {
+        match write_type {
+            WriteType::Reliable => write_reliably(self, values),
+            _ => write_request(self, None, values, Some(write_type)),
+        }
+    }
+
+    fn read_value_at(&self, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        read_value(self, offset)
+    }
+
+    fn write_value_at(&self, offset: u16, values: Vec<u8>) -> Result<(), BluetoothError> {
+        write_value(self, Some(offset), values)
+    }
+
+    fn start_notify(&self) -> Result<(), BluetoothError> {
+        wait_while_hung(characteristic_device(self));
+        FakeBluetoothGATTCharacteristic::start_notify(self).map_err(map_error)
+    }
+
+    fn stop_notify(&self) -> Result<(), BluetoothError> {
+        wait_while_hung(characteristic_device(self));
+        FakeBluetoothGATTCharacteristic::stop_notify(self).map_err(map_error)?;
+        stop_notifications(self);
+        Ok(())
+    }
+
+    fn subscribe(&self) -> Result<NotificationSubscription, BluetoothError> {
+        Ok(notifications(self).subscribe())
+    }
+
+    fn get_gatt_descriptors_async(&self) -> BluetoothFuture<Vec<Arc<dyn BluetoothGATTDescriptorBackend>>> {
+        let characteristic = self.clone();
+        let parent = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || {
+            FakeBluetoothGATTCharacteristic::get_gatt_descriptors(&characteristic).map_err(map_error)
+        })
+            .map(move |descriptors| descriptors.into_iter()
+                                               .map(|id| Arc::new(FakeBluetoothGATTDescriptor::new_empty(parent.clone(), id)) as Arc<dyn BluetoothGATTDescriptorBackend>)
+                                               .collect())
+    }
+
+    fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || read_value(&characteristic, 0))
+    }
+
+    fn write_value_async(&self, values: Vec<u8>) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || write_value(&characteristic, None, values))
+    }
+
+    fn write_value_with_type_async(&self, values: Vec<u8>, write_type: WriteType) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || {
+            BluetoothGATTCharacteristicBackend::write_value_with_type(&characteristic, values, write_type)
+        })
+    }
+
+    fn read_value_at_async(&self, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || read_value(&characteristic, offset))
+    }
+
+    fn write_value_at_async(&self, offset: u16, values: Vec<u8>) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || write_value(&characteristic, Some(offset), values))
+    }
+
+    fn start_notify_async(&self) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || {
+            BluetoothGATTCharacteristicBackend::start_notify(&characteristic)
+        })
+    }
+
+    fn stop_notify_async(&self) -> BluetoothFuture<()> {
+        let characteristic = self.clone();
+        spawn_when_released(characteristic_device(&characteristic), move || {
+            BluetoothGATTCharacteristicBackend::stop_notify(&characteristic)
+        })
+    }
+
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothGATTCharacteristic>> {
+        Some(self)
+    }
+}
+
+impl BluetoothGATTDescriptorBackend for Arc<FakeBluetoothGATTDescriptor> {
+    fn get_id(&self) -> String {
+        FakeBluetoothGATTDescriptor::get_id(self)
+    }
+
+    fn get_uuid(&self) -> Result<BluetoothUuid, BluetoothError> {
+        FakeBluetoothGATTDescriptor::get_uuid(self).map_err(map_error)?.parse()
+    }
+
+    fn get_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        FakeBluetoothGATTDescriptor::get_value(self).map_err(map_error)
+    }
+
+    fn get_flags(&self) -> Result<Vec<String>, BluetoothError> {
+        FakeBluetoothGATTDescriptor::get_flags(self).map_err(map_error)
+    }
+
+    fn read_value(&self) -> Result<Vec<u8>, BluetoothError> {
+        read_descriptor_value(self, 0)
+    }
+
+    fn write_value(&self, values: Vec<u8>) -> Result<(), BluetoothError> {
+        write_descriptor_value(self, None, values)
+    }
+
+    fn read_value_at(&self, offset: u16) -> Result<Vec<u8>, BluetoothError> {
+        read_descriptor_value(self, offset)
+    }
+
+    fn write_value_at(&self, offset: u16, values: Vec<u8>) -> Result<(), BluetoothError> {
+        write_descriptor_value(self, Some(offset), values)
+    }
+
+    fn read_value_async(&self) -> BluetoothFuture<Vec<u8>> {
+        let descriptor = self.clone();
+        spawn_when_released(descriptor_device(&descriptor), move || read_descriptor_value(&descriptor, 0))
+    }
+
+    fn write_value_async(&self, values: Vec<u8>) -> BluetoothFuture<()> {
+        let descriptor = self.clone();
+        spawn_when_released(descriptor_device(&descriptor), move || write_descriptor_value(&descriptor, None, values))
+    }
+
+    fn read_value_at_async(&self, offset: u16) -> BluetoothFuture<Vec<u8>> {
+        let descriptor = self.clone();
+        spawn_when_released(descriptor_device(&descriptor), move || read_descriptor_value(&descriptor, offset))
+    }
+
+    fn write_value_at_async(&self, offset: u16, values: Vec<u8>) -> BluetoothFuture<()> {
+        let descriptor = self.clone();
+        spawn_when_released(descriptor_device(&descriptor), move || write_descriptor_value(&descriptor, Some(offset), values))
+    }
+
+    fn as_mock(&self) -> Option<&Arc<FakeBluetoothGATTDescriptor>> {
+        Some(self)
+    }
+}
@@ -0,0 +1,30 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::stream::{Hub, Stream};
+
+use std::time::SystemTime;
+
+/// A value received through a notification or an indication.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotificationValue {
+    pub value: Vec<u8>,
+    pub timestamp: SystemTime,
+}
+
+/// The receiving end of a characteristic's notifications.
+///
+/// The subscription ends, i.e. `recv` returns `None` and the iteration stops, when
+/// notifications are stopped with `stop_notify` or the device disconnects.
+pub type NotificationSubscription = Stream<NotificationValue>;
+
+/// Delivers the notifications of one characteristic to all of its subscriptions.
+pub type NotificationHub = Hub<NotificationValue>;
+
+impl Hub<NotificationValue> {
+    /// Sends the value, received now, to every live subscription.
+    pub fn notify(&self, value: Vec<u8>) {
+        self.send(NotificationValue { value, timestamp: SystemTime::now() });
+    }
+}
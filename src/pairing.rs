@@ -0,0 +1,103 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::uuid::BluetoothUuid;
+
+/// The input and output capabilities of the local side of a pairing, which decide the pairing
+/// methods the devices can agree on (Bluetooth Core Specification, Vol 3, Part H, 2.3.2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum IoCapability {
+    DisplayOnly,
+    DisplayYesNo,
+    KeyboardOnly,
+    NoInputNoOutput,
+    #[default]
+    KeyboardDisplay,
+}
+
+impl IoCapability {
+    /// Whether a passkey can be typed in.
+    pub fn has_keyboard(&self) -> bool {
+        match *self {
+            IoCapability::KeyboardOnly | IoCapability::KeyboardDisplay => true,
+            IoCapability::DisplayOnly | IoCapability::DisplayYesNo | IoCapability::NoInputNoOutput => false,
+        }
+    }
+
+    /// Whether a passkey can be shown.
+    pub fn has_display(&self) -> bool {
+        match *self {
+            IoCapability::DisplayOnly | IoCapability::DisplayYesNo | IoCapability::KeyboardDisplay => true,
+            IoCapability::KeyboardOnly | IoCapability::NoInputNoOutput => false,
+        }
+    }
+
+    /// Whether a passkey can be shown and confirmed with a yes or no.
+    pub fn can_confirm(&self) -> bool {
+        match *self {
+            IoCapability::DisplayYesNo | IoCapability::KeyboardDisplay => true,
+            IoCapability::DisplayOnly | IoCapability::KeyboardOnly | IoCapability::NoInputNoOutput => false,
+        }
+    }
+}
+
+/// How the remote device authenticates a pairing.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PairingMethod {
+    /// Unauthenticated pairing, without any user interaction.
+    #[default]
+    JustWorks,
+    /// Legacy BR/EDR pairing, with the PIN code the device expects.
+    PinCode(String),
+    /// The device displays the passkey, which has to be typed in locally.
+    PasskeyEntry(u32),
+    /// The passkey is displayed locally, and typed in on the device.
+    PasskeyDisplay(u32),
+    /// Both sides display the passkey, and the user confirms they match.
+    NumericComparison(u32),
+}
+
+/// Answers the authentication requests of the pairings of an adapter, registered with
+/// `BluetoothAdapter::register_pairing_agent`.
+///
+/// Devices are identified by their id, as returned by `BluetoothDevice::get_id`. The requests
+/// may come from another thread, and may block until the user answers. The default methods
+/// reject every request.
+pub trait PairingAgent: Send + Sync {
+    /// Returns the PIN code of a legacy pairing, or `None` to reject it.
+    fn request_pin_code(&self, _device: &str) -> Option<String> {
+        None
+    }
+
+    /// Shows the PIN code the user has to type in on the device.
+    fn display_pin_code(&self, _device: &str, _pin_code: &str) {}
+
+    /// Returns the passkey, from 0 to 999999, displayed by the device, or `None` to reject the
+    /// pairing.
+    fn request_passkey(&self, _device: &str) -> Option<u32> {
+        None
+    }
+
+    /// Shows the passkey the user has to type in on the device. `entered` counts the digits
+    /// typed in so far, when the device reports them.
+    fn display_passkey(&self, _device: &str, _passkey: u32, _entered: u16) {}
+
+    /// Whether the passkey matches the one displayed by the device.
+    fn request_confirmation(&self, _device: &str, _passkey: u32) -> bool {
+        false
+    }
+
+    /// Whether to accept a pairing without authentication, initiated by the device.
+    fn request_authorization(&self, _device: &str) -> bool {
+        false
+    }
+
+    /// Whether the device may connect to a local service.
+    fn authorize_service(&self, _device: &str, _uuid: BluetoothUuid) -> bool {
+        false
+    }
+
+    /// The pairing was cancelled, and the request being answered can be dismissed.
+    fn cancel(&self) {}
+}
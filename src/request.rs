@@ -0,0 +1,342 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::bluetooth::BluetoothDevice;
+use super::error::BluetoothError;
+use super::uuid::BluetoothUuid;
+
+// The longest device name, in UTF-8 bytes.
+const MAX_NAME_LENGTH: usize = 248;
+
+/// Matches the data of an advertisement which starts with `data_prefix`, comparing only the
+/// bits set in `mask` (every bit if `None`). A mask shorter than the prefix matches nothing,
+/// and fails the validation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BluetoothDataFilter {
+    pub data_prefix: Vec<u8>,
+    pub mask: Option<Vec<u8>>,
+}
+
+impl BluetoothDataFilter {
+    pub fn matches(&self, data: &[u8]) -> bool {
+        if data.len() < self.data_prefix.len() {
+            return false;
+        }
+        self.data_prefix.iter().zip(data).enumerate().all(|(index, (prefix, byte))| {
+            let mask = match self.mask {
+                Some(ref mask) => mask.get(index).cloned(),
+                None => Some(0xFF),
+            };
+            mask.is_some_and(|mask| prefix & mask == byte & mask)
+        })
+    }
+
+    fn validate(&self) -> Result<(), BluetoothError> {
+        match self.mask {
+            Some(ref mask) if mask.len() != self.data_prefix.len() =>
+                Err(BluetoothError::InvalidOptions("The mask must be as long as the data prefix".to_owned())),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BluetoothManufacturerDataFilter {
+    pub company_identifier: u16,
+    pub data: BluetoothDataFilter,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BluetoothServiceDataFilter {
+    pub service: BluetoothUuid,
+    pub data: BluetoothDataFilter,
+}
+
+/// A filter of `requestDevice`, which a device matches if it matches every member set. The
+/// lists, when present, must not be empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BluetoothLEScanFilter {
+    pub name: Option<String>,
+    pub name_prefix: Option<String>,
+    /// Services the device must all advertise.
+    pub services: Option<Vec<BluetoothUuid>>,
+    pub manufacturer_data: Option<Vec<BluetoothManufacturerDataFilter>>,
+    pub service_data: Option<Vec<BluetoothServiceDataFilter>>,
+}
+
+impl BluetoothLEScanFilter {
+    pub fn new() -> BluetoothLEScanFilter {
+        BluetoothLEScanFilter::default()
+    }
+
+    pub fn matches(&self, device: &BluetoothDevice) -> bool {
+        if self.name.is_some() || self.name_prefix.is_some() {
+            let name = match device.get_name() {
+                Ok(name) => name,
+                Err(_) => return false,
+            };
+            if self.name.as_ref().is_some_and(|expected| *expected != name) {
+                return false;
+            }
+            if self.name_prefix.as_ref().is_some_and(|prefix| !name.starts_with(prefix.as_str())) {
+                return false;
+            }
+        }
+        if let Some(ref services) = self.services {
+            let uuids = device.get_uuids().unwrap_or_default();
+            if !services.iter().all(|service| uuids.contains(service)) {
+                return false;
+            }
+        }
+        if let Some(ref filters) = self.manufacturer_data {
+            let manufacturer_data = device.get_manufacturer_data().unwrap_or_default();
+            let matches = filters.iter().all(|filter| {
+                manufacturer_data.get(&filter.company_identifier).is_some_and(|data| filter.data.matches(data))
+            });
+            if !matches {
+                return false;
+            }
+        }
+        if let Some(ref filters) = self.service_data {
+            let service_data = device.get_service_data().unwrap_or_default();
+            let matches = filters.iter().all(|filter| {
+                service_data.get(&filter.service).is_some_and(|data| filter.data.matches(data))
+            });
+            if !matches {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn validate(&self) -> Result<(), BluetoothError> {
+        let invalid = |reason: &str| Err(BluetoothError::InvalidOptions(reason.to_owned()));
+        if self.name.is_none() && self.name_prefix.is_none() && self.services.is_none() &&
+           self.manufacturer_data.is_none() && self.service_data.is_none() {
+            return invalid("A filter must restrict the devices");
+        }
+        if self.services.as_ref().is_some_and(Vec::is_empty) {
+            return invalid("The services are empty");
+        }
+        if self.name.as_ref().is_some_and(|name| name.len() > MAX_NAME_LENGTH) {
+            return invalid("The name is too long");
+        }
+        match self.name_prefix {
+            Some(ref prefix) if prefix.is_empty() => return invalid("The name prefix is empty"),
+            Some(ref prefix) if prefix.len() > MAX_NAME_LENGTH => return invalid("The name prefix is too long"),
+            _ => {},
+        }
+        if let Some(ref filters) = self.manufacturer_data {
+            if filters.is_empty() {
+                return invalid("The manufacturer data filters are empty");
+            }
+            for (index, filter) in filters.iter().enumerate() {
+                filter.data.validate()?;
+                if filters[..index].iter().any(|other| other.company_identifier == filter.company_identifier) {
+                    return invalid("The manufacturer data filters have the same company identifier");
+                }
+            }
+        }
+        if let Some(ref filters) = self.service_data {
+            if filters.is_empty() {
+                return invalid("The service data filters are empty");
+            }
+            for (index, filter) in filters.iter().enumerate() {
+                filter.data.validate()?;
+                if filters[..index].iter().any(|other| other.service == filter.service) {
+                    return invalid("The service data filters have the same service");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The options of the Web Bluetooth `requestDevice`, see
+/// https://webbluetoothcg.github.io/web-bluetooth/#dictdef-requestdeviceoptions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RequestDeviceOptions {
+    /// The device must match one of the filters.
+    pub filters: Vec<BluetoothLEScanFilter>,
+    /// The device must match none of the exclusion filters.
+    pub exclusion_filters: Vec<BluetoothLEScanFilter>,
+    /// Services the page may access besides the ones of the filters.
+    pub optional_services: Vec<BluetoothUuid>,
+    pub optional_manufacturer_data: Vec<u16>,
+    /// Every device matches, in place of the filters.
+    pub accept_all_devices: bool,
+}
+
+impl RequestDeviceOptions {
+    pub fn new() -> RequestDeviceOptions {
+        RequestDeviceOptions::default()
+    }
+
+    /// Checks the options the way `requestDevice` does before scanning, and fails with
+    /// `BluetoothError::InvalidOptions` where the page would get a `TypeError`.
+    pub fn validate(&self) -> Result<(), BluetoothError> {
+        let invalid = |reason: &str| Err(BluetoothError::InvalidOptions(reason.to_owned()));
+        if self.accept_all_devices && !self.filters.is_empty() {
+            return invalid("Filters cannot be used with acceptAllDevices");
+        }
+        if !self.accept_all_devices && self.filters.is_empty() {
+            return invalid("Either filters or acceptAllDevices is required");
+        }
+        if self.filters.is_empty() && !self.exclusion_filters.is_empty() {
+            return invalid("Exclusion filters require filters");
+        }
+        for filter in self.filters.iter().chain(&self.exclusion_filters) {
+            filter.validate()?;
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, device: &BluetoothDevice) -> bool {
+        if self.accept_all_devices {
+            return true;
+        }
+        self.filters.iter().any(|filter| filter.matches(device)) &&
+        !self.exclusion_filters.iter().any(|filter| filter.matches(device))
+    }
+
+    /// The services the page may access on the chosen device.
+    pub fn allowed_services(&self) -> Vec<BluetoothUuid> {
+        let mut services = vec![];
+        let filter_services = self.filters.iter().flat_map(|filter| {
+            let services = filter.services.iter().flatten();
+            services.chain(filter.service_data.iter().flatten().map(|service_data| &service_data.service))
+        });
+        for service in filter_services.chain(&self.optional_services) {
+            if !services.contains(service) {
+                services.push(*service);
+            }
+        }
+        services
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BluetoothDataFilter, BluetoothError, BluetoothLEScanFilter, BluetoothManufacturerDataFilter};
+    use super::{BluetoothServiceDataFilter, BluetoothUuid, RequestDeviceOptions, MAX_NAME_LENGTH};
+
+    const HEART_RATE: BluetoothUuid = BluetoothUuid::from_u16(0x180D);
+    const BATTERY: BluetoothUuid = BluetoothUuid::from_u16(0x180F);
+
+    fn data(data_prefix: &[u8], mask: Option<&[u8]>) -> BluetoothDataFilter {
+        BluetoothDataFilter { data_prefix: data_prefix.to_vec(), mask: mask.map(<[u8]>::to_vec) }
+    }
+
+    fn named(name: &str) -> BluetoothLEScanFilter {
+        BluetoothLEScanFilter { name: Some(name.to_owned()), ..BluetoothLEScanFilter::new() }
+    }
+
+    fn manufacturer_data(company_identifier: u16, data: BluetoothDataFilter) -> BluetoothManufacturerDataFilter {
+        BluetoothManufacturerDataFilter { company_identifier, data }
+    }
+
+    fn is_invalid(filter: BluetoothLEScanFilter) -> bool {
+        let options = RequestDeviceOptions { filters: vec![filter], ..RequestDeviceOptions::new() };
+        matches!(options.validate(), Err(BluetoothError::InvalidOptions(_)))
+    }
+
+    #[test]
+    fn data_filters_match_a_prefix() {
+        let filter = data(&[1, 2], None);
+        assert!(filter.matches(&[1, 2]));
+        assert!(filter.matches(&[1, 2, 3]));
+        assert!(!filter.matches(&[1]));
+        assert!(!filter.matches(&[1, 3]));
+        assert!(data(&[], None).matches(&[]));
+    }
+
+    #[test]
+    fn data_filters_compare_the_masked_bits() {
+        let filter = data(&[0x12, 0x30], Some(&[0xFF, 0xF0]));
+        assert!(filter.matches(&[0x12, 0x3F]));
+        assert!(!filter.matches(&[0x13, 0x30]));
+        assert!(!filter.matches(&[0x12, 0x40]));
+        assert!(data(&[0xFF], Some(&[0x00])).matches(&[0x00]));
+    }
+
+    #[test]
+    fn data_filters_with_a_short_mask_match_nothing() {
+        assert!(!data(&[1, 2], Some(&[0xFF])).matches(&[1, 2]));
+        assert!(data(&[1, 2], Some(&[0xFF, 0xFF, 0xFF])).matches(&[1, 2]));
+    }
+
+    #[test]
+    fn filters_must_restrict_the_devices() {
+        assert!(is_invalid(BluetoothLEScanFilter::new()));
+        assert!(!is_invalid(named("device")));
+        assert!(!is_invalid(BluetoothLEScanFilter { services: Some(vec![HEART_RATE]), ..BluetoothLEScanFilter::new() }));
+    }
+
+    #[test]
+    fn present_lists_must_not_be_empty() {
+        assert!(is_invalid(BluetoothLEScanFilter { services: Some(vec![]), ..BluetoothLEScanFilter::new() }));
+        assert!(is_invalid(BluetoothLEScanFilter { manufacturer_data: Some(vec![]), ..BluetoothLEScanFilter::new() }));
+        assert!(is_invalid(BluetoothLEScanFilter { service_data: Some(vec![]), ..BluetoothLEScanFilter::new() }));
+        assert!(is_invalid(BluetoothLEScanFilter { services: Some(vec![]), ..named("device") }));
+    }
+
+    #[test]
+    fn names_are_bounded() {
+        assert!(!is_invalid(named(&"a".repeat(MAX_NAME_LENGTH))));
+        assert!(is_invalid(named(&"a".repeat(MAX_NAME_LENGTH + 1))));
+        let prefixed = |prefix: String| BluetoothLEScanFilter { name_prefix: Some(prefix), ..BluetoothLEScanFilter::new() };
+        assert!(is_invalid(prefixed(String::new())));
+        assert!(!is_invalid(prefixed("a".to_owned())));
+        assert!(is_invalid(prefixed("a".repeat(MAX_NAME_LENGTH + 1))));
+    }
+
+    #[test]
+    fn data_filters_are_validated() {
+        let filter = |filters| BluetoothLEScanFilter { manufacturer_data: Some(filters), ..BluetoothLEScanFilter::new() };
+        assert!(!is_invalid(filter(vec![manufacturer_data(1, data(&[1], Some(&[0xFF])))])));
+        assert!(is_invalid(filter(vec![manufacturer_data(1, data(&[1], Some(&[])))])));
+        assert!(is_invalid(filter(vec![manufacturer_data(1, data(&[], None)), manufacturer_data(1, data(&[2], None))])));
+
+        let service_data = |service| BluetoothServiceDataFilter { service, data: data(&[], None) };
+        let filter = |filters| BluetoothLEScanFilter { service_data: Some(filters), ..BluetoothLEScanFilter::new() };
+        assert!(!is_invalid(filter(vec![service_data(HEART_RATE), service_data(BATTERY)])));
+        assert!(is_invalid(filter(vec![service_data(HEART_RATE), service_data(HEART_RATE)])));
+    }
+
+    #[test]
+    fn options_need_filters_or_accept_all_devices() {
+        let invalid = |options: RequestDeviceOptions| matches!(options.validate(), Err(BluetoothError::InvalidOptions(_)));
+        assert!(invalid(RequestDeviceOptions::new()));
+        assert!(!invalid(RequestDeviceOptions { accept_all_devices: true, ..RequestDeviceOptions::new() }));
+        assert!(invalid(RequestDeviceOptions {
+            accept_all_devices: true,
+            filters: vec![named("device")],
+            ..RequestDeviceOptions::new()
+        }));
+        assert!(invalid(RequestDeviceOptions {
+            accept_all_devices: true,
+            exclusion_filters: vec![named("device")],
+            ..RequestDeviceOptions::new()
+        }));
+        assert!(invalid(RequestDeviceOptions {
+            filters: vec![named("device")],
+            exclusion_filters: vec![BluetoothLEScanFilter::new()],
+            ..RequestDeviceOptions::new()
+        }));
+    }
+
+    #[test]
+    fn allowed_services_gather_the_filters_and_the_optional_services() {
+        let options = RequestDeviceOptions {
+            filters: vec![BluetoothLEScanFilter { services: Some(vec![HEART_RATE]), ..BluetoothLEScanFilter::new() },
+                          BluetoothLEScanFilter {
+                              service_data: Some(vec![BluetoothServiceDataFilter { service: BATTERY, data: data(&[], None) }]),
+                              ..BluetoothLEScanFilter::new()
+                          }],
+            optional_services: vec![HEART_RATE, BluetoothUuid::from_u16(0x1800)],
+            ..RequestDeviceOptions::new()
+        };
+        assert_eq!(options.allowed_services(), vec![HEART_RATE, BATTERY, BluetoothUuid::from_u16(0x1800)]);
+    }
+}
@@ -0,0 +1,87 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+/// The receiving end of the values a `Hub`, or a backend's forwarding thread, sends, such as
+/// the events of an adapter or the notifications of a characteristic.
+#[derive(Debug)]
+pub struct Stream<T> {
+    receiver: Receiver<T>,
+    alive: Arc<()>,
+}
+
+impl<T> Stream<T> {
+    pub fn new(receiver: Receiver<T>) -> Stream<T> {
+        Stream { receiver, alive: Arc::new(()) }
+    }
+
+    /// Lets a forwarding thread notice that the stream was dropped while it had nothing to
+    /// send: the reference is dead once the stream is.
+    pub fn liveness(&self) -> Weak<()> {
+        Arc::downgrade(&self.alive)
+    }
+
+    /// Blocks until the next value arrives, or returns `None` if the stream ended.
+    pub fn recv(&self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+
+    /// Returns a pending value without blocking.
+    pub fn try_recv(&self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl<T> Iterator for Stream<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.recv()
+    }
+}
+
+/// Delivers the values of one source to all of its streams.
+#[derive(Debug)]
+pub struct Hub<T> {
+    senders: Mutex<Vec<Sender<T>>>,
+}
+
+impl<T: Clone> Hub<T> {
+    pub fn new() -> Hub<T> {
+        Hub { senders: Mutex::new(vec![]) }
+    }
+
+    pub fn subscribe(&self) -> Stream<T> {
+        let (sender, receiver) = channel();
+        self.lock().push(sender);
+        Stream::new(receiver)
+    }
+
+    /// Sends the value to every live stream, and forgets the dropped ones.
+    pub fn send(&self, value: T) {
+        self.lock().retain(|sender| sender.send(value.clone()).is_ok());
+    }
+
+    /// Ends every stream.
+    pub fn close(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Sender<T>>> {
+        self.senders.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<T: Clone> Default for Hub<T> {
+    fn default() -> Hub<T> {
+        Hub::new()
+    }
+}
@@ -0,0 +1,210 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::error::BluetoothError;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+// 00000000-0000-1000-8000-00805F9B34FB
+const BASE_UUID: u128 = 0x0000_0000_0000_1000_8000_0080_5F9B_34FB;
+const BASE_UUID_MASK: u128 = 0x0000_0000_FFFF_FFFF_FFFF_FFFF_FFFF_FFFF;
+
+/// A Bluetooth UUID.
+///
+/// 16 and 32-bit UUIDs are stored expanded against the Bluetooth base UUID, so every form of
+/// the same UUID compares and hashes equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BluetoothUuid(u128);
+
+impl BluetoothUuid {
+    pub const BASE: BluetoothUuid = BluetoothUuid(BASE_UUID);
+
+    pub const fn from_u16(uuid: u16) -> BluetoothUuid {
+        BluetoothUuid::from_u32(uuid as u32)
+    }
+
+    pub const fn from_u32(uuid: u32) -> BluetoothUuid {
+        BluetoothUuid(BASE_UUID | ((uuid as u128) << 96))
+    }
+
+    pub const fn from_u128(uuid: u128) -> BluetoothUuid {
+        BluetoothUuid(uuid)
+    }
+
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+
+    /// Returns the 32-bit short form, if the UUID is based on the Bluetooth base UUID.
+    pub fn to_u32(&self) -> Option<u32> {
+        if self.0 & BASE_UUID_MASK == BASE_UUID {
+            Some((self.0 >> 96) as u32)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the 16-bit short form, if the UUID is based on the Bluetooth base UUID.
+    pub fn to_u16(&self) -> Option<u16> {
+        self.to_u32().and_then(|uuid| u16::try_from(uuid).ok())
+    }
+
+    pub fn to_bytes(&self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+}
+
+impl fmt::Display for BluetoothUuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+               (self.0 >> 96) as u32,
+               (self.0 >> 80) as u16,
+               (self.0 >> 64) as u16,
+               (self.0 >> 48) as u16,
+               self.0 & 0xFFFF_FFFF_FFFF)
+    }
+}
+
+impl FromStr for BluetoothUuid {
+    type Err = BluetoothError;
+
+    /// Accepts the 16-bit (`"180d"`, `"0x180D"`), 32-bit (`"0000180d"`) and 128-bit
+    /// (`"0000180d-0000-1000-8000-00805f9b34fb"`, with or without dashes) forms, in any case.
+    fn from_str(uuid: &str) -> Result<BluetoothUuid, BluetoothError> {
+        let invalid = || BluetoothError::InvalidUuid(uuid.to_owned());
+        let trimmed = uuid.trim();
+        let short = trimmed.strip_prefix("0x").or_else(|| trimmed.strip_prefix("0X")).unwrap_or(trimmed);
+        if !short.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err(invalid());
+        }
+        match short.len() {
+            4 => u16::from_str_radix(short, 16).map(BluetoothUuid::from_u16).map_err(|_| invalid()),
+            8 => u32::from_str_radix(short, 16).map(BluetoothUuid::from_u32).map_err(|_| invalid()),
+            32 => u128::from_str_radix(short, 16).map(BluetoothUuid::from_u128).map_err(|_| invalid()),
+            36 if short != trimmed => Err(invalid()),
+            36 => {
+                let dashes = [8, 13, 18, 23];
+                let well_formed = short.char_indices().all(|(i, c)| (c == '-') == dashes.contains(&i));
+                if !well_formed {
+                    return Err(invalid());
+                }
+                let digits: String = short.chars().filter(|c| *c != '-').collect();
+                u128::from_str_radix(&digits, 16).map(BluetoothUuid::from_u128).map_err(|_| invalid())
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<&str> for BluetoothUuid {
+    type Error = BluetoothError;
+
+    fn try_from(uuid: &str) -> Result<BluetoothUuid, BluetoothError> {
+        uuid.parse()
+    }
+}
+
+impl TryFrom<String> for BluetoothUuid {
+    type Error = BluetoothError;
+
+    fn try_from(uuid: String) -> Result<BluetoothUuid, BluetoothError> {
+        uuid.parse()
+    }
+}
+
+impl From<u16> for BluetoothUuid {
+    fn from(uuid: u16) -> BluetoothUuid {
+        BluetoothUuid::from_u16(uuid)
+    }
+}
+
+impl From<u32> for BluetoothUuid {
+    fn from(uuid: u32) -> BluetoothUuid {
+        BluetoothUuid::from_u32(uuid)
+    }
+}
+
+impl From<u128> for BluetoothUuid {
+    fn from(uuid: u128) -> BluetoothUuid {
+        BluetoothUuid::from_u128(uuid)
+    }
+}
+
+impl From<BluetoothUuid> for String {
+    fn from(uuid: BluetoothUuid) -> String {
+        uuid.to_string()
+    }
+}
+
+impl PartialEq<str> for BluetoothUuid {
+    fn eq(&self, other: &str) -> bool {
+        other.parse::<BluetoothUuid>().map(|other| *self == other).unwrap_or(false)
+    }
+}
+
+impl PartialEq<&str> for BluetoothUuid {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<String> for BluetoothUuid {
+    fn eq(&self, other: &String) -> bool {
+        *self == *other.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BluetoothUuid;
+
+    const HEART_RATE: &str = "0000180d-0000-1000-8000-00805f9b34fb";
+
+    #[test]
+    fn short_forms_round_trip() {
+        let uuid = BluetoothUuid::from_u16(0x180D);
+        assert_eq!(uuid.to_u16(), Some(0x180D));
+        assert_eq!(uuid.to_u32(), Some(0x180D));
+        assert_eq!(uuid.to_string(), HEART_RATE);
+
+        let uuid = BluetoothUuid::from_u32(0x1234_5678);
+        assert_eq!(uuid.to_u32(), Some(0x1234_5678));
+        assert_eq!(uuid.to_u16(), None);
+        assert_eq!(uuid.to_string(), "12345678-0000-1000-8000-00805f9b34fb");
+    }
+
+    #[test]
+    fn long_form_round_trips() {
+        let long = "f000aa01-0451-4000-b000-000000000000";
+        let uuid: BluetoothUuid = long.parse().unwrap();
+        assert_eq!(uuid.to_string(), long);
+        assert_eq!(uuid.as_u128(), 0xf000_aa01_0451_4000_b000_0000_0000_0000);
+        assert_eq!(uuid.to_u32(), None);
+        assert_eq!(BluetoothUuid::from_u128(uuid.as_u128()), uuid);
+        assert_eq!(uuid.to_bytes()[0], 0xf0);
+    }
+
+    #[test]
+    fn every_form_parses_to_the_same_uuid() {
+        let expected = BluetoothUuid::from_u16(0x180D);
+        for form in &["180d", "180D", "0x180d", "0X180D", "0000180d", HEART_RATE,
+                      "0000180D-0000-1000-8000-00805F9B34FB", "0000180d00001000800000805f9b34fb"] {
+            assert_eq!(form.parse::<BluetoothUuid>().unwrap(), expected, "{}", form);
+        }
+        assert_eq!(expected, HEART_RATE);
+        assert_eq!(expected, "0x180d".to_owned());
+    }
+
+    #[test]
+    fn malformed_uuids_are_rejected() {
+        for form in &["", "180", "180d1", "g80d", "0x0000180d-0000-1000-8000-00805f9b34fb",
+                      "0000180d0-000-1000-8000-00805f9b34fb", "0000180d-0000-1000-8000-00805f9b34fb0",
+                      "+180"] {
+            assert!(form.parse::<BluetoothUuid>().is_err(), "{}", form);
+        }
+        assert!(BluetoothUuid::from_u16(0x180D) != "not a uuid");
+    }
+}
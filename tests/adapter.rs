@@ -0,0 +1,34 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+use device::adapter::BluetoothAdapter;
+use device::bluetooth::BluetoothError;
+use std::time::{Duration, Instant};
+
+#[test]
+fn waiting_confirms_a_setting() {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    adapter.set_powered(false).unwrap();
+    adapter.set_powered(true).unwrap();
+    assert_eq!(adapter.wait_until(Duration::from_secs(5), |adapter| adapter.is_powered()), Ok(()));
+}
+
+#[test]
+fn waiting_times_out_if_the_condition_never_holds() {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    adapter.set_powered(false).unwrap();
+    let start = Instant::now();
+    assert_eq!(adapter.wait_until(Duration::from_millis(50), |adapter| adapter.is_powered()),
+               Err(BluetoothError::Timeout));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn waiting_fails_with_the_condition() {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    assert_eq!(adapter.wait_until(Duration::from_secs(5), |adapter| adapter.get_modalias().map(|_| true)),
+               Err(BluetoothError::NotSupported));
+}
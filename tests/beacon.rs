@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+use device::adapter::BluetoothAdapter;
+use device::beacon::{AltBeacon, Beacon, EddystoneFrame, IBeacon, APPLE_COMPANY_IDENTIFIER};
+use device::uuid::BluetoothUuid;
+use std::collections::HashMap;
+
+fn ibeacon() -> IBeacon {
+    IBeacon {
+        proximity_uuid: BluetoothUuid::from(0xE2C56DB5_DFFB_48D2_B060_D0F5A71096E0_u128),
+        major: 1,
+        minor: 2,
+        measured_power: -59,
+    }
+}
+
+fn altbeacon(company_identifier: u16) -> AltBeacon {
+    AltBeacon { company_identifier, beacon_id: [7; 20], reference_rssi: -65, reserved: 0 }
+}
+
+fn eddystone() -> EddystoneFrame {
+    EddystoneFrame::Url { tx_power: -20, url: "https://example.com/".to_owned() }
+}
+
+#[test]
+fn mock_beacons_decode_back() {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    let beacons = [Beacon::IBeacon(ibeacon()), Beacon::AltBeacon(altbeacon(0x0118)), Beacon::Eddystone(eddystone())];
+    for (index, beacon) in beacons.iter().enumerate() {
+        let device = adapter.create_mock_beacon(format!("beacon{}", index), beacon).unwrap();
+        assert_eq!(Beacon::from_device(&device), vec![beacon.clone()]);
+    }
+}
+
+#[test]
+fn beacons_are_ordered_by_company_with_eddystone_last() {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    let device = adapter.create_mock_beacon("beacon".to_owned(), &Beacon::Eddystone(eddystone())).unwrap();
+    let mut manufacturer_data = HashMap::new();
+    for company_identifier in [0x0118, 0x0006] {
+        manufacturer_data.insert(company_identifier, altbeacon(company_identifier).encode());
+    }
+    manufacturer_data.insert(APPLE_COMPANY_IDENTIFIER, ibeacon().encode());
+    device.set_manufacturer_data(manufacturer_data).unwrap();
+
+    assert_eq!(Beacon::from_device(&device),
+               vec![Beacon::AltBeacon(altbeacon(0x0006)),
+                    Beacon::IBeacon(ibeacon()),
+                    Beacon::AltBeacon(altbeacon(0x0118)),
+                    Beacon::Eddystone(eddystone())]);
+}
@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_characteristic, mock_descriptor, MockCharacteristic};
+use common::{CLIENT_CHARACTERISTIC_CONFIGURATION, HEART_RATE_MEASUREMENT, HEART_RATE_SERVICE};
+use device::bluetooth::{BluetoothError, BluetoothGATTService, BluetoothUuid, Blocklisted, GattBlocklist, WriteType};
+
+const BATTERY_SERVICE: BluetoothUuid = BluetoothUuid::from_u16(0x180F);
+const FLAGS: &[&str] = &["read", "write", "write-without-response", "notify"];
+
+fn blocklist(uuid: BluetoothUuid, blocklisted: Blocklisted) -> GattBlocklist {
+    let mut blocklist = GattBlocklist::new();
+    blocklist.insert(uuid, blocklisted);
+    blocklist
+}
+
+fn assert_blocked<T: std::fmt::Debug>(result: Result<T, BluetoothError>) {
+    match result {
+        Err(BluetoothError::Blocklisted) => {},
+        result => panic!("{:?} is not blocklisted", result),
+    }
+}
+
+#[test]
+fn characteristics_excluded_from_reads_cannot_be_read_or_notify() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(FLAGS);
+    characteristic.set_value(vec![1]).unwrap();
+    let blocklist = blocklist(HEART_RATE_MEASUREMENT, Blocklisted::ExcludeReads);
+    assert_blocked(blocklist.read_value(&characteristic));
+    assert_blocked(blocklist.read_value_async(&characteristic).wait(None));
+    assert_blocked(blocklist.read_value_at(&characteristic, 0));
+    assert_blocked(blocklist.read_value_at_async(&characteristic, 0).wait(None));
+    assert_blocked(blocklist.start_notify(&characteristic));
+    assert_blocked(blocklist.start_notify_async(&characteristic).wait(None));
+    assert_blocked(blocklist.subscribe(&characteristic));
+    assert_eq!(characteristic.is_notifying(), Ok(false));
+
+    assert_eq!(blocklist.write_value(&characteristic, vec![2]), Ok(()));
+    assert_eq!(blocklist.write_value_async(&characteristic, vec![3]).wait(None), Ok(()));
+    assert_eq!(blocklist.write_value_at(&characteristic, 1, vec![4]), Ok(()));
+    assert_eq!(blocklist.write_value_at_async(&characteristic, 2, vec![5]).wait(None), Ok(()));
+    assert_eq!(blocklist.write_value_with_type(&characteristic, vec![6], WriteType::WithoutResponse), Ok(()));
+    assert_eq!(blocklist.write_value_with_type_async(&characteristic, vec![7], WriteType::WithResponse).wait(None),
+               Ok(()));
+    assert_eq!(characteristic.get_value(), Ok(vec![7]));
+}
+
+#[test]
+fn characteristics_excluded_from_writes_cannot_be_written() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(FLAGS);
+    characteristic.set_value(vec![1]).unwrap();
+    let blocklist = blocklist(HEART_RATE_MEASUREMENT, Blocklisted::ExcludeWrites);
+    assert_blocked(blocklist.write_value(&characteristic, vec![2]));
+    assert_blocked(blocklist.write_value_async(&characteristic, vec![2]).wait(None));
+    assert_blocked(blocklist.write_value_at(&characteristic, 0, vec![2]));
+    assert_blocked(blocklist.write_value_at_async(&characteristic, 0, vec![2]).wait(None));
+    assert_blocked(blocklist.write_value_with_type(&characteristic, vec![2], WriteType::WithoutResponse));
+    assert_blocked(blocklist.write_value_with_type_async(&characteristic, vec![2], WriteType::WithResponse).wait(None));
+    assert_eq!(characteristic.get_value(), Ok(vec![1]));
+
+    assert_eq!(blocklist.read_value(&characteristic), Ok(vec![1]));
+    assert_eq!(blocklist.read_value_async(&characteristic).wait(None), Ok(vec![1]));
+    assert_eq!(blocklist.read_value_at(&characteristic, 1), Ok(vec![]));
+    assert_eq!(blocklist.read_value_at_async(&characteristic, 0).wait(None), Ok(vec![1]));
+    assert!(blocklist.subscribe(&characteristic).is_ok());
+    assert_eq!(blocklist.start_notify(&characteristic), Ok(()));
+}
+
+#[test]
+fn excluded_characteristics_can_do_nothing() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(FLAGS);
+    mock_descriptor(&characteristic, &["read"]);
+    let blocklist = blocklist(HEART_RATE_MEASUREMENT, Blocklisted::Exclude);
+    assert_blocked(blocklist.read_value(&characteristic));
+    assert_blocked(blocklist.write_value(&characteristic, vec![1]));
+    assert_blocked(blocklist.start_notify_async(&characteristic).wait(None));
+    assert_blocked(blocklist.get_gatt_descriptors(&characteristic));
+    assert_blocked(blocklist.get_gatt_descriptors_async(&characteristic).wait(None));
+}
+
+#[test]
+fn descriptors_follow_their_blocklist_entry() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(FLAGS);
+    let descriptor = mock_descriptor(&characteristic, &["read", "write"]);
+    descriptor.set_value(vec![1]).unwrap();
+
+    let blocklist = blocklist(CLIENT_CHARACTERISTIC_CONFIGURATION, Blocklisted::ExcludeReads);
+    assert_blocked(blocklist.read_descriptor_value(&descriptor));
+    assert_blocked(blocklist.read_descriptor_value_async(&descriptor).wait(None));
+    assert_blocked(blocklist.read_descriptor_value_at(&descriptor, 0));
+    assert_blocked(blocklist.read_descriptor_value_at_async(&descriptor, 0).wait(None));
+    assert_eq!(blocklist.write_descriptor_value(&descriptor, vec![2]), Ok(()));
+    assert_eq!(blocklist.write_descriptor_value_at_async(&descriptor, 1, vec![3]).wait(None), Ok(()));
+
+    let blocklist = self::blocklist(CLIENT_CHARACTERISTIC_CONFIGURATION, Blocklisted::ExcludeWrites);
+    assert_blocked(blocklist.write_descriptor_value(&descriptor, vec![4]));
+    assert_blocked(blocklist.write_descriptor_value_async(&descriptor, vec![4]).wait(None));
+    assert_blocked(blocklist.write_descriptor_value_at(&descriptor, 0, vec![4]));
+    assert_blocked(blocklist.write_descriptor_value_at_async(&descriptor, 0, vec![4]).wait(None));
+    assert_eq!(blocklist.read_descriptor_value(&descriptor), Ok(vec![2, 3]));
+    assert_eq!(blocklist.read_descriptor_value_at_async(&descriptor, 1).wait(None), Ok(vec![3]));
+}
+
+#[test]
+fn excluded_attributes_are_left_out_of_the_lists() {
+    let MockCharacteristic { device, service, characteristic, .. } = mock_characteristic(FLAGS);
+    mock_descriptor(&characteristic, &["read"]);
+
+    let blocklist = blocklist(CLIENT_CHARACTERISTIC_CONFIGURATION, Blocklisted::Exclude);
+    assert_eq!(blocklist.get_gatt_descriptors(&characteristic).map(|descriptors| descriptors.len()), Ok(0));
+    assert_eq!(blocklist.get_gatt_descriptors_async(&characteristic).wait(None).map(|descriptors| descriptors.len()),
+               Ok(0));
+    assert_eq!(characteristic.get_gatt_descriptors().map(|descriptors| descriptors.len()), Ok(1));
+
+    let blocklist = self::blocklist(HEART_RATE_MEASUREMENT, Blocklisted::Exclude);
+    assert_eq!(blocklist.get_gatt_characteristics(&service).map(|characteristics| characteristics.len()), Ok(0));
+    assert_eq!(blocklist.get_gatt_characteristics_async(&service).wait(None).map(|characteristics| characteristics.len()),
+               Ok(0));
+
+    device.connect().unwrap();
+    let blocklist = self::blocklist(HEART_RATE_SERVICE, Blocklisted::Exclude);
+    assert_eq!(blocklist.get_gatt_services(&device).map(|services| services.len()), Ok(0));
+    assert_eq!(blocklist.get_gatt_services_async(&device).wait(None).map(|services| services.len()), Ok(0));
+    assert_blocked(blocklist.get_gatt_characteristics(&service));
+    assert_blocked(blocklist.get_gatt_characteristics_async(&service).wait(None));
+
+    assert_blocked(blocklist.get_includes(&service));
+    assert_blocked(blocklist.get_includes_async(&service).wait(None));
+
+    let included = BluetoothGATTService::create_mock_service(device.clone(), "included".to_owned()).unwrap();
+    included.set_uuid(BATTERY_SERVICE).unwrap();
+    service.add_included_service(&included).unwrap();
+    let blocklist = self::blocklist(BATTERY_SERVICE, Blocklisted::Exclude);
+    assert_eq!(blocklist.get_includes(&service).map(|services| services.len()), Ok(0));
+    assert_eq!(blocklist.get_includes_async(&service).wait(None).map(|services| services.len()), Ok(0));
+    assert_eq!(service.get_includes().map(|services| services.len()), Ok(1));
+
+    let blocklist = GattBlocklist::new();
+    assert_eq!(blocklist.get_gatt_services_async(&device).wait(None).map(|services| services.len()), Ok(2));
+    assert_eq!(blocklist.get_includes(&service).map(|services| services.len()), Ok(1));
+}
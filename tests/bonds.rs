@@ -0,0 +1,61 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, MockDevice};
+use device::adapter::BluetoothAdapter;
+use device::bluetooth::{BluetoothDevice, BluetoothError};
+use device::event::{AdapterEvent, DeviceProperty};
+
+fn ids(devices: Result<Vec<BluetoothDevice>, BluetoothError>) -> Vec<String> {
+    let mut ids: Vec<String> = devices.unwrap().iter().map(|device| device.get_id()).collect();
+    ids.sort();
+    ids
+}
+
+fn bonded_ids(adapter: &BluetoothAdapter) -> Vec<String> {
+    ids(adapter.get_bonded_devices())
+}
+
+#[test]
+fn paired_devices_are_bonded_until_removed() {
+    let MockDevice { adapter, device } = mock_device();
+    let other = adapter.create_mock_device("other".to_owned()).unwrap();
+    other.set_address("01:23:45:67:89:AC".to_owned()).unwrap();
+    other.set_connectable(true).unwrap();
+    assert_eq!(bonded_ids(&adapter), Vec::<String>::new());
+
+    device.pair().unwrap();
+    other.pair().unwrap();
+    assert_eq!(bonded_ids(&adapter), vec!["device".to_owned(), "other".to_owned()]);
+
+    adapter.remove_device(&device).unwrap();
+    assert_eq!(bonded_ids(&adapter), vec!["other".to_owned()]);
+    assert_eq!(ids(adapter.get_devices()), vec!["other".to_owned()]);
+}
+
+#[test]
+fn trusting_and_blocking_are_reported() {
+    let MockDevice { adapter, device } = mock_device();
+    let events = adapter.subscribe_events().unwrap();
+    let id = device.get_id();
+
+    device.set_trusted(true).unwrap();
+    assert_eq!(device.is_trusted(), Ok(true));
+    assert_eq!(events.try_recv(), Some(AdapterEvent::DeviceUpdated(id.clone(), DeviceProperty::Trusted)));
+
+    device.set_blocked(true).unwrap();
+    assert_eq!(device.is_blocked(), Ok(true));
+    assert_eq!(events.try_recv(), Some(AdapterEvent::DeviceUpdated(id.clone(), DeviceProperty::Blocked)));
+
+    device.set_trusted(true).unwrap();
+    device.set_blocked(true).unwrap();
+    assert_eq!(events.try_recv(), None);
+
+    device.set_blocked(false).unwrap();
+    assert_eq!(events.try_recv(), Some(AdapterEvent::DeviceUpdated(id, DeviceProperty::Blocked)));
+}
@@ -0,0 +1,59 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// Every test binary uses a different part of these helpers.
+#![allow(dead_code)]
+
+use device::adapter::BluetoothAdapter;
+use device::bluetooth::{BluetoothDevice, BluetoothGATTCharacteristic, BluetoothGATTDescriptor, BluetoothGATTService};
+use device::uuid::BluetoothUuid;
+
+pub const HEART_RATE_SERVICE: BluetoothUuid = BluetoothUuid::from_u16(0x180D);
+pub const HEART_RATE_MEASUREMENT: BluetoothUuid = BluetoothUuid::from_u16(0x2A37);
+pub const CLIENT_CHARACTERISTIC_CONFIGURATION: BluetoothUuid = BluetoothUuid::from_u16(0x2902);
+
+/// A connectable mock device, which keeps its adapter alive.
+pub struct MockDevice {
+    pub adapter: BluetoothAdapter,
+    pub device: BluetoothDevice,
+}
+
+pub fn mock_device() -> MockDevice {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    let device = adapter.create_mock_device("device".to_owned()).unwrap();
+    device.set_address("01:23:45:67:89:AB".to_owned()).unwrap();
+    device.set_connectable(true).unwrap();
+    MockDevice { adapter, device }
+}
+
+/// A mock device with a heart rate service and a measurement characteristic with the given
+/// flags, which keeps its adapter and device alive.
+pub struct MockCharacteristic {
+    pub adapter: BluetoothAdapter,
+    pub device: BluetoothDevice,
+    pub service: BluetoothGATTService,
+    pub characteristic: BluetoothGATTCharacteristic,
+}
+
+pub fn mock_characteristic(flags: &[&str]) -> MockCharacteristic {
+    let MockDevice { adapter, device } = mock_device();
+    let service = BluetoothGATTService::create_mock_service(device.clone(), "service".to_owned()).unwrap();
+    service.set_uuid(HEART_RATE_SERVICE).unwrap();
+    service.set_primary(true).unwrap();
+    let characteristic =
+        BluetoothGATTCharacteristic::create_mock_characteristic(service.clone(), "characteristic".to_owned()).unwrap();
+    characteristic.set_uuid(HEART_RATE_MEASUREMENT).unwrap();
+    characteristic.set_flags(flags.iter().map(|flag| flag.to_string()).collect()).unwrap();
+    characteristic.set_value(vec![]).unwrap();
+    MockCharacteristic { adapter, device, service, characteristic }
+}
+
+pub fn mock_descriptor(characteristic: &BluetoothGATTCharacteristic, flags: &[&str]) -> BluetoothGATTDescriptor {
+    let descriptor =
+        BluetoothGATTDescriptor::create_mock_descriptor(characteristic.clone(), "descriptor".to_owned()).unwrap();
+    descriptor.set_uuid(CLIENT_CHARACTERISTIC_CONFIGURATION).unwrap();
+    descriptor.set_flags(flags.iter().map(|flag| flag.to_string()).collect()).unwrap();
+    descriptor.set_value(vec![]).unwrap();
+    descriptor
+}
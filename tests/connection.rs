@@ -0,0 +1,105 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, MockDevice};
+use device::backend::{BluetoothDeviceBackend, BluetoothGATTServiceBackend};
+use device::bluetooth::{BluetoothDevice, BluetoothError, BluetoothUuid, ConnectionPriority, DeviceId, Phy};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A backend which leaves the connection parameters and the PHY to their defaults, as the
+/// platform backends but the mock do.
+#[derive(Debug)]
+struct PlatformDevice;
+
+impl BluetoothDeviceBackend for PlatformDevice {
+    fn get_id(&self) -> String {
+        "device".to_owned()
+    }
+
+    fn get_address(&self) -> Result<String, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_name(&self) -> Result<String, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_icon(&self) -> Result<String, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_class(&self) -> Result<u32, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_appearance(&self) -> Result<u16, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_uuids(&self) -> Result<Vec<BluetoothUuid>, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn is_paired(&self) -> Result<bool, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn is_connected(&self) -> Result<bool, BluetoothError> { Ok(true) }
+    fn is_trusted(&self) -> Result<bool, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn is_blocked(&self) -> Result<bool, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_alias(&self) -> Result<String, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn is_legacy_pairing(&self) -> Result<bool, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_modalias(&self) -> Result<DeviceId, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_rssi(&self) -> Result<i16, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_tx_power(&self) -> Result<i16, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_service_data(&self) -> Result<HashMap<BluetoothUuid, Vec<u8>>, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn get_gatt_services(&self) -> Result<Vec<Arc<dyn BluetoothGATTServiceBackend>>, BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn connect(&self) -> Result<(), BluetoothError> { Ok(()) }
+    fn disconnect(&self) -> Result<(), BluetoothError> { Ok(()) }
+    fn connect_profile(&self, _uuid: BluetoothUuid) -> Result<(), BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn disconnect_profile(&self, _uuid: BluetoothUuid) -> Result<(), BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn pair(&self) -> Result<(), BluetoothError> { Err(BluetoothError::NotSupported) }
+    fn cancel_pairing(&self) -> Result<(), BluetoothError> { Err(BluetoothError::NotSupported) }
+}
+
+#[test]
+fn the_connection_needs_a_connected_device() {
+    let MockDevice { device, .. } = mock_device();
+    assert_eq!(device.request_connection_priority(ConnectionPriority::LowLatency), Err(BluetoothError::NotConnected));
+    assert_eq!(device.get_connection_parameters(), Err(BluetoothError::NotConnected));
+    assert_eq!(device.set_preferred_phy(Phy::Le2M), Err(BluetoothError::NotConnected));
+    assert_eq!(device.get_phy(), Err(BluetoothError::NotConnected));
+}
+
+#[test]
+fn a_connection_takes_the_parameters_of_its_priority() {
+    let MockDevice { device, .. } = mock_device();
+    device.connect().unwrap();
+    assert_eq!(device.get_connection_parameters(), Ok(ConnectionPriority::Balanced.parameters()));
+
+    for priority in [ConnectionPriority::LowLatency, ConnectionPriority::LowPower] {
+        device.request_connection_priority(priority).unwrap();
+        assert_eq!(device.get_connection_parameters(), Ok(priority.parameters()));
+    }
+}
+
+#[test]
+fn the_preferred_phy_is_used() {
+    let MockDevice { device, .. } = mock_device();
+    device.connect().unwrap();
+    assert_eq!(device.get_phy(), Ok(Phy::Le1M));
+
+    for phy in [Phy::Le2M, Phy::LeCoded, Phy::Le1M] {
+        device.set_preferred_phy(phy).unwrap();
+        assert_eq!(device.get_phy(), Ok(phy));
+    }
+}
+
+#[test]
+fn a_new_connection_starts_over_from_the_defaults() {
+    let MockDevice { device, .. } = mock_device();
+    device.connect().unwrap();
+    device.request_connection_priority(ConnectionPriority::LowPower).unwrap();
+    device.set_preferred_phy(Phy::LeCoded).unwrap();
+
+    device.disconnect().unwrap();
+    device.connect().unwrap();
+    assert_eq!(device.get_connection_parameters(), Ok(ConnectionPriority::Balanced.parameters()));
+    assert_eq!(device.get_phy(), Ok(Phy::Le1M));
+}
+
+#[test]
+fn other_backends_do_not_support_the_connection_settings() {
+    let device = BluetoothDevice::from_backend(Arc::new(PlatformDevice));
+    device.connect().unwrap();
+    assert_eq!(device.request_connection_priority(ConnectionPriority::LowLatency), Err(BluetoothError::NotSupported));
+    assert_eq!(device.get_connection_parameters(), Err(BluetoothError::NotSupported));
+    assert_eq!(device.set_preferred_phy(Phy::Le2M), Err(BluetoothError::NotSupported));
+    assert_eq!(device.get_phy(), Err(BluetoothError::NotSupported));
+}
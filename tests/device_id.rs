@@ -0,0 +1,42 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, MockDevice};
+use device::bluetooth::{BluetoothError, DeviceId, VendorIdSource};
+
+#[test]
+fn an_unset_device_id_is_not_supported() {
+    let MockDevice { adapter, device } = mock_device();
+    assert_eq!(adapter.get_modalias(), Err(BluetoothError::NotSupported));
+    assert_eq!(adapter.get_vendor_id(), Err(BluetoothError::NotSupported));
+    assert_eq!(device.get_modalias(), Err(BluetoothError::NotSupported));
+    assert_eq!(device.get_vendor_id_source(), Err(BluetoothError::NotSupported));
+    assert_eq!(device.get_product_id(), Err(BluetoothError::NotSupported));
+    assert_eq!(device.get_device_id(), Err(BluetoothError::NotSupported));
+}
+
+#[test]
+fn the_device_id_round_trips() {
+    let MockDevice { adapter, device } = mock_device();
+    let adapter_id = DeviceId::new(VendorIdSource::Usb, 0x1D6B, 0x0246, 0x0537);
+    let device_id = DeviceId::new(VendorIdSource::Bluetooth, 0x004C, 0x2002, 0x0A01);
+    adapter.set_modalias(adapter_id).unwrap();
+    device.set_modalias(device_id).unwrap();
+
+    assert_eq!(adapter.get_modalias(), Ok(adapter_id));
+    assert_eq!(adapter.get_vendor_id_source(), Ok(VendorIdSource::Usb));
+    assert_eq!(adapter.get_vendor_id(), Ok(0x1D6B));
+    assert_eq!(adapter.get_product_id(), Ok(0x0246));
+    assert_eq!(adapter.get_device_id(), Ok(0x0537));
+
+    assert_eq!(device.get_modalias(), Ok(device_id));
+    assert_eq!(device.get_modalias().unwrap().to_string(), "bluetooth:v004Cp2002d0A01");
+    assert_eq!(device.get_vendor_id(), Ok(0x004C));
+    assert_eq!(device.get_product_id(), Ok(0x2002));
+    assert_eq!(device.get_device_id(), Ok(0x0A01));
+}
@@ -0,0 +1,202 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, HEART_RATE_SERVICE};
+use device::adapter::BluetoothAdapter;
+use device::discovery::{DiscoveryFilter, DiscoveryTransport};
+use device::uuid::BluetoothUuid;
+use std::collections::HashMap;
+
+const BATTERY_SERVICE: BluetoothUuid = BluetoothUuid::from_u16(0x180F);
+
+#[test]
+fn the_default_filter_matches_every_device() {
+    let mock = mock_device();
+    assert!(DiscoveryFilter::new().matches(&mock.device));
+}
+
+#[test]
+fn service_uuids_match_advertised_services_or_service_data() {
+    let mock = mock_device();
+    let filter = DiscoveryFilter { service_uuids: vec![HEART_RATE_SERVICE], ..DiscoveryFilter::default() };
+    assert!(!filter.matches(&mock.device));
+
+    mock.device.set_uuids(vec![BATTERY_SERVICE]).unwrap();
+    assert!(!filter.matches(&mock.device));
+    mock.device.set_uuids(vec![BATTERY_SERVICE, HEART_RATE_SERVICE]).unwrap();
+    assert!(filter.matches(&mock.device));
+
+    mock.device.set_uuids(vec![]).unwrap();
+    let mut service_data = HashMap::new();
+    service_data.insert(HEART_RATE_SERVICE, vec![1, 2]);
+    mock.device.set_service_data(service_data).unwrap();
+    assert!(filter.matches(&mock.device));
+}
+
+#[test]
+fn rssi_is_a_lower_bound() {
+    let mock = mock_device();
+    let filter = DiscoveryFilter { rssi: Some(-70), ..DiscoveryFilter::default() };
+    mock.device.set_rssi(-71).unwrap();
+    assert!(!filter.matches(&mock.device));
+    mock.device.set_rssi(-70).unwrap();
+    assert!(filter.matches(&mock.device));
+    mock.device.set_rssi(-40).unwrap();
+    assert!(filter.matches(&mock.device));
+}
+
+#[test]
+fn pathloss_is_an_upper_bound_and_needs_the_tx_power() {
+    let mock = mock_device();
+    let filter = DiscoveryFilter { pathloss: Some(60), ..DiscoveryFilter::default() };
+    mock.device.set_rssi(-50).unwrap();
+    mock.device.set_tx_power(10).unwrap();
+    assert!(filter.matches(&mock.device));
+    mock.device.set_tx_power(11).unwrap();
+    assert!(!filter.matches(&mock.device));
+
+    let without_tx_power = mock.adapter.create_mock_device("without tx power".to_owned()).unwrap();
+    without_tx_power.set_rssi(-50).unwrap();
+    assert!(!filter.matches(&without_tx_power));
+}
+
+#[test]
+fn name_prefix_matches_the_start_of_the_name() {
+    let mock = mock_device();
+    let filter = DiscoveryFilter { name_prefix: Some("Heart".to_owned()), ..DiscoveryFilter::default() };
+    assert!(!filter.matches(&mock.device));
+    mock.device.set_name(Some("Heart Rate Sensor".to_owned())).unwrap();
+    assert!(filter.matches(&mock.device));
+    mock.device.set_name(Some("My Heart Rate Sensor".to_owned())).unwrap();
+    assert!(!filter.matches(&mock.device));
+}
+
+#[test]
+fn every_criterion_must_match() {
+    let mock = mock_device();
+    mock.device.set_uuids(vec![HEART_RATE_SERVICE]).unwrap();
+    mock.device.set_rssi(-80).unwrap();
+    let filter = DiscoveryFilter {
+        service_uuids: vec![HEART_RATE_SERVICE],
+        rssi: Some(-70),
+        ..DiscoveryFilter::default()
+    };
+    assert!(!filter.matches(&mock.device));
+    mock.device.set_rssi(-60).unwrap();
+    assert!(filter.matches(&mock.device));
+}
+
+#[test]
+fn session_devices_pass_the_session_filter() {
+    let mock = mock_device();
+    mock.device.set_rssi(-80).unwrap();
+    let near = mock.adapter.create_mock_device("near".to_owned()).unwrap();
+    near.set_rssi(-40).unwrap();
+    let filter = DiscoveryFilter { rssi: Some(-60), ..DiscoveryFilter::default() };
+    let session = mock.adapter.create_filtered_discovery_session(filter).unwrap();
+    let ids: Vec<String> = session.get_devices().unwrap().iter().map(|device| device.get_id()).collect();
+    assert_eq!(ids, vec!["near".to_owned()]);
+}
+
+#[test]
+fn the_mock_scans_with_the_filter_of_the_started_session() {
+    let mock = mock_device();
+    let filter = DiscoveryFilter {
+        service_uuids: vec![HEART_RATE_SERVICE],
+        transport: DiscoveryTransport::Le,
+        ..DiscoveryFilter::default()
+    };
+    let session = mock.adapter.create_filtered_discovery_session(filter.clone()).unwrap();
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), None);
+    session.start_discovery().unwrap();
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), Some(filter));
+    session.stop_discovery().unwrap();
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), None);
+}
+
+#[test]
+fn the_scan_lasts_while_a_session_is_started() {
+    let mock = mock_device();
+    let first = mock.adapter.create_discovery_session().unwrap();
+    let second = mock.adapter.create_discovery_session().unwrap();
+    first.start_discovery().unwrap();
+    second.start_discovery().unwrap();
+    assert!(first.is_active() && second.is_active());
+
+    // Only stopping the last started session reaches the adapter.
+    mock.adapter.set_can_stop_discovery(false).unwrap();
+    first.stop_discovery().unwrap();
+    assert!(!first.is_active());
+    assert!(second.stop_discovery().is_err());
+    assert!(second.is_active());
+
+    mock.adapter.set_can_stop_discovery(true).unwrap();
+    second.stop_discovery().unwrap();
+    assert!(!second.is_active());
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), None);
+}
+
+#[test]
+fn dropping_the_last_started_session_stops_the_scan() {
+    let mock = mock_device();
+    let session = mock.adapter.create_discovery_session().unwrap();
+    session.start_discovery().unwrap();
+    assert!(mock.adapter.get_discovery_filter().unwrap().is_some());
+    drop(session);
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), None);
+}
+
+#[test]
+fn handles_on_the_same_adapter_share_the_sessions() {
+    let mock = mock_device();
+    let other_handle = BluetoothAdapter::list_mock().unwrap().pop().unwrap();
+    let first = mock.adapter.create_discovery_session().unwrap();
+    let second = other_handle.create_discovery_session().unwrap();
+    first.start_discovery().unwrap();
+    second.start_discovery().unwrap();
+    drop(first);
+    assert!(other_handle.get_discovery_filter().unwrap().is_some());
+    drop(second);
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), None);
+}
+
+#[test]
+fn adapters_do_not_share_sessions() {
+    let mock = mock_device();
+    let other = BluetoothAdapter::new_mock().unwrap();
+    let session = mock.adapter.create_discovery_session().unwrap();
+    session.start_discovery().unwrap();
+    assert_eq!(other.get_discovery_filter().unwrap(), None);
+}
+
+#[test]
+fn the_scan_uses_the_union_of_the_started_filters() {
+    let mock = mock_device();
+    let heart_rate = DiscoveryFilter {
+        service_uuids: vec![HEART_RATE_SERVICE],
+        rssi: Some(-60),
+        ..DiscoveryFilter::default()
+    };
+    let battery = DiscoveryFilter {
+        service_uuids: vec![BATTERY_SERVICE],
+        rssi: Some(-80),
+        duplicate_data: true,
+        ..DiscoveryFilter::default()
+    };
+    let first = mock.adapter.create_filtered_discovery_session(heart_rate.clone()).unwrap();
+    let second = mock.adapter.create_filtered_discovery_session(battery).unwrap();
+    first.start_discovery().unwrap();
+    second.start_discovery().unwrap();
+    let merged = mock.adapter.get_discovery_filter().unwrap().unwrap();
+    assert_eq!(merged.service_uuids, vec![HEART_RATE_SERVICE, BATTERY_SERVICE]);
+    assert_eq!(merged.rssi, Some(-80));
+    assert!(merged.duplicate_data);
+
+    second.stop_discovery().unwrap();
+    assert_eq!(mock.adapter.get_discovery_filter().unwrap(), Some(heart_rate));
+}
@@ -0,0 +1,72 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::mock_device;
+use device::event::AdapterEvent;
+
+#[test]
+fn creating_a_device_reports_its_discovery_once() {
+    let mock = mock_device();
+    let events = mock.adapter.subscribe_events().unwrap();
+    mock.adapter.create_mock_device("other".to_owned()).unwrap();
+    mock.adapter.create_mock_device("other".to_owned()).unwrap();
+    assert_eq!(events.try_recv(), Some(AdapterEvent::DeviceDiscovered("other".to_owned())));
+    assert_eq!(events.try_recv(), None);
+}
+
+#[test]
+fn connections_are_reported_on_transitions_only() {
+    let mock = mock_device();
+    let events = mock.adapter.subscribe_events().unwrap();
+    let id = mock.device.get_id();
+
+    mock.device.connect().unwrap();
+    mock.device.connect().unwrap();
+    assert_eq!(events.try_recv(), Some(AdapterEvent::Connected(id.clone())));
+    assert_eq!(events.try_recv(), None);
+
+    mock.device.disconnect().unwrap();
+    assert!(mock.device.disconnect().is_err());
+    assert_eq!(events.try_recv(), Some(AdapterEvent::Disconnected(id.clone())));
+    assert_eq!(events.try_recv(), None);
+
+    mock.device.set_connected(true).unwrap();
+    mock.device.set_connected(false).unwrap();
+    mock.device.set_connected(false).unwrap();
+    assert_eq!(events.try_recv(), Some(AdapterEvent::Connected(id.clone())));
+    assert_eq!(events.try_recv(), Some(AdapterEvent::Disconnected(id)));
+    assert_eq!(events.try_recv(), None);
+}
+
+#[test]
+fn every_stream_receives_the_events() {
+    let mock = mock_device();
+    let first = mock.adapter.subscribe_events().unwrap();
+    drop(mock.adapter.subscribe_events().unwrap());
+    let second = mock.adapter.subscribe_events().unwrap();
+    mock.device.connect().unwrap();
+    let connected = AdapterEvent::Connected(mock.device.get_id());
+    assert_eq!(first.try_recv(), Some(connected.clone()));
+    assert_eq!(second.try_recv(), Some(connected));
+}
+
+#[test]
+fn mtu_changes_are_reported() {
+    let mock = mock_device();
+    let id = mock.device.get_id();
+    mock.device.connect().unwrap();
+    let events = mock.adapter.subscribe_events().unwrap();
+
+    assert_eq!(mock.device.request_mtu(247), Ok(247));
+    assert_eq!(events.try_recv(), Some(AdapterEvent::MtuChanged(id.clone(), 247)));
+
+    mock.device.set_mtu(185).unwrap();
+    assert_eq!(events.try_recv(), Some(AdapterEvent::MtuChanged(id.clone(), 185)));
+    mock.device.set_mtu(185).unwrap();
+    assert_eq!(events.try_recv(), None);
+}
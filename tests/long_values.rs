@@ -0,0 +1,122 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_characteristic, mock_descriptor, MockCharacteristic};
+use device::bluetooth::{AttError, BluetoothError, OperationTimeouts, WriteType};
+use std::time::Duration;
+
+fn value(length: usize) -> Vec<u8> {
+    (0..length).map(|byte| byte as u8).collect()
+}
+
+#[test]
+fn long_characteristic_values_are_written_and_read_in_full() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "write"]);
+    characteristic.write_value(value(100)).unwrap();
+    assert_eq!(characteristic.get_value(), Ok(value(100)));
+    assert_eq!(characteristic.read_value(), Ok(value(100)));
+    assert_eq!(characteristic.read_value_async().wait(None), Ok(value(100)));
+
+    characteristic.write_value(value(3)).unwrap();
+    assert_eq!(characteristic.read_value(), Ok(value(3)));
+}
+
+#[test]
+fn long_values_are_read_and_written_from_an_offset() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "write"]);
+    characteristic.write_value(value(10)).unwrap();
+    characteristic.write_value_at(10, value(60)).unwrap();
+    let mut expected = value(10);
+    expected.extend(value(60));
+    assert_eq!(characteristic.read_value(), Ok(expected.clone()));
+    assert_eq!(characteristic.read_value_at(5), Ok(expected[5..].to_vec()));
+    assert_eq!(characteristic.read_value_at_async(5).wait(None), Ok(expected[5..].to_vec()));
+    assert_eq!(characteristic.read_value_at(70), Ok(vec![]));
+    assert_eq!(characteristic.read_value_at(71), Err(BluetoothError::GattOperationFailed(AttError::InvalidOffset)));
+}
+
+#[test]
+fn long_values_are_split_with_a_timeout_too() {
+    let MockCharacteristic { adapter, characteristic, .. } = mock_characteristic(&["read", "write"]);
+    adapter.set_operation_timeouts(OperationTimeouts { connect: None, gatt: Some(Duration::from_secs(5)) });
+    characteristic.write_value(value(100)).unwrap();
+    characteristic.write_value_at(50, value(100)).unwrap();
+    let mut expected = value(50);
+    expected.extend(value(100));
+    assert_eq!(characteristic.read_value(), Ok(expected.clone()));
+    assert_eq!(characteristic.read_value_at(30), Ok(expected[30..].to_vec()));
+}
+
+#[test]
+fn long_values_are_written_and_read_asynchronously_too() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "write"]);
+    assert_eq!(characteristic.write_value_async(value(100)).wait(None), Ok(()));
+    assert_eq!(characteristic.write_value_at_async(100, value(50)).wait(None), Ok(()));
+    let mut expected = value(100);
+    expected.extend(value(50));
+    assert_eq!(characteristic.read_value_async().wait(None), Ok(expected.clone()));
+    assert_eq!(characteristic.read_value_at_async(30).wait(None), Ok(expected[30..].to_vec()));
+}
+
+#[test]
+fn values_cannot_outgrow_the_attribute() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "write"]);
+    characteristic.set_max_value_length(Some(50)).unwrap();
+    let too_long = Err(BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength));
+    assert_eq!(characteristic.write_value(value(51)), too_long);
+    assert_eq!(characteristic.write_value(value(50)), Ok(()));
+    assert_eq!(characteristic.write_value_at(40, value(11)), too_long);
+    assert_eq!(characteristic.write_value_at(40, value(10)), Ok(()));
+
+    characteristic.set_max_value_length(None).unwrap();
+    assert_eq!(characteristic.write_value(value(512)), Ok(()));
+    assert_eq!(characteristic.read_value(), Ok(value(512)));
+    assert_eq!(characteristic.write_value(value(513)), too_long);
+}
+
+#[test]
+fn a_larger_mtu_takes_fewer_requests() {
+    let MockCharacteristic { device, characteristic, .. } = mock_characteristic(&["read", "write", "write-without-response"]);
+    let too_long_for_a_request = Err(BluetoothError::GattOperationFailed(AttError::InvalidPdu));
+    assert_eq!(characteristic.write_value_with_type(value(21), WriteType::WithoutResponse), too_long_for_a_request);
+    assert_eq!(characteristic.write_value_with_type(value(20), WriteType::WithoutResponse), Ok(()));
+
+    // With the default MTU, a Write and 16 Prepare Write requests, then 14 Read requests.
+    let count = characteristic.get_request_count().unwrap();
+    characteristic.write_value(value(300)).unwrap();
+    assert_eq!(characteristic.read_value(), Ok(value(300)));
+    assert_eq!(characteristic.get_request_count(), Ok(count + 31));
+
+    device.connect().unwrap();
+    assert_eq!(device.request_mtu(247), Ok(247));
+    assert_eq!(characteristic.write_value_with_type(value(244), WriteType::WithoutResponse), Ok(()));
+    // A Write and a Prepare Write request, then 2 Read requests.
+    let count = characteristic.get_request_count().unwrap();
+    characteristic.write_value(value(300)).unwrap();
+    assert_eq!(characteristic.read_value(), Ok(value(300)));
+    assert_eq!(characteristic.get_request_count(), Ok(count + 4));
+}
+
+#[test]
+fn long_descriptor_values_are_written_and_read_in_full() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read"]);
+    let descriptor = mock_descriptor(&characteristic, &["read", "write"]);
+    descriptor.write_value(value(80)).unwrap();
+    assert_eq!(descriptor.read_value(), Ok(value(80)));
+    descriptor.write_value_at(80, value(40)).unwrap();
+    let mut expected = value(80);
+    expected.extend(value(40));
+    assert_eq!(descriptor.read_value_at(0), Ok(expected.clone()));
+    assert_eq!(descriptor.read_value_async().wait(None), Ok(expected));
+
+    descriptor.set_max_value_length(Some(100)).unwrap();
+    assert_eq!(descriptor.write_value(value(101)),
+               Err(BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength)));
+    assert_eq!(descriptor.write_value_async(value(100)).wait(None), Ok(()));
+    assert_eq!(descriptor.read_value(), Ok(value(100)));
+}
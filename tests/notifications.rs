@@ -0,0 +1,75 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::mock_characteristic;
+use std::time::Duration;
+
+const WAIT: Duration = Duration::from_secs(1);
+
+#[test]
+fn subscriptions_receive_the_notified_values() {
+    let mock = mock_characteristic(&["notify"]);
+    mock.device.connect().unwrap();
+    let first = mock.characteristic.subscribe().unwrap();
+    let second = mock.characteristic.subscribe().unwrap();
+    assert!(mock.characteristic.is_notifying().unwrap());
+
+    mock.characteristic.notify_value(vec![0x06, 0x48]).unwrap();
+    mock.characteristic.notify_value(vec![0x06, 0x49]).unwrap();
+    for subscription in &[first, second] {
+        assert_eq!(subscription.recv_timeout(WAIT).unwrap().value, vec![0x06, 0x48]);
+        assert_eq!(subscription.recv_timeout(WAIT).unwrap().value, vec![0x06, 0x49]);
+        assert!(subscription.try_recv().is_none());
+    }
+    assert_eq!(mock.characteristic.get_value().unwrap(), vec![0x06, 0x49]);
+}
+
+#[test]
+fn values_are_not_delivered_unless_notifying() {
+    let mock = mock_characteristic(&["notify"]);
+    mock.device.connect().unwrap();
+    let subscription = mock.characteristic.subscribe().unwrap();
+    mock.characteristic.set_notifying(true).unwrap();
+    mock.characteristic.notify_value(vec![1]).unwrap();
+    assert_eq!(subscription.recv_timeout(WAIT).unwrap().value, vec![1]);
+
+    mock.characteristic.set_notifying(false).unwrap();
+    mock.characteristic.notify_value(vec![2]).unwrap();
+    assert_eq!(subscription.recv_timeout(WAIT), None);
+    assert_eq!(mock.characteristic.get_value().unwrap(), vec![2]);
+}
+
+#[test]
+fn stop_notify_ends_the_subscriptions() {
+    let mock = mock_characteristic(&["notify"]);
+    mock.device.connect().unwrap();
+    let subscription = mock.characteristic.subscribe().unwrap();
+    mock.characteristic.stop_notify().unwrap();
+    assert!(!mock.characteristic.is_notifying().unwrap());
+    assert_eq!(subscription.recv(), None);
+    assert_eq!(subscription.count(), 0);
+}
+
+#[test]
+fn disconnecting_ends_the_subscriptions() {
+    let mock = mock_characteristic(&["notify"]);
+    mock.device.connect().unwrap();
+    let subscription = mock.characteristic.subscribe().unwrap();
+    mock.device.disconnect().unwrap();
+    assert_eq!(subscription.recv(), None);
+}
+
+#[test]
+fn dropped_subscriptions_do_not_stop_the_others() {
+    let mock = mock_characteristic(&["notify"]);
+    mock.device.connect().unwrap();
+    drop(mock.characteristic.subscribe().unwrap());
+    let subscription = mock.characteristic.subscribe().unwrap();
+    mock.characteristic.notify_value(vec![3]).unwrap();
+    assert_eq!(subscription.recv_timeout(WAIT).unwrap().value, vec![3]);
+}
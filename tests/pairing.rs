@@ -0,0 +1,193 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, MockDevice};
+use device::bluetooth::{BluetoothError, IoCapability, PairingAgent, PairingMethod};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+
+const PIN_CODE: &str = "0000";
+const PASSKEY: u32 = 123456;
+
+/// Answers with `PIN_CODE` and `PASSKEY`, confirms if `confirms`, and records the requests.
+struct Agent {
+    confirms: bool,
+    requests: Mutex<Vec<String>>,
+}
+
+impl Agent {
+    fn new(confirms: bool) -> Arc<Agent> {
+        Arc::new(Agent { confirms, requests: Mutex::new(vec![]) })
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn record(&self, request: String) {
+        self.requests.lock().unwrap().push(request);
+    }
+}
+
+impl PairingAgent for Agent {
+    fn request_pin_code(&self, device: &str) -> Option<String> {
+        self.record(format!("pin code {}", device));
+        Some(PIN_CODE.to_owned())
+    }
+
+    fn request_passkey(&self, device: &str) -> Option<u32> {
+        self.record(format!("passkey {}", device));
+        Some(PASSKEY)
+    }
+
+    fn display_passkey(&self, device: &str, passkey: u32, _entered: u16) {
+        self.record(format!("display {} {}", device, passkey));
+    }
+
+    fn request_confirmation(&self, device: &str, passkey: u32) -> bool {
+        self.record(format!("confirm {} {}", device, passkey));
+        self.confirms
+    }
+}
+
+fn register(mock: &MockDevice, agent: &Arc<Agent>, capability: IoCapability) {
+    mock.adapter.register_pairing_agent(agent.clone(), capability).unwrap();
+}
+
+#[test]
+fn just_works_needs_no_agent() {
+    let mock = mock_device();
+    assert_eq!(mock.device.pair(), Ok(()));
+    assert_eq!(mock.device.is_paired(), Ok(true));
+}
+
+#[test]
+fn pin_codes_are_asked_to_the_agent() {
+    let mock = mock_device();
+    let id = mock.device.get_id();
+    mock.device.set_pairing_method(PairingMethod::PinCode(PIN_CODE.to_owned())).unwrap();
+    assert_eq!(mock.device.pair(), Err(BluetoothError::NotAuthorized));
+
+    let agent = Agent::new(true);
+    register(&mock, &agent, IoCapability::KeyboardDisplay);
+    assert_eq!(mock.device.pair(), Ok(()));
+    assert_eq!(agent.requests(), vec![format!("pin code {}", id)]);
+
+    mock.device.unpair().unwrap();
+    mock.device.set_pairing_method(PairingMethod::PinCode("1234".to_owned())).unwrap();
+    assert_eq!(mock.device.pair(), Err(BluetoothError::NotAuthorized));
+    assert_eq!(mock.device.is_paired(), Ok(false));
+}
+
+#[test]
+fn passkeys_follow_the_io_capability() {
+    let mock = mock_device();
+    let id = mock.device.get_id();
+    let agent = Agent::new(true);
+    mock.device.set_pairing_method(PairingMethod::PasskeyEntry(PASSKEY)).unwrap();
+    register(&mock, &agent, IoCapability::KeyboardOnly);
+    assert_eq!(mock.device.pair(), Ok(()));
+    assert_eq!(agent.requests(), vec![format!("passkey {}", id)]);
+
+    // Without a keyboard, the pairing falls back to Just Works.
+    mock.device.unpair().unwrap();
+    register(&mock, &agent, IoCapability::DisplayOnly);
+    assert_eq!(mock.device.pair(), Ok(()));
+    assert_eq!(agent.requests().len(), 1);
+
+    mock.device.unpair().unwrap();
+    mock.device.set_pairing_method(PairingMethod::PasskeyDisplay(PASSKEY)).unwrap();
+    assert_eq!(mock.device.pair(), Ok(()));
+    assert_eq!(agent.requests()[1], format!("display {} {}", id, PASSKEY));
+}
+
+#[test]
+fn numeric_comparisons_need_a_confirmation() {
+    let mock = mock_device();
+    mock.device.set_pairing_method(PairingMethod::NumericComparison(PASSKEY)).unwrap();
+    register(&mock, &Agent::new(false), IoCapability::DisplayYesNo);
+    assert_eq!(mock.device.pair(), Err(BluetoothError::NotAuthorized));
+
+    let agent = Agent::new(true);
+    register(&mock, &agent, IoCapability::DisplayYesNo);
+    assert_eq!(mock.device.pair(), Ok(()));
+    assert_eq!(agent.requests(), vec![format!("confirm {} {}", mock.device.get_id(), PASSKEY)]);
+}
+
+#[test]
+fn unregistered_agents_are_not_asked() {
+    let mock = mock_device();
+    let agent = Agent::new(true);
+    register(&mock, &agent, IoCapability::KeyboardDisplay);
+    mock.adapter.unregister_pairing_agent().unwrap();
+    mock.device.set_pairing_method(PairingMethod::PinCode(PIN_CODE.to_owned())).unwrap();
+    assert_eq!(mock.device.pair(), Err(BluetoothError::NotAuthorized));
+    assert!(agent.requests().is_empty());
+}
+
+#[test]
+fn mock_agents_answer_for_their_adapter_only() {
+    let mock = mock_device();
+    let other = mock_device();
+    let agent = Agent::new(true);
+    register(&other, &agent, IoCapability::KeyboardDisplay);
+    mock.device.set_pairing_method(PairingMethod::PinCode(PIN_CODE.to_owned())).unwrap();
+    assert_eq!(mock.device.pair(), Err(BluetoothError::NotAuthorized));
+    assert!(agent.requests().is_empty());
+}
+
+#[test]
+fn pairing_errors_and_blocked_devices_fail_the_pairing() {
+    let mock = mock_device();
+    mock.device.set_pairing_error(Some(BluetoothError::ConnectionFailed)).unwrap();
+    assert_eq!(mock.device.pair(), Err(BluetoothError::ConnectionFailed));
+    mock.device.set_pairing_error(None).unwrap();
+
+    mock.device.set_blocked(true).unwrap();
+    assert_eq!(mock.device.pair(), Err(BluetoothError::NotAuthorized));
+    mock.device.set_blocked(false).unwrap();
+    assert_eq!(mock.device.pair(), Ok(()));
+}
+
+/// Waits for the pairing to be cancelled before rejecting the PIN code request.
+struct WaitingAgent {
+    waiting: Mutex<Sender<()>>,
+    cancelled: Mutex<bool>,
+    cancellation: Condvar,
+}
+
+impl PairingAgent for WaitingAgent {
+    fn request_pin_code(&self, _device: &str) -> Option<String> {
+        self.waiting.lock().unwrap().send(()).unwrap();
+        let mut cancelled = self.cancelled.lock().unwrap();
+        while !*cancelled {
+            cancelled = self.cancellation.wait(cancelled).unwrap();
+        }
+        None
+    }
+
+    fn cancel(&self) {
+        *self.cancelled.lock().unwrap() = true;
+        self.cancellation.notify_all();
+    }
+}
+
+#[test]
+fn cancelling_a_pairing_cancels_the_agent_request() {
+    let mock = mock_device();
+    let (waiting, waited) = channel();
+    let agent = WaitingAgent { waiting: Mutex::new(waiting), cancelled: Mutex::new(false), cancellation: Condvar::new() };
+    mock.adapter.register_pairing_agent(Arc::new(agent), IoCapability::KeyboardDisplay).unwrap();
+    mock.device.set_pairing_method(PairingMethod::PinCode(PIN_CODE.to_owned())).unwrap();
+
+    let pairing = mock.device.pair_async();
+    waited.recv().unwrap();
+    mock.device.cancel_pairing().unwrap();
+    assert_eq!(pairing.wait(None), Err(BluetoothError::Cancelled));
+    assert_eq!(mock.device.is_paired(), Ok(false));
+}
@@ -0,0 +1,36 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, MockDevice};
+use device::appearance::Appearance;
+use device::class::{ClassOfDevice, MajorDeviceClass, MajorServiceClass};
+
+#[test]
+fn the_appearance_round_trips() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_appearance(Appearance::from(0x0341)).unwrap();
+    let appearance = device.get_appearance().unwrap();
+    assert_eq!(appearance, Appearance::from(0x0341));
+    assert_eq!(appearance.to_string(), "Heart Rate Sensor: Heart Rate Belt");
+}
+
+#[test]
+fn the_class_of_device_round_trips() {
+    let MockDevice { adapter, device } = mock_device();
+    let phone = ClassOfDevice::new(&[MajorServiceClass::Telephony, MajorServiceClass::Audio], MajorDeviceClass::Phone, 3);
+    device.set_class(phone).unwrap();
+    let class = device.get_class().unwrap();
+    assert_eq!(class, phone);
+    assert_eq!(class.major_device_class(), MajorDeviceClass::Phone);
+    assert!(class.has_service_class(MajorServiceClass::Telephony));
+
+    let computer = ClassOfDevice::new(&[MajorServiceClass::Networking], MajorDeviceClass::Computer, 1);
+    adapter.set_class(computer).unwrap();
+    assert_eq!(adapter.get_class(), Ok(computer));
+    assert_eq!(device.get_class(), Ok(phone));
+}
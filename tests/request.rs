@@ -0,0 +1,139 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_device, MockDevice, HEART_RATE_SERVICE};
+use device::bluetooth::BluetoothUuid;
+use device::request::{BluetoothDataFilter, BluetoothLEScanFilter, BluetoothManufacturerDataFilter};
+use device::request::{BluetoothServiceDataFilter, RequestDeviceOptions};
+use std::collections::HashMap;
+
+const BATTERY_SERVICE: BluetoothUuid = BluetoothUuid::from_u16(0x180F);
+const COMPANY: u16 = 0x00E0;
+
+// How the data filters compare the prefixes and masks is up to the unit tests of
+// `BluetoothDataFilter`: these only check which device data the filters look at.
+
+fn with_name(name: &str) -> BluetoothLEScanFilter {
+    BluetoothLEScanFilter { name: Some(name.to_owned()), ..BluetoothLEScanFilter::new() }
+}
+
+fn with_services(services: &[BluetoothUuid]) -> BluetoothLEScanFilter {
+    BluetoothLEScanFilter { services: Some(services.to_vec()), ..BluetoothLEScanFilter::new() }
+}
+
+fn with_manufacturer_data(company_identifier: u16, data_prefix: &[u8]) -> BluetoothLEScanFilter {
+    let data = BluetoothDataFilter { data_prefix: data_prefix.to_vec(), mask: None };
+    let filter = BluetoothManufacturerDataFilter { company_identifier, data };
+    BluetoothLEScanFilter { manufacturer_data: Some(vec![filter]), ..BluetoothLEScanFilter::new() }
+}
+
+fn with_service_data(service: BluetoothUuid, data_prefix: &[u8]) -> BluetoothLEScanFilter {
+    let data = BluetoothDataFilter { data_prefix: data_prefix.to_vec(), mask: None };
+    let filter = BluetoothServiceDataFilter { service, data };
+    BluetoothLEScanFilter { service_data: Some(vec![filter]), ..BluetoothLEScanFilter::new() }
+}
+
+fn options(filters: Vec<BluetoothLEScanFilter>, exclusion_filters: Vec<BluetoothLEScanFilter>) -> RequestDeviceOptions {
+    let options = RequestDeviceOptions { filters, exclusion_filters, ..RequestDeviceOptions::new() };
+    options.validate().unwrap();
+    options
+}
+
+#[test]
+fn filters_match_the_name_and_its_prefix() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_name(Some("Heart Rate Monitor".to_owned())).unwrap();
+
+    assert!(with_name("Heart Rate Monitor").matches(&device));
+    assert!(!with_name("Heart Rate").matches(&device));
+    let prefixed = |prefix: &str| BluetoothLEScanFilter { name_prefix: Some(prefix.to_owned()), ..BluetoothLEScanFilter::new() };
+    assert!(prefixed("Heart").matches(&device));
+    assert!(!prefixed("Rate").matches(&device));
+    assert!(!BluetoothLEScanFilter { name_prefix: Some("Heart".to_owned()), ..with_name("Monitor") }.matches(&device));
+}
+
+#[test]
+fn name_filters_do_not_match_unnamed_devices() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_name(None).unwrap();
+    device.set_uuids(vec![HEART_RATE_SERVICE]).unwrap();
+
+    assert!(!with_name("device").matches(&device));
+    assert!(!BluetoothLEScanFilter { name_prefix: Some("d".to_owned()), ..BluetoothLEScanFilter::new() }.matches(&device));
+    assert!(with_services(&[HEART_RATE_SERVICE]).matches(&device));
+}
+
+#[test]
+fn filters_need_every_service() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_uuids(vec![HEART_RATE_SERVICE, BATTERY_SERVICE]).unwrap();
+
+    assert!(with_services(&[HEART_RATE_SERVICE]).matches(&device));
+    assert!(with_services(&[HEART_RATE_SERVICE, BATTERY_SERVICE]).matches(&device));
+    assert!(!with_services(&[HEART_RATE_SERVICE, BluetoothUuid::from_u16(0x1800)]).matches(&device));
+
+    device.set_uuids(vec![]).unwrap();
+    assert!(!with_services(&[HEART_RATE_SERVICE]).matches(&device));
+}
+
+#[test]
+fn filters_match_the_manufacturer_data() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_manufacturer_data(vec![(COMPANY, vec![0x12, 0x34])].into_iter().collect()).unwrap();
+
+    assert!(with_manufacturer_data(COMPANY, &[]).matches(&device));
+    assert!(with_manufacturer_data(COMPANY, &[0x12]).matches(&device));
+    assert!(!with_manufacturer_data(COMPANY, &[0x12, 0x30]).matches(&device));
+    assert!(!with_manufacturer_data(COMPANY + 1, &[]).matches(&device));
+
+    device.set_manufacturer_data(HashMap::new()).unwrap();
+    assert!(!with_manufacturer_data(COMPANY, &[]).matches(&device));
+}
+
+#[test]
+fn filters_match_the_service_data() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_service_data(vec![(HEART_RATE_SERVICE, vec![0xAB])].into_iter().collect()).unwrap();
+
+    assert!(with_service_data(HEART_RATE_SERVICE, &[0xAB]).matches(&device));
+    assert!(!with_service_data(HEART_RATE_SERVICE, &[0xAC]).matches(&device));
+    assert!(!with_service_data(BATTERY_SERVICE, &[]).matches(&device));
+}
+
+#[test]
+fn filters_need_every_member() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_name(Some("device".to_owned())).unwrap();
+    device.set_uuids(vec![HEART_RATE_SERVICE]).unwrap();
+
+    assert!(BluetoothLEScanFilter { services: Some(vec![HEART_RATE_SERVICE]), ..with_name("device") }.matches(&device));
+    assert!(!BluetoothLEScanFilter { services: Some(vec![BATTERY_SERVICE]), ..with_name("device") }.matches(&device));
+    assert!(!BluetoothLEScanFilter { services: Some(vec![HEART_RATE_SERVICE]), ..with_name("other") }.matches(&device));
+}
+
+#[test]
+fn options_match_any_filter_but_no_exclusion_filter() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_name(Some("device".to_owned())).unwrap();
+    device.set_uuids(vec![HEART_RATE_SERVICE]).unwrap();
+
+    assert!(options(vec![with_name("other"), with_name("device")], vec![]).matches(&device));
+    assert!(!options(vec![with_name("other")], vec![]).matches(&device));
+    assert!(options(vec![with_name("device")], vec![with_services(&[BATTERY_SERVICE])]).matches(&device));
+    assert!(!options(vec![with_name("device")], vec![with_name("other"), with_services(&[HEART_RATE_SERVICE])]).matches(&device));
+}
+
+#[test]
+fn options_accepting_all_devices_match_every_device() {
+    let MockDevice { device, .. } = mock_device();
+    device.set_name(None).unwrap();
+
+    let options = RequestDeviceOptions { accept_all_devices: true, ..RequestDeviceOptions::new() };
+    options.validate().unwrap();
+    assert!(options.matches(&device));
+}
@@ -0,0 +1,88 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_characteristic, mock_device, MockCharacteristic, MockDevice};
+use device::adapter::BluetoothAdapter;
+use device::error::BluetoothError;
+use device::future::{CancellationToken, OperationTimeouts};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SHORT: Duration = Duration::from_millis(50);
+
+fn short_timeouts() -> OperationTimeouts {
+    OperationTimeouts { connect: Some(SHORT), gatt: Some(SHORT) }
+}
+
+#[test]
+fn operations_on_a_hung_device_time_out_and_are_abandoned() {
+    let MockDevice { adapter, device } = mock_device();
+    adapter.set_operation_timeouts(short_timeouts());
+    device.set_hung(true).unwrap();
+    assert_eq!(device.connect(), Err(BluetoothError::Timeout));
+
+    device.set_hung(false).unwrap();
+    thread::sleep(SHORT);
+    assert_eq!(device.is_connected(), Ok(false));
+    assert_eq!(device.connect(), Ok(()));
+}
+
+#[test]
+fn gatt_operations_on_a_hung_device_time_out() {
+    let MockCharacteristic { adapter, device, characteristic, .. } = mock_characteristic(&["read"]);
+    adapter.set_operation_timeouts(short_timeouts());
+    device.set_hung(true).unwrap();
+    assert_eq!(characteristic.read_value(), Err(BluetoothError::Timeout));
+    device.set_hung(false).unwrap();
+    assert_eq!(characteristic.read_value(), Ok(vec![]));
+}
+
+#[test]
+fn cancelled_operations_on_a_hung_device_fail() {
+    let MockDevice { adapter: _adapter, device } = mock_device();
+    device.set_hung(true).unwrap();
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    thread::spawn(move || {
+        thread::sleep(SHORT);
+        canceller.cancel();
+    });
+    assert_eq!(device.connect_async().cancellable(&token).wait(None), Err(BluetoothError::Cancelled));
+
+    device.set_hung(false).unwrap();
+    thread::sleep(SHORT);
+    assert_eq!(device.is_connected(), Ok(false));
+}
+
+#[test]
+fn handles_on_an_adapter_share_its_timeouts() {
+    let adapter = BluetoothAdapter::new_mock().unwrap();
+    adapter.set_operation_timeouts(short_timeouts());
+    let other = BluetoothAdapter::list_mock().unwrap().pop().unwrap();
+    assert_eq!(other.get_operation_timeouts(), short_timeouts());
+
+    other.set_operation_timeouts(OperationTimeouts::default());
+    assert_eq!(adapter.get_operation_timeouts(), OperationTimeouts::default());
+}
+
+#[test]
+fn handles_can_override_the_timeouts_of_their_adapter() {
+    let MockDevice { adapter, device } = mock_device();
+    let patient = OperationTimeouts { connect: Some(Duration::from_secs(60)), gatt: None };
+    adapter.set_operation_timeouts(patient);
+    let impatient = device.with_operation_timeouts(short_timeouts());
+    device.set_hung(true).unwrap();
+    let started = Instant::now();
+    assert_eq!(impatient.connect(), Err(BluetoothError::Timeout));
+    assert!(started.elapsed() < Duration::from_secs(60));
+    assert_eq!(adapter.get_operation_timeouts(), patient);
+
+    device.set_hung(false).unwrap();
+    assert_eq!(impatient.connect(), Ok(()));
+    assert_eq!(device.is_connected(), Ok(true));
+}
@@ -0,0 +1,73 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+#![cfg(feature = "bluetooth-test")]
+
+mod common;
+
+use common::{mock_characteristic, MockCharacteristic};
+use device::bluetooth::WriteType;
+use device::error::{AttError, BluetoothError};
+use device::future::OperationTimeouts;
+use std::time::Duration;
+
+#[test]
+fn writes_record_their_write_type() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "write", "write-without-response"]);
+    assert_eq!(characteristic.get_last_write_type(), Ok(None));
+
+    characteristic.write_value_with_type(vec![1], WriteType::WithoutResponse).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(Some(WriteType::WithoutResponse)));
+    assert_eq!(characteristic.read_value(), Ok(vec![1]));
+
+    characteristic.write_value_with_type(vec![2], WriteType::WithResponse).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(Some(WriteType::WithResponse)));
+
+    characteristic.write_value(vec![3]).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(None));
+}
+
+#[test]
+fn writes_with_a_timeout_record_their_write_type() {
+    let MockCharacteristic { adapter, characteristic, .. } = mock_characteristic(&["write", "write-without-response"]);
+    adapter.set_operation_timeouts(OperationTimeouts { connect: None, gatt: Some(Duration::from_secs(5)) });
+    characteristic.write_value_with_type(vec![1], WriteType::WithoutResponse).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(Some(WriteType::WithoutResponse)));
+
+    characteristic.write_value_with_type_async(vec![2], WriteType::WithResponse).wait(None).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(Some(WriteType::WithResponse)));
+}
+
+#[test]
+fn write_types_need_their_flag() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["write"]);
+    assert_eq!(characteristic.write_value_with_type(vec![1], WriteType::Signed), Err(BluetoothError::NotSupported));
+    assert_eq!(characteristic.write_value_with_type_async(vec![1], WriteType::Reliable).wait(None),
+               Err(BluetoothError::NotSupported));
+    assert_eq!(characteristic.get_last_write_type(), Ok(None));
+}
+
+#[test]
+fn reliable_writes_apply_long_values_at_once() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "write", "reliable-write"]);
+    let value: Vec<u8> = (0..100).collect();
+    characteristic.write_value_with_type(value.clone(), WriteType::Reliable).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(Some(WriteType::Reliable)));
+    assert_eq!(characteristic.read_value(), Ok(value.clone()));
+
+    characteristic.set_max_value_length(Some(60)).unwrap();
+    assert_eq!(characteristic.write_value_with_type_async(vec![1; 80], WriteType::Reliable).wait(None),
+               Err(BluetoothError::GattOperationFailed(AttError::InvalidAttributeValueLength)));
+    assert_eq!(characteristic.read_value(), Ok(value));
+}
+
+#[test]
+fn signed_writes_are_write_commands() {
+    let MockCharacteristic { characteristic, .. } = mock_characteristic(&["read", "authenticated-signed-writes"]);
+    characteristic.write_value_with_type(vec![1, 2], WriteType::Signed).unwrap();
+    assert_eq!(characteristic.get_last_write_type(), Ok(Some(WriteType::Signed)));
+    assert_eq!(characteristic.read_value(), Ok(vec![1, 2]));
+    assert_eq!(characteristic.write_value_with_type(vec![0; 21], WriteType::Signed),
+               Err(BluetoothError::GattOperationFailed(AttError::InvalidPdu)));
+}